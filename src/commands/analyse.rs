@@ -1,4 +1,4 @@
-use std::fs::File;
+use std::{fmt::Write, fs::File};
 
 use anstream::stdout;
 use camino::{Utf8Path, Utf8PathBuf};
@@ -52,10 +52,17 @@ impl Analyse {
                 installer.sha_256 = sha_256.clone();
             }
         }
-        let yaml = match analyser.installers.as_slice() {
+        let mut yaml = String::new();
+        if let Some(signer) = &analyser.signer {
+            writeln!(yaml, "# Signed by {signer}")?;
+            if signer.digest_valid == Some(false) {
+                writeln!(yaml, "# The signature's digest does not match the file")?;
+            }
+        }
+        yaml.push_str(&match analyser.installers.as_slice() {
             [installer] => serde_yaml::to_string(installer)?,
             installers => serde_yaml::to_string(installers)?,
-        };
+        });
         let mut lock = stdout().lock();
         print_manifest(&mut lock, &yaml);
         Ok(())
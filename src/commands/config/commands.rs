@@ -0,0 +1,21 @@
+use clap::{Args, Subcommand};
+
+use crate::commands::config::{
+    get::GetConfig, list::ListConfig, set::SetConfig, unset::UnsetConfig, use_profile::UseProfile,
+};
+
+/// View or change the settings in Komac's config file
+#[derive(Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    Get(GetConfig),
+    Set(SetConfig),
+    Unset(UnsetConfig),
+    List(ListConfig),
+    Use(UseProfile),
+}
@@ -0,0 +1,31 @@
+use anstream::println;
+use clap::Parser;
+use color_eyre::eyre::{Result, bail};
+
+use crate::config::{Config, ConfigKey};
+
+/// Print the value of a setting in the selected profile
+#[derive(Parser)]
+pub struct GetConfig {
+    /// The setting to print
+    #[arg(value_enum)]
+    key: ConfigKey,
+}
+
+impl GetConfig {
+    pub fn run(self, profile: Option<&str>) -> Result<()> {
+        let config = Config::load()?;
+        let profile = config.profile_name(profile);
+
+        match config
+            .profiles
+            .get(&profile)
+            .and_then(|settings| settings.get(&self.key))
+        {
+            Some(value) => println!("{value}"),
+            None => bail!("{} is not set in the {profile} profile", self.key),
+        }
+
+        Ok(())
+    }
+}
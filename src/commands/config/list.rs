@@ -0,0 +1,47 @@
+use anstream::println;
+use clap::{Parser, ValueEnum};
+use color_eyre::eyre::Result;
+use owo_colors::OwoColorize;
+
+use crate::config::{Config, ConfigKey};
+
+/// List every profile and its settings
+#[derive(Parser)]
+pub struct ListConfig {
+    /// List the settings that can be set and the environment variables they provide defaults for
+    #[arg(long)]
+    keys: bool,
+}
+
+impl ListConfig {
+    pub fn run(self, profile: Option<&str>) -> Result<()> {
+        if self.keys {
+            for key in ConfigKey::value_variants() {
+                println!("{key} ({})", key.env_var().bright_black());
+            }
+            return Ok(());
+        }
+
+        let path = Config::path()?;
+        let config = Config::load()?;
+        let selected = config.profile_name(profile);
+
+        println!("Config file: {}", path.blue());
+        if config.profiles.is_empty() {
+            println!("No profiles have been set. Create one with `komac config set <KEY> <VALUE>`");
+        }
+        for (name, settings) in &config.profiles {
+            println!();
+            if *name == selected {
+                println!("{} {}", name.bold(), "(selected)".green());
+            } else {
+                println!("{}", name.bold());
+            }
+            for (key, value) in settings {
+                println!("  {key} = {value}");
+            }
+        }
+
+        Ok(())
+    }
+}
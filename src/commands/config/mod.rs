@@ -0,0 +1,6 @@
+pub mod commands;
+pub mod get;
+pub mod list;
+pub mod set;
+pub mod unset;
+pub mod use_profile;
@@ -0,0 +1,42 @@
+use anstream::println;
+use clap::Parser;
+use color_eyre::eyre::Result;
+use owo_colors::OwoColorize;
+
+use crate::config::{Config, ConfigKey};
+
+/// Set a setting in the selected profile, creating the profile if it does not exist
+#[derive(Parser)]
+pub struct SetConfig {
+    /// The setting to change
+    #[arg(value_enum)]
+    key: ConfigKey,
+
+    /// The new value of the setting
+    value: String,
+}
+
+impl SetConfig {
+    pub fn run(self, profile: Option<&str>) -> Result<()> {
+        self.key.validate(&self.value)?;
+
+        let mut config = Config::load()?;
+        let profile = config.profile_name(profile);
+        config
+            .profiles
+            .entry(profile.clone())
+            .or_default()
+            .insert(self.key, self.value);
+        let path = config.save()?;
+
+        println!(
+            "{} set {} in the {} profile of {}",
+            "Successfully".green(),
+            self.key.blue(),
+            profile.blue(),
+            path.blue()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use anstream::println;
+use clap::Parser;
+use color_eyre::eyre::{Result, bail};
+use owo_colors::OwoColorize;
+
+use crate::config::{Config, ConfigKey};
+
+/// Remove a setting from the selected profile
+#[derive(Parser)]
+pub struct UnsetConfig {
+    /// The setting to remove
+    #[arg(value_enum)]
+    key: ConfigKey,
+}
+
+impl UnsetConfig {
+    pub fn run(self, profile: Option<&str>) -> Result<()> {
+        let mut config = Config::load()?;
+        let profile = config.profile_name(profile);
+
+        if config
+            .profiles
+            .get_mut(&profile)
+            .and_then(|settings| settings.remove(&self.key))
+            .is_none()
+        {
+            bail!("{} is not set in the {profile} profile", self.key);
+        }
+        config.save()?;
+
+        println!(
+            "{} removed {} from the {} profile",
+            "Successfully".green(),
+            self.key.blue(),
+            profile.blue()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use anstream::println;
+use clap::Parser;
+use color_eyre::eyre::{Result, bail};
+use owo_colors::OwoColorize;
+
+use crate::config::Config;
+
+/// Set the profile used when `--profile` and `KOMAC_PROFILE` are not given
+#[derive(Parser)]
+pub struct UseProfile {
+    /// The name of the profile
+    name: String,
+}
+
+impl UseProfile {
+    pub fn run(self) -> Result<()> {
+        let mut config = Config::load()?;
+
+        if !config.profiles.contains_key(&self.name) {
+            bail!(
+                "Profile `{}` does not exist. Create it with `komac config set --profile {} <KEY> <VALUE>`",
+                self.name,
+                self.name
+            );
+        }
+
+        config.default_profile = Some(self.name);
+        config.save()?;
+
+        println!(
+            "{} set the default profile to {}",
+            "Successfully".green(),
+            config.default_profile.unwrap_or_default().blue()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use anstream::print;
+use clap::Parser;
+use color_eyre::Result;
+use winget_types::{PackageIdentifier, PackageVersion};
+
+use crate::{
+    credential::handle_token,
+    github::github_client::GitHub,
+    manifests::diff::{ManifestDiff, ManifestSnapshot},
+};
+
+/// Compare the manifests of two versions of a package field by field
+#[derive(Parser)]
+pub struct Diff {
+    /// The package's unique identifier
+    #[arg()]
+    package_identifier: PackageIdentifier,
+
+    /// The version to compare from
+    #[arg()]
+    old_version: PackageVersion,
+
+    /// The version to compare to
+    #[arg()]
+    new_version: PackageVersion,
+
+    /// GitHub personal access token with the `public_repo` scope
+    #[arg(short, long, env = "GITHUB_TOKEN")]
+    token: Option<String>,
+}
+
+impl Diff {
+    pub async fn run(self) -> Result<()> {
+        let token = handle_token(self.token.as_deref()).await?;
+        let github = GitHub::new(&token)?;
+
+        let (old_manifests, new_manifests) = tokio::try_join!(
+            github.get_manifests(&self.package_identifier, &self.old_version),
+            github.get_manifests(&self.package_identifier, &self.new_version),
+        )?;
+
+        print!(
+            "{}",
+            ManifestDiff::new(
+                &ManifestSnapshot::new(&old_manifests),
+                &ManifestSnapshot::new(&new_manifests)
+            )
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use std::fs;
+
+use anstream::println;
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::Parser;
+use color_eyre::eyre::{Result, bail};
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+use thiserror::Error;
+use walkdir::WalkDir;
+
+use crate::manifests::{
+    created_with_header, header_comment, manifest::Manifest, migrate::dropped_fields,
+};
+
+/// Format manifests with the same header, field order, optimisation and line endings as Komac
+#[derive(Parser)]
+#[clap(visible_alias = "format")]
+pub struct Fmt {
+    /// A manifest or a directory of manifests to format
+    #[arg(value_hint = clap::ValueHint::AnyPath)]
+    path: Utf8PathBuf,
+
+    /// Fail if any manifest is not formatted instead of formatting it
+    #[arg(long)]
+    check: bool,
+
+    /// Upgrade the manifests to the latest manifest version
+    #[arg(long)]
+    upgrade: bool,
+}
+
+#[derive(Debug, Error)]
+enum FormatError {
+    #[error(transparent)]
+    Yaml(#[from] serde_yaml::Error),
+    #[error("Formatting would drop or leave incomplete {}", _0.iter().join(", "))]
+    DroppedFields(Vec<String>),
+}
+
+impl Fmt {
+    pub fn run(self) -> Result<()> {
+        let paths = yaml_file_paths(&self.path)?;
+
+        let mut unformatted = 0;
+        let mut failed = 0;
+        for path in &paths {
+            let original = fs::read_to_string(path)?;
+            match format(&original, self.upgrade) {
+                Ok(formatted) if formatted == original => {}
+                Ok(formatted) => {
+                    unformatted += 1;
+                    if self.check {
+                        println!("{} {path}", "Would format".yellow());
+                    } else {
+                        fs::write(path, formatted)?;
+                        println!("{} {path}", "Formatted".green());
+                    }
+                }
+                Err(error) => {
+                    failed += 1;
+                    println!("{} {path}: {error}", "Failed to format".red());
+                }
+            }
+        }
+
+        if failed > 0 {
+            bail!(
+                "{failed} of {} manifests could not be formatted",
+                paths.len()
+            )
+        }
+        if self.check && unformatted > 0 {
+            bail!(
+                "{unformatted} of {} manifests are not formatted",
+                paths.len()
+            )
+        }
+        if !self.check {
+            println!(
+                "{} formatted {unformatted} of {} manifests",
+                "Successfully".green(),
+                paths.len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn yaml_file_paths(path: &Utf8Path) -> walkdir::Result<Vec<Utf8PathBuf>> {
+    WalkDir::new(path)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map_ok(|entry| {
+            entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml"))
+                .then(|| Utf8PathBuf::from_path_buf(entry.into_path()).ok())?
+        })
+        .collect()
+}
+
+/// Parses a manifest and serializes it again, keeping its header comment and, unless upgrading,
+/// its manifest version
+fn format(content: &str, upgrade: bool) -> Result<String, FormatError> {
+    let content = content.trim_start_matches('\u{FEFF}');
+    let mut manifest = if upgrade {
+        let (manifest, migration) = Manifest::migrate(content)?;
+        let dropped = migration
+            .notes
+            .into_iter()
+            .filter(|note| note.lossy)
+            .map(|note| note.field)
+            .collect::<Vec<_>>();
+        if !dropped.is_empty() {
+            return Err(FormatError::DroppedFields(dropped));
+        }
+        manifest
+    } else {
+        Manifest::from_yaml(content)?
+    };
+
+    let manifest_version = manifest.manifest_version();
+    if let Manifest::Installer(installer) = &mut manifest {
+        installer.optimize();
+    }
+    manifest.set_manifest_version(manifest_version);
+
+    let formatted = manifest.to_yaml(
+        &header_comment(content).map_or_else(|| created_with_header(None), str::to_owned),
+    )?;
+
+    if !upgrade {
+        let dropped = dropped_fields(
+            &serde_yaml::from_str(content)?,
+            &serde_yaml::from_str(&formatted)?,
+        );
+        if !dropped.is_empty() {
+            return Err(FormatError::DroppedFields(dropped));
+        }
+    }
+
+    Ok(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::{FormatError, format};
+
+    const INSTALLER: &str = indoc! {"
+        # Created with WinGet Releaser using komac v2.0.0
+        PackageIdentifier: Contoso.App
+        PackageVersion: 1.2.3
+        Installers:
+        - Architecture: x64
+          InstallerType: inno
+          InstallerUrl: https://example.com/setup-x64.exe
+          InstallerSha256: AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
+        - Architecture: x86
+          InstallerType: inno
+          InstallerUrl: https://example.com/setup-x86.exe
+          InstallerSha256: BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB
+        ManifestType: installer
+        ManifestVersion: 1.6.0
+    "};
+
+    #[test]
+    fn formats_installer_manifest() {
+        let formatted = format(INSTALLER, false).unwrap();
+
+        assert_eq!(
+            formatted.replace("\r\n", "\n"),
+            indoc! {"
+                # Created with WinGet Releaser using komac v2.0.0
+                # yaml-language-server: $schema=https://aka.ms/winget-manifest.installer.1.6.0.schema.json
+
+                PackageIdentifier: Contoso.App
+                PackageVersion: 1.2.3
+                InstallerType: inno
+                Installers:
+                - Architecture: x86
+                  InstallerUrl: https://example.com/setup-x86.exe
+                  InstallerSha256: BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB
+                - Architecture: x64
+                  InstallerUrl: https://example.com/setup-x64.exe
+                  InstallerSha256: AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
+                ManifestType: installer
+                ManifestVersion: 1.6.0
+            "}
+        );
+        assert_eq!(format(&formatted, false).unwrap(), formatted);
+    }
+
+    #[test]
+    fn upgrade() {
+        let formatted = format(INSTALLER, true).unwrap();
+
+        assert!(formatted.contains("winget-manifest.installer.1.10.0.schema.json"));
+        assert!(formatted.contains("ManifestVersion: 1.10.0"));
+    }
+
+    #[test]
+    fn refuses_to_drop_fields() {
+        let manifest = INSTALLER.replace(
+            "ManifestType: installer",
+            "InstallerLocale: en-US\nInstalerSwitches:\n  Silent: /S\nManifestType: installer",
+        );
+
+        assert!(matches!(
+            format(&manifest, false),
+            Err(FormatError::DroppedFields(fields)) if fields == ["InstalerSwitches"]
+        ));
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt::Write;
+
+use anstream::println;
+use camino::Utf8PathBuf;
+use clap::Parser;
+use color_eyre::eyre::{Result, bail};
+use indicatif::ProgressBar;
+use owo_colors::OwoColorize;
+use winget_types::{ManifestVersion, PackageIdentifier, PackageVersion, url::DecodedUrl};
+
+use crate::{
+    commands::utils::{
+        SPINNER_TICK_RATE, SubmitOption, TemplateArgs, print_migrations,
+        prompt_existing_pull_request, write_changes_to_dir,
+    },
+    credential::handle_token,
+    github::{
+        github_client::{GitHub, WINGET_PKGS_FULL_NAME},
+        utils::{PackagePath, pull_request::pr_changes, template::installer_summary},
+    },
+    manifests::migrate::Migration,
+    terminal::Hyperlinkable,
+};
+
+/// Migrate the manifests of a version to the latest manifest version
+#[derive(Parser)]
+pub struct Migrate {
+    /// The package's unique identifier
+    #[arg()]
+    package_identifier: PackageIdentifier,
+
+    /// The package's version, defaulting to the latest version
+    #[arg(short = 'v', long = "version")]
+    package_version: Option<PackageVersion>,
+
+    /// Submit the migration even if values could not be carried over
+    #[arg(long)]
+    allow_lossy: bool,
+
+    #[command(flatten)]
+    pr_templates: TemplateArgs,
+
+    /// Automatically submit a pull request
+    #[arg(short, long)]
+    submit: bool,
+
+    /// Name of external tool that invoked Komac
+    #[arg(long, env = "KOMAC_CREATED_WITH")]
+    created_with: Option<String>,
+
+    /// URL to external tool that invoked Komac
+    #[arg(long, env = "KOMAC_CREATED_WITH_URL", value_hint = clap::ValueHint::Url)]
+    created_with_url: Option<DecodedUrl>,
+
+    /// Directory to output the manifests to
+    #[arg(short, long, env = "OUTPUT_DIRECTORY", value_hint = clap::ValueHint::DirPath)]
+    output: Option<Utf8PathBuf>,
+
+    /// Open pull request link automatically
+    #[arg(long, env = "OPEN_PR")]
+    open_pr: bool,
+
+    /// Run without submitting
+    #[arg(long, env = "DRY_RUN")]
+    dry_run: bool,
+
+    /// Skip checking for existing pull requests
+    #[arg(long, env)]
+    skip_pr_check: bool,
+
+    /// GitHub personal access token with the `public_repo` scope
+    #[arg(short, long, env = "GITHUB_TOKEN")]
+    token: Option<String>,
+}
+
+impl Migrate {
+    pub async fn run(self) -> Result<()> {
+        let token = handle_token(self.token.as_deref()).await?;
+        let pr_templates = self.pr_templates.templates()?;
+        let github = GitHub::new(&token)?;
+
+        let versions = github.get_versions(&self.package_identifier).await?;
+        let package_version = match self.package_version {
+            Some(version) if !versions.contains(&version) => {
+                let closest = version.closest(&versions).unwrap_or_else(|| unreachable!());
+                bail!(
+                    "{} {version} does not exist in {WINGET_PKGS_FULL_NAME}. The closest version is {closest}",
+                    self.package_identifier
+                )
+            }
+            Some(version) => version,
+            None => versions.last().cloned().unwrap_or_else(|| unreachable!()),
+        };
+
+        let (mut manifests, migrations) = github
+            .get_migrated_manifests(&self.package_identifier, &package_version)
+            .await?;
+
+        if !migrations
+            .iter()
+            .any(|(_, migration)| migration.is_needed())
+        {
+            println!(
+                "{} {package_version} already uses manifest version {}",
+                self.package_identifier,
+                ManifestVersion::DEFAULT
+            );
+            return Ok(());
+        }
+
+        print_migrations(&self.package_identifier, &package_version, &migrations);
+        if !self.allow_lossy && migrations.iter().any(|(_, migration)| migration.is_lossy()) {
+            bail!(
+                "Migrating {} {package_version} would lose values or leave them incomplete. Fix the warnings above or pass --allow-lossy",
+                self.package_identifier
+            )
+        }
+
+        if !(self.skip_pr_check || self.dry_run)
+            && let Some(pull_request) = github
+                .get_existing_pull_request(&self.package_identifier, &package_version)
+                .await?
+            && !prompt_existing_pull_request(
+                &self.package_identifier,
+                &package_version,
+                &pull_request,
+            )?
+        {
+            return Ok(());
+        }
+
+        manifests.installer.optimize();
+
+        let package_path = PackagePath::new(&self.package_identifier, Some(&package_version), None);
+        let mut changes = pr_changes()
+            .package_identifier(&self.package_identifier)
+            .manifests(&manifests)
+            .package_path(&package_path)
+            .maybe_created_with(self.created_with.as_deref())
+            .create()?;
+
+        if let Some(output) = self.output.map(|out| out.join(package_path.as_str())) {
+            write_changes_to_dir(&changes, output.as_path()).await?;
+            println!(
+                "{} written all manifest files to {output}",
+                "Successfully".green()
+            );
+        }
+
+        let submit_option = SubmitOption::prompt(
+            &mut changes,
+            &self.package_identifier,
+            &package_version,
+            self.submit,
+            self.dry_run,
+            None,
+        )?;
+
+        if submit_option == SubmitOption::Exit {
+            return Ok(());
+        }
+
+        let pr_progress = ProgressBar::new_spinner().with_message(format!(
+            "Creating a pull request for {} {package_version}",
+            self.package_identifier
+        ));
+        pr_progress.enable_steady_tick(SPINNER_TICK_RATE);
+
+        let pull_request_url = github
+            .add_version()
+            .identifier(&self.package_identifier)
+            .version(&package_version)
+            .versions(&versions)
+            .changes(changes)
+            .maybe_created_with(self.created_with)
+            .maybe_created_with_url(self.created_with_url)
+            .templates(&pr_templates)
+            .installers(&installer_summary(&manifests.installer))
+            .description(&migration_summary(&migrations))
+            .send()
+            .await?;
+
+        pr_progress.finish_and_clear();
+
+        println!(
+            "{} created a {} to {WINGET_PKGS_FULL_NAME}",
+            "Successfully".green(),
+            "pull request".hyperlink(&pull_request_url)
+        );
+
+        if self.open_pr {
+            open::that(pull_request_url.as_str())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Explains in the pull request body which manifest versions were migrated and what changed
+fn migration_summary(migrations: &[(String, Migration)]) -> String {
+    let mut summary = format!(
+        "### Migrated to manifest version {}\n\n",
+        ManifestVersion::DEFAULT
+    );
+    for (file_name, migration) in migrations
+        .iter()
+        .filter(|(_, migration)| migration.is_needed())
+    {
+        let _ = writeln!(summary, "- `{file_name}` from {}", migration.from);
+        for note in &migration.notes {
+            let _ = writeln!(summary, "  - {note}");
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use winget_types::ManifestVersion;
+
+    use super::migration_summary;
+    use crate::manifests::migrate::{Migration, MigrationNote};
+
+    #[test]
+    fn summary() {
+        let migrations = [
+            (
+                String::from("Contoso.App.installer.yaml"),
+                Migration {
+                    from: ManifestVersion::new(1, 0, 0),
+                    notes: vec![MigrationNote {
+                        version: ManifestVersion::new(1, 1, 0),
+                        field: String::from("InstallerSuccessCodes"),
+                        message: String::from("no longer lists 0 as it always means success"),
+                        lossy: false,
+                    }],
+                },
+            ),
+            (
+                String::from("Contoso.App.yaml"),
+                Migration {
+                    from: ManifestVersion::DEFAULT,
+                    notes: Vec::new(),
+                },
+            ),
+        ];
+
+        assert_eq!(
+            migration_summary(&migrations),
+            indoc! {"
+                ### Migrated to manifest version 1.10.0
+
+                - `Contoso.App.installer.yaml` from 1.0.0
+                  - InstallerSuccessCodes no longer lists 0 as it always means success
+            "}
+        );
+    }
+}
@@ -1,10 +1,17 @@
 pub mod analyse;
 pub mod cleanup;
 pub mod complete;
+pub mod config;
+pub mod diff;
+pub mod fmt;
 pub mod list_versions;
+pub mod migrate;
 pub mod new_version;
+pub mod pull_requests;
+pub mod rehash;
 pub mod remove_dead_versions;
 pub mod remove_version;
+pub mod scan;
 pub mod show_version;
 pub mod submit;
 pub mod sync_fork;
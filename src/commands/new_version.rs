@@ -7,12 +7,13 @@ use std::{
 use anstream::println;
 use camino::Utf8PathBuf;
 use clap::Parser;
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{Result, bail};
 use indicatif::ProgressBar;
 use inquire::CustomType;
 use itertools::Itertools;
 use ordinal::Ordinal;
 use owo_colors::OwoColorize;
+use tracing::warn;
 use winget_types::{
     LanguageTag, ManifestType, ManifestVersion, PackageIdentifier, PackageVersion,
     installer::{
@@ -21,8 +22,8 @@ use winget_types::{
         switches::{CustomSwitch, InstallerSwitches, SilentSwitch, SilentWithProgressSwitch},
     },
     locale::{
-        Author, Copyright, DefaultLocaleManifest, Description, License, Moniker, PackageName,
-        Publisher, ShortDescription, Tag,
+        Author, Copyright, DefaultLocaleManifest, Description, License, LocaleManifest, Moniker,
+        PackageName, Publisher, ShortDescription, Tag,
     },
     url::{
         CopyrightUrl, DecodedUrl, LicenseUrl, PackageUrl, PublisherSupportUrl, PublisherUrl,
@@ -33,25 +34,37 @@ use winget_types::{
 
 use crate::{
     commands::utils::{
-        SPINNER_TICK_RATE, SubmitOption, prompt_existing_pull_request, write_changes_to_dir,
+        DraftedLocale, HeaderArgs, LocaleArg, SPINNER_TICK_RATE, SubmitOption, TemplateArgs,
+        draft_locale, prompt_existing_pull_request, warn_version_mismatches, write_changes_to_dir,
     },
     credential::handle_token,
     download::{Download, Downloader},
     download_file::process_files,
     github::{
         github_client::{GITHUB_HOST, GitHub, WINGET_PKGS_FULL_NAME},
-        utils::{PackagePath, pull_request::pr_changes},
+        utils::{PackagePath, pull_request::pr_changes, template::installer_summary},
     },
-    manifests::{Manifests, Url},
-    prompts::{
-        check_prompt, handle_inquire_error,
-        list::list_prompt,
-        radio_prompt,
-        text::{confirm_prompt, optional_prompt, required_prompt},
+    manifests::{
+        Manifests, Url,
+        spec::{LocaleSpec, PackageSpec},
     },
+    prompts::{handle_inquire_error, prompter::Prompter, text::required_prompt},
     terminal::Hyperlinkable,
+    translation::Translator,
 };
 
+const INSTALLER_URLS: &str = "Installer URLs";
+const NESTED_INSTALLER_FILES: &str = "Nested installer files";
+
+/// The installer types that an EXE that was not recognised as a specific installer can be
+const EXE_INSTALLER_TYPES: [InstallerType; 5] = [
+    InstallerType::Exe,
+    InstallerType::Portable,
+    InstallerType::Inno,
+    InstallerType::Nullsoft,
+    InstallerType::Burn,
+];
+
 /// Create a new package from scratch
 #[derive(Parser)]
 pub struct NewVersion {
@@ -67,7 +80,7 @@ pub struct NewVersion {
     #[arg(short, long, num_args = 1.., value_hint = clap::ValueHint::Url)]
     urls: Vec<Url>,
 
-    #[arg(long)]
+    #[arg(long, env = "KOMAC_DEFAULT_LOCALE")]
     package_locale: Option<LanguageTag>,
 
     #[arg(long)]
@@ -112,14 +125,39 @@ pub struct NewVersion {
     #[arg(long, value_hint = clap::ValueHint::Url)]
     release_notes_url: Option<ReleaseNotesUrl>,
 
+    /// YAML file with a partial manifest of the package's values. Arguments take precedence over
+    /// values in the file
+    #[arg(long, value_hint = clap::ValueHint::FilePath)]
+    from: Option<Utf8PathBuf>,
+
+    /// Fail with a list of missing required values instead of prompting for them
+    #[arg(long)]
+    no_prompt: bool,
+
+    /// Additional locale to add, such as `de-DE`, or `de-DE=path.yaml` where the file is a partial
+    /// locale manifest. Descriptions that are not given are drafted by the translation provider
+    #[arg(long = "locale", value_name = "LOCALE[=FILE]")]
+    locales: Vec<LocaleArg>,
+
+    /// Create a pull request with machine translations that have not been reviewed because prompting
+    /// is disabled
+    #[arg(long)]
+    accept_translations: bool,
+
     /// Number of installers to download at the same time
-    #[arg(long, default_value_t = NonZeroUsize::new(num_cpus::get()).unwrap())]
+    #[arg(long, env = "KOMAC_CONCURRENT_DOWNLOADS", default_value_t = NonZeroUsize::new(num_cpus::get()).unwrap())]
     concurrent_downloads: NonZeroUsize,
 
+    #[command(flatten)]
+    download_headers: HeaderArgs,
+
     /// List of issues that adding this package or version would resolve
     #[arg(long)]
     resolves: Option<Vec<NonZeroU32>>,
 
+    #[command(flatten)]
+    pr_templates: TemplateArgs,
+
     /// Automatically submit a pull request
     #[arg(short, long)]
     submit: bool,
@@ -155,10 +193,45 @@ pub struct NewVersion {
 
 impl NewVersion {
     pub async fn run(self) -> Result<()> {
+        let pr_templates = self.pr_templates.templates()?;
+        let spec = self
+            .from
+            .as_deref()
+            .map(PackageSpec::from_file)
+            .transpose()?
+            .unwrap_or_default();
+        let mut prompter = Prompter::new(!self.no_prompt, self.from.is_some());
+        if let Some(installer_type) = spec.installer_type
+            && !EXE_INSTALLER_TYPES.contains(&installer_type)
+        {
+            bail!(
+                "InstallerType `{installer_type}` cannot be applied to EXE installers. Use one of {}",
+                EXE_INSTALLER_TYPES.iter().join(", ")
+            );
+        }
+
+        let package_identifier = self.package_identifier.or(spec.package_identifier);
+        let package_version = self.package_version.or(spec.package_version);
+        let package_locale = self.package_locale.or(spec.package_locale);
+        let mut urls = if self.urls.is_empty() {
+            spec.installer_urls
+        } else {
+            self.urls
+        };
+
+        // Report everything that is needed before any analysis at once rather than one at a time
+        prompter.expect(package_identifier.as_ref());
+        prompter.expect(package_version.as_ref());
+        prompter.expect(package_locale.as_ref());
+        if urls.is_empty() && !prompter.is_interactive() {
+            prompter.missing(INSTALLER_URLS);
+        }
+        prompter.finish()?;
+
         let token = handle_token(self.token.as_deref()).await?;
         let github = GitHub::new(&token)?;
 
-        let package_identifier = required_prompt(self.package_identifier)?;
+        let package_identifier = required_prompt(package_identifier)?;
 
         let versions = github.get_versions(&package_identifier).await.ok();
 
@@ -171,24 +244,26 @@ impl NewVersion {
         let manifests =
             latest_version.map(|version| github.get_manifests(&package_identifier, version));
 
-        let package_version = required_prompt(self.package_version)?;
+        let package_version = required_prompt(package_version)?;
 
         if let Some(pull_request) = github
             .get_existing_pull_request(&package_identifier, &package_version)
             .await?
+            && !(self.skip_pr_check || self.dry_run)
         {
-            if !(self.skip_pr_check || self.dry_run)
-                && !prompt_existing_pull_request(
-                    &package_identifier,
-                    &package_version,
-                    &pull_request,
-                )?
+            if !prompter.is_interactive() {
+                bail!(
+                    "There is already {} pull request for {package_identifier} {package_version}: {}. Use --skip-pr-check to create another",
+                    pull_request.state,
+                    pull_request.url
+                );
+            }
+            if !prompt_existing_pull_request(&package_identifier, &package_version, &pull_request)?
             {
                 return Ok(());
             }
         }
 
-        let mut urls = self.urls;
         if urls.is_empty() {
             while urls.len() < 1024 {
                 let message = format!("{} Installer URL", Ordinal(urls.len() + 1));
@@ -224,7 +299,8 @@ impl NewVersion {
             }
         });
 
-        let downloader = Downloader::new_with_concurrent(self.concurrent_downloads);
+        let downloader = Downloader::new_with_concurrent(self.concurrent_downloads)
+            .with_headers(self.download_headers.download_headers()?);
         let mut files = downloader
             .download(
                 &urls
@@ -235,33 +311,72 @@ impl NewVersion {
             )
             .await?;
         let mut download_results = process_files(&mut files).await?;
+        warn_version_mismatches(&download_results, &package_version);
 
+        let spec_switches = spec.installer_switches.unwrap_or_default();
+        let mut spec_nested_installer_files = spec.nested_installer_files;
+        let mut has_exe = false;
         let mut installers = Vec::new();
-        for analyser in &mut download_results.values_mut() {
+        for (url, analyser) in &mut download_results {
             let mut installer_switches = InstallerSwitches::default();
-            if analyser
+            if let Some(exe) = analyser
                 .installers
                 .iter()
-                .any(|installer| installer.r#type == Some(InstallerType::Exe))
+                .find(|installer| installer.r#type == Some(InstallerType::Exe))
             {
-                if confirm_prompt(&format!("Is {} a portable exe?", analyser.file_name))? {
-                    for installer in &mut analyser.installers {
-                        installer.r#type = Some(InstallerType::Portable);
+                has_exe = true;
+                // Switches from a matched switch rule mean that the EXE is known to be an installer
+                let detected_switches = exe.switches.clone();
+                let silent = spec_switches.silent.clone().or(detected_switches.silent);
+                let installer_type = match spec.installer_type {
+                    Some(installer_type) => installer_type,
+                    None if silent.is_none()
+                        && prompter
+                            .confirm(&format!("Is {} a portable exe?", analyser.file_name))? =>
+                    {
+                        InstallerType::Portable
+                    }
+                    None => InstallerType::Exe,
+                };
+                for installer in &mut analyser.installers {
+                    installer.r#type = Some(installer_type);
+                    if let Some(portable) = &analyser.portable {
+                        portable.enrich(installer);
                     }
                 }
-                installer_switches.silent = Some(required_prompt::<SilentSwitch>(None)?);
-                installer_switches.silent_with_progress =
-                    Some(required_prompt::<SilentWithProgressSwitch>(None)?);
+                // WinGet knows the switches of the other installer types
+                if matches!(installer_type, InstallerType::Exe | InstallerType::Portable) {
+                    installer_switches.silent = prompter.required::<SilentSwitch>(silent)?;
+                    installer_switches.silent_with_progress = prompter
+                        .required::<SilentWithProgressSwitch>(
+                            spec_switches
+                                .silent_with_progress
+                                .clone()
+                                .or(detected_switches.silent_with_progress),
+                        )?;
+                }
             }
             if analyser
                 .installers
                 .iter()
                 .any(|installer| installer.r#type == Some(InstallerType::Portable))
             {
-                installer_switches.custom = optional_prompt::<CustomSwitch>(None)?;
+                installer_switches.custom =
+                    prompter.optional::<CustomSwitch>(spec_switches.custom.clone())?;
             }
             if let Some(zip) = &mut analyser.zip {
-                zip.prompt()?;
+                if let Some(nested_installer_files) = spec_nested_installer_files.remove(url) {
+                    zip.select(nested_installer_files)?;
+                } else if prompter.is_interactive() {
+                    zip.prompt()?;
+                } else if !zip.possible_installer_files.is_empty()
+                    && zip
+                        .installers
+                        .iter()
+                        .all(|installer| installer.nested_installer_files.is_empty())
+                {
+                    prompter.missing(NESTED_INSTALLER_FILES);
+                }
             }
             let mut analyser_installers = mem::take(&mut analyser.installers);
             for installer in &mut analyser_installers {
@@ -271,7 +386,17 @@ impl NewVersion {
             }
             installers.extend(analyser_installers);
         }
-        let default_locale = required_prompt(self.package_locale)?;
+        if let Some(installer_type) = spec.installer_type
+            && !has_exe
+        {
+            bail!(
+                "InstallerType `{installer_type}` was given, but none of the installers were detected as a generic EXE"
+            );
+        }
+        if let Some(url) = spec_nested_installer_files.keys().next() {
+            bail!("NestedInstallerFiles were given for {url}, which is not a zip installer URL");
+        }
+        let default_locale = required_prompt(package_locale)?;
         let manifests = match manifests {
             Some(manifests) => Some(manifests.await?),
             None => None,
@@ -285,17 +410,17 @@ impl NewVersion {
             {
                 InstallModes::all()
             } else {
-                check_prompt::<InstallModes>()?
+                prompter.check::<InstallModes>(spec.install_modes)?
             },
-            success_codes: list_prompt::<InstallerSuccessCode>()?,
-            upgrade_behavior: Some(radio_prompt::<UpgradeBehavior>()?),
-            commands: list_prompt::<Command>()?,
-            protocols: list_prompt::<Protocol>()?,
+            success_codes: prompter.list::<InstallerSuccessCode>(spec.installer_success_codes)?,
+            upgrade_behavior: prompter.radio::<UpgradeBehavior>(spec.upgrade_behavior)?,
+            commands: prompter.list::<Command>(spec.commands)?,
+            protocols: prompter.list::<Protocol>(spec.protocols)?,
             file_extensions: if installers
                 .iter()
                 .all(|installer| installer.file_extensions.is_empty())
             {
-                list_prompt::<FileExtension>()?
+                prompter.list::<FileExtension>(spec.file_extensions)?
             } else {
                 BTreeSet::new()
             },
@@ -309,58 +434,77 @@ impl NewVersion {
             None => None,
         };
 
-        let default_locale_manifest = DefaultLocaleManifest {
-            package_identifier: package_identifier.clone(),
-            package_version: package_version.clone(),
-            package_locale: default_locale.clone(),
-            publisher: match download_results
+        let publisher = prompter.required(self.publisher.or(spec.publisher).or_else(|| {
+            download_results
                 .values_mut()
                 .find(|analyser| analyser.publisher.is_some())
                 .and_then(|analyser| analyser.publisher.take())
-            {
-                Some(publisher) => publisher,
-                None => required_prompt(self.publisher)?,
-            },
-            publisher_url: optional_prompt(self.publisher_url)?,
-            publisher_support_url: optional_prompt(self.publisher_support_url)?,
-            author: optional_prompt(self.author)?,
-            package_name: match download_results
-                .values_mut()
-                .find(|analyser| analyser.package_name.is_some())
-                .and_then(|analyser| analyser.package_name.take())
-            {
-                Some(package_name) => package_name,
-                None => required_prompt(self.package_name)?,
-            },
-            package_url: optional_prompt(self.package_url)?,
-            license: match github_values
+        }))?;
+        let publisher_url = prompter.optional(self.publisher_url.or(spec.publisher_url))?;
+        let publisher_support_url =
+            prompter.optional(self.publisher_support_url.or(spec.publisher_support_url))?;
+        let author = prompter.optional(self.author.or(spec.author))?;
+        let package_name =
+            prompter.required(self.package_name.or(spec.package_name).or_else(|| {
+                download_results
+                    .values_mut()
+                    .find(|analyser| analyser.package_name.is_some())
+                    .and_then(|analyser| analyser.package_name.take())
+            }))?;
+        let package_url = prompter.optional(self.package_url.or(spec.package_url))?;
+        let license = prompter.required(self.license.or(spec.license).or_else(|| {
+            github_values
                 .as_mut()
                 .and_then(|values| values.license.take())
-            {
-                Some(license) => license,
-                None => required_prompt(self.license)?,
-            },
-            license_url: optional_prompt(self.license_url)?,
-            copyright: match download_results
+        }))?;
+        let license_url = prompter.optional(self.license_url.or(spec.license_url))?;
+        let copyright = prompter.optional(self.copyright.or(spec.copyright).or_else(|| {
+            download_results
                 .values_mut()
                 .find(|analyser| analyser.copyright.is_some())
                 .and_then(|analyser| analyser.copyright.take())
-            {
-                Some(copyright) => Some(copyright),
-                None => optional_prompt(self.copyright)?,
-            },
-            copyright_url: optional_prompt(self.copyright_url)?,
-            short_description: required_prompt(self.short_description)?,
-            description: optional_prompt(self.description)?,
-            moniker: optional_prompt(self.moniker)?,
-            tags: match github_values
-                .as_mut()
-                .map(|values| mem::take(&mut values.topics))
-            {
-                Some(topics) => topics,
-                None => list_prompt::<Tag>()?,
-            },
-            release_notes_url: optional_prompt(self.release_notes_url)?,
+        }))?;
+        let copyright_url = prompter.optional(self.copyright_url.or(spec.copyright_url))?;
+        let short_description =
+            prompter.required(self.short_description.or(spec.short_description))?;
+        let description = prompter.optional(self.description.or(spec.description))?;
+        let moniker = prompter.optional(self.moniker.or(spec.moniker))?;
+        let tags = match github_values
+            .as_mut()
+            .map(|values| mem::take(&mut values.topics))
+        {
+            Some(topics) => topics,
+            None => prompter.list::<Tag>(spec.tags)?,
+        };
+        let release_notes_url =
+            prompter.optional(self.release_notes_url.or(spec.release_notes_url))?;
+
+        let (Some(publisher), Some(package_name), Some(license), Some(short_description)) =
+            (publisher, package_name, license, short_description)
+        else {
+            return Err(prompter.missing_error());
+        };
+        prompter.finish()?;
+
+        let default_locale_manifest = DefaultLocaleManifest {
+            package_identifier: package_identifier.clone(),
+            package_version: package_version.clone(),
+            package_locale: default_locale.clone(),
+            publisher,
+            publisher_url,
+            publisher_support_url,
+            author,
+            package_name,
+            package_url,
+            license,
+            license_url,
+            copyright,
+            copyright_url,
+            short_description,
+            description,
+            moniker,
+            tags,
+            release_notes_url,
             manifest_type: ManifestType::DefaultLocale,
             ..DefaultLocaleManifest::default()
         };
@@ -373,6 +517,51 @@ impl NewVersion {
 
         installer_manifest.optimize();
 
+        let translator = Translator::from_env()?;
+        let mut locales = manifests
+            .map(|manifests| manifests.locales)
+            .unwrap_or_default();
+        let mut unreviewed = Vec::new();
+        for LocaleArg { locale, path } in self.locales {
+            if locale == default_locale {
+                warn!("{locale} is already the default locale");
+                continue;
+            }
+            let spec = path
+                .as_deref()
+                .map(LocaleSpec::from_file)
+                .transpose()?
+                .unwrap_or_default();
+            if prompter.is_interactive() {
+                println!("Adding the {} locale", locale.blue());
+            }
+            let drafted = draft_locale(
+                locale,
+                spec,
+                &default_locale_manifest,
+                translator.as_ref(),
+                &prompter,
+            )
+            .await?;
+            add_locale(&mut locales, &mut unreviewed, drafted);
+        }
+        while prompter.confirm("Would you like to add another locale?")? {
+            let locale = required_prompt::<LanguageTag>(None)?;
+            if locale == default_locale {
+                println!("{locale} is already the default locale");
+                continue;
+            }
+            let drafted = draft_locale(
+                locale,
+                LocaleSpec::default(),
+                &default_locale_manifest,
+                translator.as_ref(),
+                &prompter,
+            )
+            .await?;
+            add_locale(&mut locales, &mut unreviewed, drafted);
+        }
+
         let version_manifest = VersionManifest {
             package_identifier: package_identifier.clone(),
             package_version: package_version.clone(),
@@ -384,9 +573,7 @@ impl NewVersion {
         let manifests = Manifests {
             installer: installer_manifest,
             default_locale: default_locale_manifest,
-            locales: manifests
-                .map(|manifests| manifests.locales)
-                .unwrap_or_default(),
+            locales,
             version: version_manifest,
         };
 
@@ -406,18 +593,33 @@ impl NewVersion {
             );
         }
 
+        if !unreviewed.is_empty() {
+            warn!(
+                "Machine translations have not been reviewed: {}",
+                unreviewed.join(", ")
+            );
+        }
+
         let submit_option = SubmitOption::prompt(
             &mut changes,
             &package_identifier,
             &package_version,
             self.submit,
-            self.dry_run,
+            // Without prompting, only submit when explicitly asked to
+            self.dry_run || (self.no_prompt && !self.submit),
+            None,
         )?;
 
         if submit_option == SubmitOption::Exit {
             return Ok(());
         }
 
+        if !unreviewed.is_empty() && !self.accept_translations {
+            bail!(
+                "Review machine translations by running without --no-prompt, or pass --accept-translations to submit them"
+            );
+        }
+
         // Create an indeterminate progress bar to show as a pull request is being created
         let pr_progress = ProgressBar::new_spinner().with_message(format!(
             "Creating a pull request for {package_identifier} {package_version}"
@@ -433,6 +635,8 @@ impl NewVersion {
             .maybe_issue_resolves(self.resolves)
             .maybe_created_with(self.created_with)
             .maybe_created_with_url(self.created_with_url)
+            .templates(&pr_templates)
+            .installers(&installer_summary(&manifests.installer))
             .send()
             .await?;
 
@@ -451,3 +655,20 @@ impl NewVersion {
         Ok(())
     }
 }
+
+/// Adds a drafted locale, replacing any existing manifest for the same locale
+fn add_locale(
+    locales: &mut Vec<LocaleManifest>,
+    unreviewed: &mut Vec<String>,
+    drafted: DraftedLocale,
+) {
+    let locale = &drafted.manifest.package_locale;
+    unreviewed.extend(
+        drafted
+            .unreviewed
+            .iter()
+            .map(|field| format!("{field} ({locale})")),
+    );
+    locales.retain(|manifest| manifest.package_locale != *locale);
+    locales.push(drafted.manifest);
+}
@@ -0,0 +1,179 @@
+mod show;
+
+use std::io;
+
+use anstream::println;
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::{Parser, Subcommand};
+use color_eyre::Result;
+use futures_util::TryFutureExt;
+use indicatif::ProgressBar;
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use url::Url;
+
+use crate::{
+    commands::{pull_requests::show::ShowPullRequest, utils::SPINNER_TICK_RATE},
+    credential::handle_token,
+    github::{
+        github_client::{GitHub, WINGET_PKGS_FULL_NAME},
+        graphql::get_branches::{PullRequest, PullRequestReviewDecision, StatusState},
+    },
+};
+
+/// Lists your open pull requests to winget-pkgs with their labels, checks and review state
+#[derive(Parser)]
+#[clap(
+    visible_alias = "pull-requests",
+    args_conflicts_with_subcommands = true
+)]
+pub struct PullRequests {
+    #[command(subcommand)]
+    command: Option<PullRequestsCommand>,
+
+    /// Output the pull requests as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// GitHub personal access token with the `public_repo` scope
+    #[arg(short, long, env = "GITHUB_TOKEN")]
+    token: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum PullRequestsCommand {
+    Show(ShowPullRequest),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PullRequestStatus<'pr> {
+    number: i32,
+    title: &'pr str,
+    url: &'pr Url,
+    created_at: DateTime<Utc>,
+    labels: Vec<&'pr str>,
+    checks: Option<StatusState>,
+    review_decision: Option<PullRequestReviewDecision>,
+}
+
+impl<'pr> From<&'pr PullRequest> for PullRequestStatus<'pr> {
+    fn from(pull_request: &'pr PullRequest) -> Self {
+        Self {
+            number: pull_request.number,
+            title: &pull_request.title,
+            url: &pull_request.url,
+            created_at: pull_request.created_at,
+            labels: pull_request.label_names().collect(),
+            checks: pull_request.check_state(),
+            review_decision: pull_request.review_decision,
+        }
+    }
+}
+
+impl PullRequests {
+    pub async fn run(self) -> Result<()> {
+        if let Some(PullRequestsCommand::Show(show)) = self.command {
+            return show.run().await;
+        }
+
+        let token = handle_token(self.token.as_deref()).await?;
+        let github = GitHub::new(&token)?;
+
+        let pb = ProgressBar::new_spinner().with_message(format!(
+            "Retrieving open pull requests to {WINGET_PKGS_FULL_NAME}"
+        ));
+        pb.enable_steady_tick(SPINNER_TICK_RATE);
+
+        let pull_requests = github
+            .get_username()
+            .and_then(|username| github.get_open_pull_requests(username))
+            .await?;
+
+        pb.finish_and_clear();
+
+        if self.json {
+            serde_json::to_writer(
+                io::stdout().lock(),
+                &pull_requests
+                    .iter()
+                    .map(PullRequestStatus::from)
+                    .collect::<Vec<_>>(),
+            )?;
+            return Ok(());
+        }
+
+        if pull_requests.is_empty() {
+            println!("You have no open pull requests to {WINGET_PKGS_FULL_NAME}");
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        for pull_request in &pull_requests {
+            println!(
+                "{} {} ({} old)",
+                format!("#{}", pull_request.number).bold(),
+                pull_request.title,
+                format_age(now - pull_request.created_at)
+            );
+
+            let checks = match pull_request.check_state() {
+                Some(state @ StatusState::Success) => state.green().to_string(),
+                Some(state @ (StatusState::Failure | StatusState::Error)) => {
+                    state.red().to_string()
+                }
+                Some(state) => state.yellow().to_string(),
+                None => "none".dimmed().to_string(),
+            };
+            let review = match pull_request.review_decision {
+                Some(decision @ PullRequestReviewDecision::Approved) => {
+                    decision.green().to_string()
+                }
+                Some(decision @ PullRequestReviewDecision::ChangesRequested) => {
+                    decision.red().to_string()
+                }
+                Some(decision) => decision.yellow().to_string(),
+                None => "none".dimmed().to_string(),
+            };
+            println!("    Checks: {checks}  Review: {review}");
+
+            let labels = pull_request.label_names().join(", ");
+            if !labels.is_empty() {
+                println!("    Labels: {}", labels.yellow());
+            }
+            println!("    {}", pull_request.url.blue());
+        }
+
+        Ok(())
+    }
+}
+
+fn format_age(age: TimeDelta) -> String {
+    let (amount, unit) = if age.num_days() > 0 {
+        (age.num_days(), "day")
+    } else if age.num_hours() > 0 {
+        (age.num_hours(), "hour")
+    } else {
+        (age.num_minutes().max(1), "minute")
+    };
+    format!("{amount} {unit}{}", if amount == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+    use rstest::rstest;
+
+    use super::format_age;
+
+    #[rstest]
+    #[case(TimeDelta::days(3), "3 days")]
+    #[case(TimeDelta::hours(25), "1 day")]
+    #[case(TimeDelta::hours(1), "1 hour")]
+    #[case(TimeDelta::minutes(45), "45 minutes")]
+    #[case(TimeDelta::seconds(5), "1 minute")]
+    fn pull_request_age(#[case] age: TimeDelta, #[case] expected: &str) {
+        assert_eq!(format_age(age), expected);
+    }
+}
@@ -0,0 +1,124 @@
+use anstream::println;
+use chrono::Local;
+use clap::Parser;
+use color_eyre::Result;
+use indicatif::ProgressBar;
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+
+use crate::{
+    commands::utils::SPINNER_TICK_RATE,
+    credential::handle_token,
+    github::{
+        github_client::GitHub,
+        graphql::get_branches::PullRequestState,
+        validation::{VALIDATION_BOTS, ValidationIssue},
+    },
+};
+
+const AZURE_DEVOPS_HOST: &str = "dev.azure.com";
+
+/// Summarises the validation pipeline results of a pull request to winget-pkgs
+#[derive(Parser)]
+pub struct ShowPullRequest {
+    /// The pull request's number
+    #[arg(value_parser = clap::value_parser!(i32).range(1..))]
+    number: i32,
+
+    /// GitHub personal access token with the `public_repo` scope
+    #[arg(short, long, env = "GITHUB_TOKEN")]
+    token: Option<String>,
+}
+
+impl ShowPullRequest {
+    pub async fn run(self) -> Result<()> {
+        let token = handle_token(self.token.as_deref()).await?;
+        let github = GitHub::new(&token)?;
+
+        let pb = ProgressBar::new_spinner()
+            .with_message(format!("Retrieving pull request #{}", self.number));
+        pb.enable_steady_tick(SPINNER_TICK_RATE);
+
+        let pull_request = github.get_pull_request(self.number).await?;
+
+        pb.finish_and_clear();
+
+        println!(
+            "{} {}",
+            format!("#{}", pull_request.number).bold(),
+            pull_request.title
+        );
+        println!("{}", pull_request.url.blue());
+        if pull_request.state != PullRequestState::Open {
+            println!("This is {} pull request", pull_request.state);
+        }
+
+        let labels = pull_request.label_names().join(", ");
+        if !labels.is_empty() {
+            println!("Labels: {}", labels.yellow());
+        }
+
+        let bot_comments = pull_request
+            .comments
+            .comments
+            .iter()
+            .filter(|comment| {
+                comment.author.as_ref().is_some_and(|author| {
+                    VALIDATION_BOTS
+                        .iter()
+                        .any(|bot| author.login.eq_ignore_ascii_case(bot))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let issues = ValidationIssue::find(
+            pull_request.label_names(),
+            bot_comments.iter().map(|comment| comment.body.as_str()),
+        );
+
+        if issues.is_empty() {
+            println!("{}", "No validation failures were found".green());
+        } else {
+            let package = pull_request.package();
+            let package = package
+                .as_ref()
+                .map(|(identifier, version)| (identifier, version));
+            println!();
+            for issue in issues {
+                println!("{} {issue}", "•".red());
+                if let Some(suggestion) = issue.suggestion(package) {
+                    println!("  {}", suggestion.cyan());
+                }
+            }
+        }
+
+        if let Some(latest_comment) = bot_comments.last() {
+            println!();
+            println!(
+                "Latest validation comment from {}: {}",
+                latest_comment.created_at.with_timezone(&Local).date_naive(),
+                latest_comment.url.blue()
+            );
+        }
+
+        // Links to the logs of the validation pipeline
+        let log_urls = bot_comments
+            .iter()
+            .flat_map(|comment| {
+                comment
+                    .body
+                    .split(|char: char| char.is_whitespace() || "()<>[]".contains(char))
+            })
+            .filter(|word| word.starts_with("https://") && word.contains(AZURE_DEVOPS_HOST))
+            .unique()
+            .collect::<Vec<_>>();
+        if !log_urls.is_empty() {
+            println!("Validation logs:");
+            for url in log_urls {
+                println!("  {}", url.blue());
+            }
+        }
+
+        Ok(())
+    }
+}
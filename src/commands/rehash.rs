@@ -0,0 +1,566 @@
+use std::{collections::HashMap, fmt::Write, mem, num::NonZeroUsize};
+
+use anstream::println;
+use camino::Utf8PathBuf;
+use clap::Parser;
+use color_eyre::eyre::{Result, bail, eyre};
+use derive_more::Display;
+use indicatif::ProgressBar;
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+use tracing::{error, warn};
+use winget_types::{
+    PackageIdentifier, PackageVersion, Sha256String,
+    installer::{Installer, InstallerManifest},
+    url::DecodedUrl,
+};
+
+use crate::{
+    commands::utils::{
+        HeaderArgs, SPINNER_TICK_RATE, SubmitOption, TemplateArgs, prompt_existing_pull_request,
+        write_changes_to_dir,
+    },
+    credential::handle_token,
+    download::{Download, Downloader},
+    download_file::process_files,
+    github::{
+        github_client::{GitHub, WINGET_PKGS_FULL_NAME},
+        utils::{PackagePath, template::installer_summary},
+    },
+    manifests::{
+        Url, created_with_header, diff::ManifestSnapshot, header_comment, manifest::Manifest,
+    },
+    terminal::Hyperlinkable,
+};
+
+/// Update the hashes of installers that were replaced at the same URL
+#[derive(Parser)]
+pub struct Rehash {
+    /// The package's unique identifier
+    #[arg()]
+    package_identifier: PackageIdentifier,
+
+    /// The package's version, defaulting to the latest version
+    #[arg(short = 'v', long = "version")]
+    package_version: Option<PackageVersion>,
+
+    /// Number of installers to download at the same time
+    #[arg(long, env = "KOMAC_CONCURRENT_DOWNLOADS", default_value_t = NonZeroUsize::new(num_cpus::get()).unwrap())]
+    concurrent_downloads: NonZeroUsize,
+
+    #[command(flatten)]
+    download_headers: HeaderArgs,
+
+    #[command(flatten)]
+    pr_templates: TemplateArgs,
+
+    /// Automatically submit a pull request
+    #[arg(short, long)]
+    submit: bool,
+
+    /// Name of external tool that invoked Komac
+    #[arg(long, env = "KOMAC_CREATED_WITH")]
+    created_with: Option<String>,
+
+    /// URL to external tool that invoked Komac
+    #[arg(long, env = "KOMAC_CREATED_WITH_URL", value_hint = clap::ValueHint::Url)]
+    created_with_url: Option<DecodedUrl>,
+
+    /// Directory to output the manifests to
+    #[arg(short, long, env = "OUTPUT_DIRECTORY", value_hint = clap::ValueHint::DirPath)]
+    output: Option<Utf8PathBuf>,
+
+    /// Open pull request link automatically
+    #[arg(long, env = "OPEN_PR")]
+    open_pr: bool,
+
+    /// Run without submitting
+    #[arg(long, env = "DRY_RUN")]
+    dry_run: bool,
+
+    /// Skip checking for existing pull requests
+    #[arg(long, env)]
+    skip_pr_check: bool,
+
+    /// GitHub personal access token with the `public_repo` scope
+    #[arg(short, long, env = "GITHUB_TOKEN")]
+    token: Option<String>,
+}
+
+impl Rehash {
+    pub async fn run(self) -> Result<()> {
+        let token = handle_token(self.token.as_deref()).await?;
+        let pr_templates = self.pr_templates.templates()?;
+        let github = GitHub::new(&token)?;
+
+        let versions = github.get_versions(&self.package_identifier).await?;
+        let package_version = match self.package_version {
+            Some(version) if !versions.contains(&version) => {
+                let closest = version.closest(&versions).unwrap_or_else(|| unreachable!());
+                bail!(
+                    "{} {version} does not exist in {WINGET_PKGS_FULL_NAME}. The closest version is {closest}",
+                    self.package_identifier
+                )
+            }
+            Some(version) => version,
+            None => versions.last().cloned().unwrap_or_else(|| unreachable!()),
+        };
+
+        if !(self.skip_pr_check || self.dry_run)
+            && let Some(pull_request) = github
+                .get_existing_pull_request(&self.package_identifier, &package_version)
+                .await?
+            && !prompt_existing_pull_request(
+                &self.package_identifier,
+                &package_version,
+                &pull_request,
+            )?
+        {
+            return Ok(());
+        }
+
+        let (mut manifests, manifest_files) = github
+            .get_manifests_with_files(&self.package_identifier, &package_version)
+            .await?;
+        let previous_manifests = ManifestSnapshot::new(&manifests);
+
+        let downloader = Downloader::new_with_concurrent(self.concurrent_downloads)
+            .with_headers(self.download_headers.download_headers()?);
+        let mut files = downloader
+            .download(
+                &manifests
+                    .installer
+                    .installers
+                    .iter()
+                    .map(|installer| &installer.url)
+                    .unique()
+                    .map(|url| Download::new(Url::from(url.clone())))
+                    .collect::<Vec<_>>(),
+            )
+            .await?;
+        let download_results = process_files(&mut files).await?;
+
+        let mut installers = mem::take(&mut manifests.installer.installers);
+        let mut rehashed = Vec::new();
+        for installer in &mut installers {
+            let analyser = download_results
+                .get(&installer.url)
+                .ok_or_else(|| eyre!("Failed to analyse {}", installer.url))?;
+            let analysed = analysed_installer(installer, &analyser.installers)
+                .ok_or_else(|| eyre!("Failed to analyse {}", installer.url))?;
+
+            let signature_sha_256 = installer
+                .signature_sha_256
+                .clone()
+                .zip(analysed.signature_sha_256.clone())
+                .filter(|(previous, new)| previous != new);
+            if installer.sha_256 == analysed.sha_256 && signature_sha_256.is_none() {
+                continue;
+            }
+
+            rehashed.push(Rehashed {
+                url: installer.url.clone(),
+                sha_256: (installer.sha_256.clone(), analysed.sha_256.clone()),
+                signature_sha_256: signature_sha_256.clone(),
+                checks: check_identity(&manifests.installer, installer, analysed)
+                    .into_iter()
+                    .chain(
+                        analyser
+                            .portable_version_mismatch(&package_version)
+                            .map(|new| Check::Changed {
+                                field: "PackageVersion",
+                                previous: package_version.to_string(),
+                                new,
+                            }),
+                    )
+                    .collect(),
+            });
+
+            installer.sha_256.clone_from(&analysed.sha_256);
+            if let Some((_, new)) = signature_sha_256 {
+                installer.signature_sha_256 = Some(new);
+            }
+        }
+        manifests.installer.installers = installers;
+
+        if rehashed.is_empty() {
+            println!(
+                "The installers of {} {package_version} still match their hashes",
+                self.package_identifier
+            );
+            return Ok(());
+        }
+
+        let rehashed = merge_by_url(rehashed);
+        for Rehashed { url, checks, .. } in &rehashed {
+            for check in checks {
+                match check {
+                    Check::Unchanged { .. } => {}
+                    Check::Changed { .. } => error!("{url}: {check}"),
+                    Check::Unverified { .. } => warn!("{url}: {check}"),
+                }
+            }
+        }
+        if rehashed.iter().any(Rehashed::is_different_release) {
+            bail!(
+                "The replaced installers of {} {package_version} are a different release. Use `komac update` to submit them as a new version instead",
+                self.package_identifier
+            )
+        }
+
+        let package_path = PackagePath::new(&self.package_identifier, Some(&package_version), None);
+        // Keep the header and manifest version of each file so that only the hashes change
+        let mut changes = manifest_files
+            .iter()
+            .map(|file| {
+                let manifest = match Manifest::from_yaml(&file.text)? {
+                    Manifest::Installer(_) => Manifest::Installer(manifests.installer.clone()),
+                    manifest => manifest,
+                };
+                let header = header_comment(&file.text).map_or_else(
+                    || created_with_header(self.created_with.as_deref()),
+                    str::to_owned,
+                );
+                Ok((
+                    format!("{package_path}/{}", file.name),
+                    manifest.to_yaml(&header)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(output) = self.output.map(|out| out.join(package_path.as_str())) {
+            write_changes_to_dir(&changes, output.as_path()).await?;
+            println!(
+                "{} written all manifest files to {output}",
+                "Successfully".green()
+            );
+        }
+
+        let submit_option = SubmitOption::prompt(
+            &mut changes,
+            &self.package_identifier,
+            &package_version,
+            self.submit,
+            self.dry_run,
+            Some(&previous_manifests),
+        )?;
+
+        if submit_option == SubmitOption::Exit {
+            return Ok(());
+        }
+
+        let pr_progress = ProgressBar::new_spinner().with_message(format!(
+            "Creating a pull request for {} {package_version}",
+            self.package_identifier
+        ));
+        pr_progress.enable_steady_tick(SPINNER_TICK_RATE);
+
+        let pull_request_url = github
+            .add_version()
+            .identifier(&self.package_identifier)
+            .version(&package_version)
+            .versions(&versions)
+            .changes(changes)
+            .maybe_created_with(self.created_with)
+            .maybe_created_with_url(self.created_with_url)
+            .templates(&pr_templates)
+            .installers(&installer_summary(&manifests.installer))
+            .description(&verification_summary(&rehashed))
+            .send()
+            .await?;
+
+        pr_progress.finish_and_clear();
+
+        println!(
+            "{} created a {} to {WINGET_PKGS_FULL_NAME}",
+            "Successfully".green(),
+            "pull request".hyperlink(&pull_request_url)
+        );
+
+        if self.open_pr {
+            open::that(pull_request_url.as_str())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An installer whose file was replaced at the same URL
+struct Rehashed {
+    url: DecodedUrl,
+    sha_256: (Sha256String, Sha256String),
+    signature_sha_256: Option<(Sha256String, Sha256String)>,
+    checks: Vec<Check>,
+}
+
+impl Rehashed {
+    fn is_different_release(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| matches!(check, Check::Changed { .. }))
+    }
+}
+
+/// The result of comparing a value that identifies the release with the new installer
+#[derive(Clone, Debug, Display, PartialEq, Eq, Hash)]
+enum Check {
+    #[display("{field} `{value}` is unchanged")]
+    Unchanged { field: &'static str, value: String },
+    #[display("{field} changed from `{previous}` to `{new}`")]
+    Changed {
+        field: &'static str,
+        previous: String,
+        new: String,
+    },
+    #[display("{field} `{previous}` could not be read from the new installer")]
+    Unverified {
+        field: &'static str,
+        previous: String,
+    },
+}
+
+impl Check {
+    /// Compares a value of the manifest with the value read from the new installer, returning
+    /// `None` if the manifest does not have the value
+    fn new(field: &'static str, previous: Option<String>, new: Option<String>) -> Option<Self> {
+        let previous = previous?;
+        Some(match new {
+            Some(new) if new.eq_ignore_ascii_case(&previous) => Self::Unchanged {
+                field,
+                value: previous,
+            },
+            Some(new) => Self::Changed {
+                field,
+                previous,
+                new,
+            },
+            None => Self::Unverified { field, previous },
+        })
+    }
+}
+
+/// Picks the installer read from a file that best corresponds to an installer in the manifest, as
+/// a bundle or burn installer can contain several
+fn analysed_installer<'a>(
+    installer: &Installer,
+    analysed: &'a [Installer],
+) -> Option<&'a Installer> {
+    analysed.iter().max_by_key(|analysed| {
+        (
+            analysed.architecture == installer.architecture,
+            installer.scope.is_none() || analysed.scope == installer.scope,
+        )
+    })
+}
+
+/// Checks that the product code, package family name and display version of an installer, falling
+/// back to the root of the manifest, are the same in the installer that replaced it
+fn check_identity(
+    manifest: &InstallerManifest,
+    previous: &Installer,
+    new: &Installer,
+) -> Vec<Check> {
+    let display_version = |installer: &Installer| {
+        installer
+            .apps_and_features_entries
+            .iter()
+            .find_map(|entry| entry.display_version.as_ref())
+            .map(ToString::to_string)
+    };
+
+    [
+        Check::new(
+            "ProductCode",
+            previous
+                .product_code
+                .clone()
+                .or_else(|| manifest.product_code.clone()),
+            new.product_code.clone(),
+        ),
+        Check::new(
+            "PackageFamilyName",
+            previous
+                .package_family_name
+                .as_ref()
+                .or(manifest.package_family_name.as_ref())
+                .map(ToString::to_string),
+            new.package_family_name.as_ref().map(ToString::to_string),
+        ),
+        Check::new(
+            "DisplayVersion",
+            display_version(previous).or_else(|| {
+                manifest
+                    .apps_and_features_entries
+                    .iter()
+                    .find_map(|entry| entry.display_version.as_ref())
+                    .map(ToString::to_string)
+            }),
+            display_version(new),
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Combines installers that share a URL, as they were replaced by the same file
+fn merge_by_url(rehashed: Vec<Rehashed>) -> Vec<Rehashed> {
+    let mut merged = Vec::<Rehashed>::new();
+    let mut indices = HashMap::new();
+    for installer in rehashed {
+        if let Some(&index) = indices.get(&installer.url) {
+            let existing: &mut Rehashed = &mut merged[index];
+            for check in installer.checks {
+                if !existing.checks.contains(&check) {
+                    existing.checks.push(check);
+                }
+            }
+        } else {
+            indices.insert(installer.url.clone(), merged.len());
+            merged.push(installer);
+        }
+    }
+    merged
+}
+
+/// Explains in the pull request body which hashes changed and what was verified about the new
+/// installers
+fn verification_summary(rehashed: &[Rehashed]) -> String {
+    let mut summary = String::from("### Installers replaced at the same URL\n\n");
+    summary.push_str(
+        "The installers below were downloaded again and no longer match their hashes. Only the hashes have been updated.\n\n",
+    );
+    for Rehashed {
+        url,
+        sha_256: (previous_sha_256, new_sha_256),
+        signature_sha_256,
+        checks,
+    } in rehashed
+    {
+        let _ = writeln!(summary, "- {url}");
+        if previous_sha_256 != new_sha_256 {
+            let _ = writeln!(
+                summary,
+                "  - InstallerSha256 changed from `{previous_sha_256}` to `{new_sha_256}`"
+            );
+        }
+        if let Some((previous, new)) = signature_sha_256 {
+            let _ = writeln!(
+                summary,
+                "  - SignatureSha256 changed from `{previous}` to `{new}`"
+            );
+        }
+        if checks.is_empty() {
+            let _ = writeln!(
+                summary,
+                "  - The manifest has no ProductCode, PackageFamilyName or DisplayVersion to verify"
+            );
+        }
+        for check in checks {
+            let _ = writeln!(summary, "  - {check}");
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use sha2::{Digest, Sha256};
+    use winget_types::{
+        Sha256String,
+        installer::{AppsAndFeaturesEntry, Installer, InstallerManifest},
+    };
+
+    use super::{Check, Rehashed, check_identity, merge_by_url, verification_summary};
+
+    fn sha_256(data: &[u8]) -> Sha256String {
+        Sha256String::from_digest(&Sha256::digest(data))
+    }
+
+    #[test]
+    fn identity_checks() {
+        let manifest = InstallerManifest {
+            product_code: Some(String::from("{ABC}")),
+            ..InstallerManifest::default()
+        };
+        let previous = Installer {
+            apps_and_features_entries: vec![AppsAndFeaturesEntry {
+                display_version: Some("1.2.3".parse().unwrap()),
+                ..AppsAndFeaturesEntry::default()
+            }],
+            ..Installer::default()
+        };
+        let new = Installer {
+            product_code: Some(String::from("{abc}")),
+            apps_and_features_entries: vec![AppsAndFeaturesEntry {
+                display_version: Some("1.2.4".parse().unwrap()),
+                ..AppsAndFeaturesEntry::default()
+            }],
+            ..Installer::default()
+        };
+
+        assert_eq!(
+            check_identity(&manifest, &previous, &new),
+            [
+                Check::Unchanged {
+                    field: "ProductCode",
+                    value: String::from("{ABC}")
+                },
+                Check::Changed {
+                    field: "DisplayVersion",
+                    previous: String::from("1.2.3"),
+                    new: String::from("1.2.4")
+                }
+            ]
+        );
+        assert_eq!(
+            check_identity(&manifest, &previous, &Installer::default()),
+            [
+                Check::Unverified {
+                    field: "ProductCode",
+                    previous: String::from("{ABC}")
+                },
+                Check::Unverified {
+                    field: "DisplayVersion",
+                    previous: String::from("1.2.3")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn summary() {
+        let installer = |checks| Rehashed {
+            url: "https://example.com/setup.exe".parse().unwrap(),
+            sha_256: (sha_256(b"old"), sha_256(b"new")),
+            signature_sha_256: None,
+            checks,
+        };
+        let product_code = Check::Unchanged {
+            field: "ProductCode",
+            value: String::from("{ABC}"),
+        };
+        let rehashed = merge_by_url(vec![
+            installer(vec![product_code.clone()]),
+            installer(vec![product_code]),
+        ]);
+
+        assert_eq!(
+            verification_summary(&rehashed),
+            format!(
+                indoc! {"
+                    ### Installers replaced at the same URL
+
+                    The installers below were downloaded again and no longer match their hashes. Only the hashes have been updated.
+
+                    - https://example.com/setup.exe
+                      - InstallerSha256 changed from `{}` to `{}`
+                      - ProductCode `{{ABC}}` is unchanged
+                "},
+                sha_256(b"old"),
+                sha_256(b"new")
+            )
+        );
+    }
+}
@@ -1,13 +1,8 @@
-use std::{
-    collections::BTreeSet,
-    fmt::Write,
-    num::NonZeroUsize,
-    sync::Arc,
-    time::{Duration, Instant},
-};
+use std::{collections::BTreeSet, fmt::Write, fs, num::NonZeroUsize, sync::Arc, time::Duration};
 
 use anstream::println;
 use bon::builder;
+use camino::Utf8PathBuf;
 use chrono::TimeDelta;
 use clap::Parser;
 use color_eyre::{Result, eyre::Error};
@@ -15,42 +10,32 @@ use futures_util::{StreamExt, TryFutureExt, TryStreamExt, stream};
 use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use itertools::Itertools;
 use owo_colors::OwoColorize;
-use reqwest::{Client, StatusCode};
+use reqwest::Client;
+use serde::Serialize;
 use tokio::{sync::mpsc, time::sleep, try_join};
+use tracing::warn;
 use winget_types::{
-    ManifestTypeWithLocale, PackageIdentifier, PackageVersion, installer::InstallerManifest,
+    ManifestTypeWithLocale, PackageIdentifier, PackageVersion,
+    installer::{Installer, InstallerManifest},
     url::DecodedUrl,
 };
 
 use crate::{
-    commands::utils::SPINNER_SLOW_TICK_RATE,
+    commands::utils::{SPINNER_SLOW_TICK_RATE, TemplateArgs},
     credential::{get_default_headers, handle_token},
-    github::{github_client::GitHub, graphql::get_branches::PullRequestState},
+    github::{
+        github_client::GitHub, graphql::get_branches::PullRequestState,
+        rate_limit::MAX_PULL_REQUESTS_PER_HOUR,
+    },
     prompts::text::confirm_prompt,
+    url_check::{DeadReason, Health, UrlChecker, UrlStatus, version_health},
 };
 
-/// GitHub has an undocumented limit of 150 pull requests per hour
-///
-/// <https://github.com/cli/cli/issues/4801#issuecomment-1430651377>
-const MAX_PULL_REQUESTS_PER_HOUR: u8 = 150;
-
-/// Minimum delay to not go above 150 pull requests per hour
+/// Delay that spreads pull requests evenly to not go above 150 pull requests per hour
 const HOURLY_RATE_LIMIT_DELAY: Duration = Duration::from_secs(
     TimeDelta::hours(1).num_seconds().unsigned_abs() / MAX_PULL_REQUESTS_PER_HOUR as u64,
 );
 
-/// GitHub has an undocumented limit of 20 pull requests per minute
-///
-/// <https://github.com/cli/cli/issues/4801#issuecomment-1430651377>
-const MAX_PULL_REQUESTS_PER_MINUTE: u8 = 20;
-
-/// Minimum delay to not go above 20 pull requests per minute
-const PER_MINUTE_RATE_LIMIT_DELAY: Duration = Duration::from_secs(
-    TimeDelta::minutes(1).num_seconds().unsigned_abs() / MAX_PULL_REQUESTS_PER_MINUTE as u64,
-);
-
-const RESOURCE_MISSING_STATUS_CODES: [StatusCode; 2] = [StatusCode::NOT_FOUND, StatusCode::GONE];
-
 /*
 This command is hidden because it's mainly for moderation and could be misused.
 If you're reading this, feel free to use it, but please be mindful not to spam winget-pkgs
@@ -70,7 +55,8 @@ pub struct RemoveDeadVersions {
     #[arg(long)]
     after: Option<PackageVersion>,
 
-    /// Use the per-minute rate limit, potentially hitting the hourly rate limit in 7.5 minutes
+    /// Create pull requests as fast as the per-minute and hourly limits allow rather than spreading
+    /// them evenly across the hour
     #[arg(long, hide = true)]
     fast: bool,
 
@@ -78,10 +64,26 @@ pub struct RemoveDeadVersions {
     #[arg(long, hide = true, env = "CI")]
     auto: bool,
 
+    /// Fall back to a ranged GET request when HEAD is refused and treat error pages served in place
+    /// of an installer as dead
+    #[arg(long)]
+    deep: bool,
+
+    /// Download each installer and compare it with its InstallerSha256. Implies --deep
+    #[arg(long)]
+    verify_hash: bool,
+
+    /// Write whether each version is dead, changed or healthy to a JSON file
+    #[arg(long, value_hint = clap::ValueHint::FilePath)]
+    report: Option<Utf8PathBuf>,
+
     /// Number of versions to check concurrently
     #[arg(short, long, default_value_t = NonZeroUsize::new(num_cpus::get()).unwrap())]
     concurrent: NonZeroUsize,
 
+    #[command(flatten)]
+    pr_templates: TemplateArgs,
+
     /// GitHub personal access token with the `public_repo` scope
     #[arg(short, long, env = "GITHUB_TOKEN")]
     token: Option<String>,
@@ -90,6 +92,7 @@ pub struct RemoveDeadVersions {
 impl RemoveDeadVersions {
     pub async fn run(self) -> Result<()> {
         let token = handle_token(self.token.as_deref()).await?;
+        let pr_templates = self.pr_templates.templates()?;
         let github = GitHub::new(&token)?;
 
         let (fork, winget_pkgs, versions) = try_join!(
@@ -100,18 +103,19 @@ impl RemoveDeadVersions {
             github.get_versions(&self.package_identifier)
         )?;
 
-        let client = Client::builder()
-            .default_headers(get_default_headers(None))
-            .build()?;
-
-        let rate_limit_delay = if self.fast {
-            PER_MINUTE_RATE_LIMIT_DELAY
-        } else {
-            HOURLY_RATE_LIMIT_DELAY
-        };
+        let checker = UrlChecker::new(
+            Client::builder()
+                .default_headers(get_default_headers(None))
+                .build()?,
+            self.deep,
+            self.verify_hash,
+        );
 
-        // Set a default last PR time to before the rate limit delay to do the first PR immediately
-        let mut last_pr_time = Instant::now() - rate_limit_delay;
+        if !self.fast {
+            github
+                .pull_requests()
+                .set_min_interval(HOURLY_RATE_LIMIT_DELAY);
+        }
 
         let versions = versions
             .into_iter()
@@ -160,6 +164,7 @@ impl RemoveDeadVersions {
                         .github(&github)
                         .identifier(&package_identifier)
                         .version(&version)
+                        .summary(&dead_summary(&url_statuses))
                         .auto(self.auto)
                         .prompt()
                         .await?;
@@ -169,15 +174,13 @@ impl RemoveDeadVersions {
                         continue;
                     }
 
-                    let deletion_reason = Self::get_deletion_reason(&url_statuses)?;
+                    let deletion_reason = get_deletion_reason(&url_statuses)?;
 
-                    let time_since_last_pr = Instant::now().duration_since(last_pr_time);
-                    if time_since_last_pr < rate_limit_delay {
-                        let wait_time = rate_limit_delay - time_since_last_pr;
-                        let wait_pb = ProgressBar::new_spinner()
-                            .with_message(format!(
-                                "Last pull request was created {time_since_last_pr:?} ago. Waiting for {wait_time:?}",
-                            ));
+                    let wait_time = github.pull_requests().delay();
+                    if !wait_time.is_zero() {
+                        let wait_pb = ProgressBar::new_spinner().with_message(format!(
+                            "Waiting for {wait_time:?} before creating the next pull request",
+                        ));
                         wait_pb.enable_steady_tick(SPINNER_SLOW_TICK_RATE);
                         sleep(wait_time).await;
                         wait_pb.finish_and_clear();
@@ -190,11 +193,10 @@ impl RemoveDeadVersions {
                         .reason(deletion_reason)
                         .fork(&fork)
                         .winget_pkgs(&winget_pkgs)
+                        .templates(&pr_templates)
                         .send()
                         .await?;
 
-                    last_pr_time = Instant::now();
-
                     multi_progress.set_draw_target(ProgressDrawTarget::stderr());
                 }
 
@@ -203,20 +205,20 @@ impl RemoveDeadVersions {
         });
 
         let total = versions.len();
-        stream::iter(versions)
+        let reports = stream::iter(versions)
             .enumerate()
             .map(|(index, version)| {
                 let package_identifier = &package_identifier;
                 let github = &github;
                 let sender = &sender;
-                let client = &client;
+                let checker = &checker;
                 let overall_progress = &overall_progress;
                 let progress_bar = &progress_bars[index % self.concurrent.get()];
                 async move {
                     progress_bar
                         .set_message(format!("Checking {package_identifier} {}", version.blue()));
 
-                    let installer_urls = github
+                    let installers = github
                         .get_manifest::<InstallerManifest>(
                             package_identifier,
                             &version,
@@ -225,26 +227,38 @@ impl RemoveDeadVersions {
                         .await?
                         .installers
                         .into_iter()
-                        .map(|installer| installer.url)
-                        .unique();
-
-                    let url_statuses = stream::iter(installer_urls)
-                        .map(|url| {
-                            client
-                                .head((*url).clone())
-                                .send()
-                                .map_ok(|response| (url, response.status()))
+                        .unique_by(|installer| installer.url.clone());
+
+                    let url_statuses = stream::iter(installers)
+                        .map(|installer| async move {
+                            let status = checker.check(&installer.url, &installer.sha_256).await;
+                            (installer, status)
                         })
                         .buffered(2)
-                        .try_collect::<Vec<(_, _)>>()
-                        .await?;
-
-                    let all_installers_missing = url_statuses
-                        .iter()
-                        .all(|(_url, status)| RESOURCE_MISSING_STATUS_CODES.contains(status));
-
-                    if all_installers_missing {
-                        sender.send((version, url_statuses)).await?;
+                        .collect::<Vec<(_, _)>>()
+                        .await;
+
+                    let health = version_health(url_statuses.iter().map(|(_, status)| status));
+                    let report = VersionReport::new(&version, health, &url_statuses);
+
+                    match health {
+                        Health::Dead => {
+                            let url_statuses = url_statuses
+                                .into_iter()
+                                .filter_map(|(installer, status)| match status {
+                                    UrlStatus::Dead(reason) => Some((installer.url, reason)),
+                                    _ => None,
+                                })
+                                .collect();
+                            sender.send((version, url_statuses)).await?;
+                        }
+                        Health::Changed => warn!(
+                            "{package_identifier} {version} has installers that no longer match their InstallerSha256"
+                        ),
+                        Health::Unknown => warn!(
+                            "{package_identifier} {version} has installers that could not be checked, so it is not removed"
+                        ),
+                        Health::Healthy => {}
                     }
 
                     overall_progress.inc(1);
@@ -254,11 +268,11 @@ impl RemoveDeadVersions {
                         progress_bar.finish_and_clear();
                     }
 
-                    Ok::<_, color_eyre::Report>(())
+                    Ok::<_, color_eyre::Report>(report)
                 }
             })
             .buffered(self.concurrent.get())
-            .try_collect::<()>()
+            .try_collect::<Vec<_>>()
             .await?;
 
         drop(sender);
@@ -266,28 +280,93 @@ impl RemoveDeadVersions {
 
         multi_progress.clear()?;
 
+        let count = |health| {
+            reports
+                .iter()
+                .filter(|report| report.status == health)
+                .count()
+        };
+        println!(
+            "Checked {total} versions: {} dead, {} changed, {} unknown, {} healthy",
+            count(Health::Dead).red(),
+            count(Health::Changed).yellow(),
+            count(Health::Unknown).yellow(),
+            count(Health::Healthy).green()
+        );
+
+        if let Some(path) = self.report {
+            fs::write(&path, serde_json::to_string_pretty(&reports)?)?;
+            println!("{} wrote the report to {path}", "Successfully".green());
+        }
+
         Ok(())
     }
+}
 
-    fn get_deletion_reason(url_statuses: &[(DecodedUrl, StatusCode)]) -> Result<String> {
-        let mut deletion_reason = String::from("All InstallerUrls returned ");
-        if let Ok(status) = url_statuses
-            .iter()
-            .map(|(_url, status)| status)
-            .all_equal_value()
-        {
-            writeln!(&mut deletion_reason, "`{status}`")?;
-            for (url, _status) in url_statuses {
-                writeln!(&mut deletion_reason, "- {url}")?;
-            }
-        } else {
-            deletion_reason.push_str("missing resources");
-            for (url, status) in url_statuses {
-                writeln!(&mut deletion_reason, "- {url} - `{status}`")?;
-            }
+#[derive(Serialize)]
+struct VersionReport {
+    version: PackageVersion,
+    status: Health,
+    installers: Vec<InstallerReport>,
+}
+
+#[derive(Serialize)]
+struct InstallerReport {
+    url: DecodedUrl,
+    status: Health,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl VersionReport {
+    fn new(
+        version: &PackageVersion,
+        status: Health,
+        url_statuses: &[(Installer, UrlStatus)],
+    ) -> Self {
+        Self {
+            version: version.clone(),
+            status,
+            installers: url_statuses
+                .iter()
+                .map(|(installer, status)| InstallerReport {
+                    url: installer.url.clone(),
+                    status: status.health(),
+                    detail: status.detail(&installer.sha_256),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Describes why all the installer URLs of a version are dead, or `missing resources` if they
+/// differ
+fn dead_summary(url_statuses: &[(DecodedUrl, DeadReason)]) -> String {
+    url_statuses
+        .iter()
+        .map(|(_url, reason)| reason)
+        .all_equal_value()
+        .map_or_else(|_| String::from("missing resources"), ToString::to_string)
+}
+
+fn get_deletion_reason(url_statuses: &[(DecodedUrl, DeadReason)]) -> Result<String> {
+    let mut deletion_reason = String::from("All InstallerUrls returned ");
+    if let Ok(reason) = url_statuses
+        .iter()
+        .map(|(_url, reason)| reason)
+        .all_equal_value()
+    {
+        writeln!(&mut deletion_reason, "{reason}")?;
+        for (url, _reason) in url_statuses {
+            writeln!(&mut deletion_reason, "- {url}")?;
+        }
+    } else {
+        deletion_reason.push_str("missing resources");
+        for (url, reason) in url_statuses {
+            writeln!(&mut deletion_reason, "- {url} - {reason}")?;
         }
-        Ok(deletion_reason)
     }
+    Ok(deletion_reason)
 }
 
 #[builder(finish_fn = prompt)]
@@ -295,6 +374,7 @@ async fn confirm_removal(
     github: &GitHub,
     identifier: &PackageIdentifier,
     version: &PackageVersion,
+    summary: &str,
     auto: bool,
 ) -> Result<bool> {
     if let Some(pull_request) = github
@@ -304,7 +384,7 @@ async fn confirm_removal(
         if pull_request.state == PullRequestState::Open {
             println!(
                 "{identifier} {version} returned {} in all its InstallerUrls but there is already {} pull request for this version that was created on {} at {}.",
-                StatusCode::NOT_FOUND.red(),
+                summary.red(),
                 pull_request.state,
                 pull_request.created_at.date_naive(),
                 pull_request.created_at.time()
@@ -320,6 +400,6 @@ async fn confirm_removal(
     Ok(auto
         || confirm_prompt(&format!(
             "{identifier} {version} returned {} in all its InstallerUrls. Remove?",
-            StatusCode::NOT_FOUND.red()
+            summary.red()
         ))?)
 }
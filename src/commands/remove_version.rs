@@ -13,6 +13,7 @@ use tokio::try_join;
 use winget_types::{PackageIdentifier, PackageVersion};
 
 use crate::{
+    commands::utils::TemplateArgs,
     credential::handle_token,
     github::github_client::{GitHub, WINGET_PKGS_FULL_NAME},
     prompts::{handle_inquire_error, text::confirm_prompt},
@@ -49,6 +50,9 @@ pub struct RemoveVersion {
     #[arg(long, env = "OPEN_PR")]
     open_pr: bool,
 
+    #[command(flatten)]
+    pr_templates: TemplateArgs,
+
     /// GitHub personal access token with the `public_repo` scope
     #[arg(short, long, env = "GITHUB_TOKEN")]
     token: Option<String>,
@@ -60,6 +64,7 @@ impl RemoveVersion {
 
     pub async fn run(self) -> Result<()> {
         let token = handle_token(self.token.as_deref()).await?;
+        let pr_templates = self.pr_templates.templates()?;
         if !self.no_warning {
             println!(
                 "{}",
@@ -117,6 +122,7 @@ impl RemoveVersion {
             .reason(deletion_reason)
             .fork(&fork)
             .winget_pkgs(&winget_pkgs)
+            .templates(&pr_templates)
             .maybe_issue_resolves(self.resolves)
             .send()
             .await?;
@@ -0,0 +1,446 @@
+mod report;
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    num::NonZeroUsize,
+};
+
+use anstream::println;
+use camino::Utf8PathBuf;
+use clap::{Args, Parser};
+use color_eyre::{
+    Result,
+    eyre::{WrapErr, bail},
+};
+use futures_util::{StreamExt, TryStreamExt, stream};
+use indicatif::{ProgressBar, ProgressStyle};
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+use regex::{Regex, RegexBuilder};
+use reqwest::Client;
+use winget_types::{
+    ManifestTypeWithLocale, ManifestVersion, PackageIdentifier, PackageVersion,
+    installer::InstallerManifest,
+};
+
+use crate::{
+    commands::scan::report::{
+        Issue, IssueKind, PackageReport, ReportFormat, VersionReport, render,
+    },
+    credential::{get_default_headers, handle_token},
+    github::github_client::GitHub,
+    url_check::{Health, UrlChecker, UrlStatus, version_health},
+};
+
+/// Check the installer URLs, hashes, Apps and Features entries and schema versions of many packages
+///
+/// This only reports issues. Removing or fixing versions is left to commands such as
+/// `remove-dead-versions`. The scan fails if the health of any version could not be determined.
+#[derive(Parser)]
+pub struct Scan {
+    #[command(flatten)]
+    packages: PackageSelection,
+
+    /// Fall back to a ranged GET request when HEAD is refused and treat error pages served in place
+    /// of an installer as dead
+    #[arg(long)]
+    deep: bool,
+
+    /// Download each installer and compare it with its InstallerSha256. Implies --deep
+    #[arg(long)]
+    verify_hash: bool,
+
+    /// File to write the report to. Without it, a Markdown report is printed
+    #[arg(short, long, value_hint = clap::ValueHint::FilePath)]
+    output: Option<Utf8PathBuf>,
+
+    /// Format of the report. Defaults to the extension of the output file, or JSON
+    #[arg(long, value_enum)]
+    format: Option<ReportFormat>,
+
+    /// File to record each scanned package in, so that an interrupted scan can be resumed by
+    /// passing the same file again
+    #[arg(long, value_hint = clap::ValueHint::FilePath)]
+    progress_file: Option<Utf8PathBuf>,
+
+    /// Number of packages to check concurrently
+    #[arg(short, long, default_value_t = NonZeroUsize::new(num_cpus::get()).unwrap())]
+    concurrent: NonZeroUsize,
+
+    /// GitHub personal access token with the `public_repo` scope
+    #[arg(short, long, env = "GITHUB_TOKEN")]
+    token: Option<String>,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct PackageSelection {
+    /// The identifiers of the packages to check
+    #[arg()]
+    package_identifiers: Vec<PackageIdentifier>,
+
+    /// Check every package whose identifier starts with a publisher, such as `Contoso`
+    #[arg(long)]
+    publisher: Option<String>,
+
+    /// Check every package whose identifier matches a glob that starts with a publisher, such as
+    /// `Contoso.Tools.*`
+    #[arg(long)]
+    glob: Option<String>,
+}
+
+impl Scan {
+    pub async fn run(self) -> Result<()> {
+        let token = handle_token(self.token.as_deref()).await?;
+        let github = GitHub::new(&token)?;
+
+        let packages = self.packages.resolve(&github).await?;
+        let checker = UrlChecker::new(
+            Client::builder()
+                .default_headers(get_default_headers(None))
+                .build()?,
+            self.deep,
+            self.verify_hash,
+        );
+
+        let mut reports = match &self.progress_file {
+            Some(path) if path.exists() => read_progress(BufReader::new(File::open(path)?))
+                .wrap_err_with(|| format!("Failed to read the progress file {path}"))?,
+            _ => Vec::new(),
+        };
+        let scanned = reports
+            .iter()
+            .map(|report| report.identifier.clone())
+            .collect::<HashSet<_>>();
+        if !scanned.is_empty() {
+            println!(
+                "Resuming from {} packages that were already scanned",
+                scanned.len()
+            );
+        }
+        let packages = packages
+            .into_iter()
+            .filter(|(identifier, _versions)| !scanned.contains(identifier))
+            .collect::<Vec<_>>();
+
+        let mut progress_file = self
+            .progress_file
+            .as_deref()
+            .map(|path| OpenOptions::new().create(true).append(true).open(path))
+            .transpose()?;
+
+        let progress = ProgressBar::new(packages.len() as u64).with_style(
+            ProgressStyle::default_bar()
+                .template("{wide_bar:.magenta/black} {human_pos}/{human_len} {msg}")?
+                .progress_chars("───"),
+        );
+
+        let mut scans = stream::iter(packages)
+            .map(|(identifier, versions)| {
+                let github = &github;
+                let checker = &checker;
+                async move { scan_package(github, checker, identifier, versions).await }
+            })
+            .buffer_unordered(self.concurrent.get());
+
+        while let Some(report) = scans.try_next().await? {
+            progress.set_message(report.identifier.to_string());
+            progress.inc(1);
+            if let Some(file) = &mut progress_file {
+                writeln!(file, "{}", serde_json::to_string(&report)?)?;
+                file.flush()?;
+            }
+            reports.push(report);
+        }
+        progress.finish_and_clear();
+
+        reports.sort_by(|a, b| a.identifier.cmp(&b.identifier));
+
+        let issues = reports
+            .iter()
+            .flat_map(|report| &report.versions)
+            .map(|version| version.issues.len())
+            .sum::<usize>();
+        println!(
+            "Scanned {} packages and found {}",
+            reports.len(),
+            if issues == 0 {
+                "no issues".green().to_string()
+            } else {
+                format!("{issues} issues").yellow().to_string()
+            }
+        );
+
+        let unknown = reports
+            .iter()
+            .flat_map(|report| &report.versions)
+            .filter(|version| version.status == Health::Unknown)
+            .count();
+
+        if let Some(output) = self.output {
+            let format = self
+                .format
+                .unwrap_or_else(|| ReportFormat::from_path(&output));
+            fs::write(&output, render(&reports, format)?)?;
+            println!("{} wrote the report to {output}", "Successfully".green());
+        } else {
+            println!(
+                "{}",
+                render(&reports, self.format.unwrap_or(ReportFormat::Markdown))?
+            );
+        }
+
+        if unknown != 0 {
+            bail!("{unknown} versions could not be checked");
+        }
+
+        Ok(())
+    }
+}
+
+impl PackageSelection {
+    async fn resolve(
+        &self,
+        github: &GitHub,
+    ) -> Result<BTreeMap<PackageIdentifier, BTreeSet<PackageVersion>>> {
+        if let Some(publisher) = &self.publisher {
+            return Ok(github.get_publisher_packages(publisher).await?);
+        }
+
+        if let Some(glob) = &self.glob {
+            let publisher = glob_publisher(glob)?;
+            let pattern = glob_regex(glob)?;
+            let packages = github
+                .get_publisher_packages(publisher)
+                .await?
+                .into_iter()
+                .filter(|(identifier, _versions)| pattern.is_match(identifier.as_str()))
+                .collect::<BTreeMap<_, _>>();
+            if packages.is_empty() {
+                bail!("No packages match {glob}");
+            }
+            return Ok(packages);
+        }
+
+        stream::iter(&self.package_identifiers)
+            .then(|identifier| async move {
+                Ok((identifier.clone(), github.get_versions(identifier).await?))
+            })
+            .try_collect()
+            .await
+    }
+}
+
+/// Returns the publisher that a glob starts with, so that only its part of the tree is listed
+fn glob_publisher(glob: &str) -> Result<&str> {
+    let publisher = glob
+        .split_once('.')
+        .map_or(glob, |(publisher, _rest)| publisher);
+    if publisher.is_empty() || publisher.contains(['*', '?']) {
+        bail!("The glob must start with a publisher, such as Contoso.*, but found {glob}");
+    }
+    Ok(publisher)
+}
+
+/// Converts a glob where `*` matches any characters and `?` matches one character into a
+/// case-insensitive regex, as package identifiers are case-insensitive
+fn glob_regex(glob: &str) -> Result<Regex> {
+    let pattern = glob
+        .split('*')
+        .map(|part| part.split('?').map(regex::escape).join("."))
+        .join(".*");
+    RegexBuilder::new(&format!("^{pattern}$"))
+        .case_insensitive(true)
+        .build()
+        .wrap_err_with(|| format!("Invalid glob {glob}"))
+}
+
+/// Reads the packages that were scanned before, leaving out any with a version whose health could
+/// not be determined so that they are scanned again
+fn read_progress(reader: impl BufRead) -> Result<Vec<PackageReport>> {
+    reader
+        .lines()
+        .filter(|line| line.as_ref().is_ok_and(|line| !line.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str::<PackageReport>(&line?)?))
+        .filter_ok(|report| {
+            report
+                .versions
+                .iter()
+                .all(|version| version.status != Health::Unknown)
+        })
+        .collect()
+}
+
+async fn scan_package(
+    github: &GitHub,
+    checker: &UrlChecker,
+    identifier: PackageIdentifier,
+    versions: BTreeSet<PackageVersion>,
+) -> Result<PackageReport> {
+    let mut reports = Vec::with_capacity(versions.len());
+    let mut display_versions = BTreeMap::<String, Vec<PackageVersion>>::new();
+
+    for version in versions {
+        let manifest = match github
+            .get_manifest::<InstallerManifest>(
+                &identifier,
+                &version,
+                ManifestTypeWithLocale::Installer,
+            )
+            .await
+        {
+            Ok(manifest) => manifest,
+            Err(error) => {
+                reports.push(VersionReport {
+                    version,
+                    status: Health::Unknown,
+                    issues: vec![Issue {
+                        kind: IssueKind::Manifest,
+                        url: None,
+                        message: format!("Failed to read the installer manifest: {error}"),
+                    }],
+                });
+                continue;
+            }
+        };
+
+        let mut issues = Vec::new();
+
+        let latest = ManifestVersion::default();
+        if manifest.manifest_version < latest {
+            issues.push(Issue {
+                kind: IssueKind::Schema,
+                url: None,
+                message: format!(
+                    "Uses schema {}, the latest is {latest}",
+                    manifest.manifest_version
+                ),
+            });
+        }
+
+        for display_version in manifest
+            .installers
+            .iter()
+            .flat_map(|installer| &installer.apps_and_features_entries)
+            .chain(&manifest.apps_and_features_entries)
+            .filter_map(|entry| entry.display_version.as_ref())
+            .map(ToString::to_string)
+            .unique()
+        {
+            display_versions
+                .entry(display_version)
+                .or_default()
+                .push(version.clone());
+        }
+
+        let statuses = stream::iter(
+            manifest
+                .installers
+                .into_iter()
+                .unique_by(|installer| installer.url.clone()),
+        )
+        .map(|installer| async move {
+            let status = checker.check(&installer.url, &installer.sha_256).await;
+            (installer, status)
+        })
+        .buffered(2)
+        .collect::<Vec<_>>()
+        .await;
+
+        let mut url_statuses = Vec::with_capacity(statuses.len());
+        for (installer, status) in statuses {
+            if let Some(message) = status.detail(&installer.sha_256) {
+                issues.push(Issue {
+                    kind: match status {
+                        UrlStatus::Changed { .. } => IssueKind::Hash,
+                        _ => IssueKind::Url,
+                    },
+                    url: Some(installer.url),
+                    message,
+                });
+            }
+            url_statuses.push(status);
+        }
+
+        reports.push(VersionReport {
+            version,
+            status: version_health(&url_statuses),
+            issues,
+        });
+    }
+
+    // Apps and Features entries of different versions must not claim the same display version
+    for (display_version, versions) in display_versions {
+        if versions.len() < 2 {
+            continue;
+        }
+        for report in &mut reports {
+            if versions.contains(&report.version) {
+                report.issues.push(Issue {
+                    kind: IssueKind::Arp,
+                    url: None,
+                    message: format!(
+                        "DisplayVersion {display_version} is also used by {}",
+                        versions
+                            .iter()
+                            .filter(|version| **version != report.version)
+                            .join(", ")
+                    ),
+                });
+            }
+        }
+    }
+
+    Ok(PackageReport {
+        identifier,
+        versions: reports,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rstest::rstest;
+
+    use super::{glob_publisher, glob_regex, read_progress};
+
+    #[rstest]
+    #[case("Contoso.*", "Contoso.App", true)]
+    #[case("Contoso.*", "contoso.app", true)]
+    #[case("Contoso.Tools.*", "Contoso.App", false)]
+    #[case("Contoso.App?", "Contoso.App2", true)]
+    #[case("Contoso.App?", "Contoso.App", false)]
+    #[case("Contoso.A+B", "Contoso.A+B", true)]
+    fn glob_matching(#[case] glob: &str, #[case] identifier: &str, #[case] matches: bool) {
+        assert_eq!(glob_regex(glob).unwrap().is_match(identifier), matches);
+    }
+
+    #[rstest]
+    #[case("Contoso.*", Some("Contoso"))]
+    #[case("Contoso", Some("Contoso"))]
+    #[case("*.App", None)]
+    #[case("Cont*.App", None)]
+    fn publisher_from_glob(#[case] glob: &str, #[case] publisher: Option<&str>) {
+        assert_eq!(glob_publisher(glob).ok(), publisher);
+    }
+
+    #[test]
+    fn progress_skips_unknown_health() {
+        const PROGRESS: &str = indoc! {r#"
+            {"identifier":"Contoso.App","versions":[{"version":"1.0","status":"healthy","issues":[]}]}
+            {"identifier":"Contoso.Tool","versions":[{"version":"1.0","status":"healthy","issues":[]},{"version":"2.0","status":"unknown","issues":[]}]}
+
+        "#};
+
+        let reports = read_progress(PROGRESS.as_bytes()).unwrap();
+        assert_eq!(
+            reports
+                .iter()
+                .map(|report| report.identifier.as_str())
+                .collect::<Vec<_>>(),
+            ["Contoso.App"]
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt::Write;
+
+use camino::Utf8Path;
+use clap::ValueEnum;
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use winget_types::{PackageIdentifier, PackageVersion, url::DecodedUrl};
+
+use crate::url_check::Health;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl ReportFormat {
+    /// Picks the format from the extension of the report file, defaulting to JSON
+    pub fn from_path(path: &Utf8Path) -> Self {
+        match path.extension().map(str::to_ascii_lowercase).as_deref() {
+            Some("csv") => Self::Csv,
+            Some("md" | "markdown") => Self::Markdown,
+            _ => Self::Json,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Display, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueKind {
+    /// The installer URL is dead
+    #[display("url")]
+    Url,
+    /// The installer URL serves a file with a different hash
+    #[display("hash")]
+    Hash,
+    /// The Apps and Features entries conflict with another version
+    #[display("arp")]
+    Arp,
+    /// The manifests use an older schema version
+    #[display("schema")]
+    Schema,
+    /// The manifests could not be read
+    #[display("manifest")]
+    Manifest,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Issue {
+    pub kind: IssueKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<DecodedUrl>,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VersionReport {
+    pub version: PackageVersion,
+    pub status: Health,
+    pub issues: Vec<Issue>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PackageReport {
+    pub identifier: PackageIdentifier,
+    pub versions: Vec<VersionReport>,
+}
+
+impl PackageReport {
+    fn issues(&self) -> impl Iterator<Item = (&PackageVersion, &Issue)> {
+        self.versions.iter().flat_map(|version| {
+            version
+                .issues
+                .iter()
+                .map(move |issue| (&version.version, issue))
+        })
+    }
+}
+
+pub fn render(reports: &[PackageReport], format: ReportFormat) -> serde_json::Result<String> {
+    Ok(match format {
+        ReportFormat::Json => serde_json::to_string_pretty(reports)?,
+        ReportFormat::Csv => render_csv(reports),
+        ReportFormat::Markdown => render_markdown(reports),
+    })
+}
+
+fn render_csv(reports: &[PackageReport]) -> String {
+    let mut csv = String::from("identifier,version,status,kind,url,message\n");
+    for report in reports {
+        for version in &report.versions {
+            for issue in &version.issues {
+                let _ = writeln!(
+                    csv,
+                    "{},{},{},{},{},{}",
+                    csv_field(report.identifier.as_str()),
+                    csv_field(&version.version.to_string()),
+                    version.status,
+                    issue.kind,
+                    csv_field(issue.url.as_ref().map_or("", |url| url.as_str())),
+                    csv_field(&issue.message)
+                );
+            }
+        }
+    }
+    csv
+}
+
+/// Quotes a CSV field if it contains a comma, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn render_markdown(reports: &[PackageReport]) -> String {
+    let versions = reports.iter().flat_map(|report| &report.versions);
+    let count = |status| {
+        versions
+            .clone()
+            .filter(|version| version.status == status)
+            .count()
+    };
+
+    let mut markdown = String::from("# Package health report\n\n");
+    let _ = writeln!(
+        markdown,
+        "Checked {} packages and {} versions: {} dead, {} changed, {} unknown and {} healthy.\n",
+        reports.len(),
+        versions.clone().count(),
+        count(Health::Dead),
+        count(Health::Changed),
+        count(Health::Unknown),
+        count(Health::Healthy)
+    );
+
+    if reports
+        .iter()
+        .all(|report| report.issues().next().is_none())
+    {
+        markdown.push_str("No issues were found.\n");
+        return markdown;
+    }
+
+    markdown.push_str("| Package | Version | Check | URL | Details |\n");
+    markdown.push_str("|---------|---------|-------|-----|---------|\n");
+    for report in reports {
+        for (version, issue) in report.issues() {
+            let _ = writeln!(
+                markdown,
+                "| {} | {version} | {} | {} | {} |",
+                report.identifier,
+                issue.kind,
+                issue.url.as_ref().map_or("", |url| url.as_str()),
+                issue.message.replace('|', "\\|").replace('\n', " ")
+            );
+        }
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::{Issue, IssueKind, PackageReport, ReportFormat, VersionReport, csv_field, render};
+    use crate::url_check::Health;
+
+    fn reports() -> Vec<PackageReport> {
+        vec![PackageReport {
+            identifier: "Contoso.App".parse().unwrap(),
+            versions: vec![
+                VersionReport {
+                    version: "1.0.0".parse().unwrap(),
+                    status: Health::Dead,
+                    issues: vec![Issue {
+                        kind: IssueKind::Url,
+                        url: Some("https://example.com/setup.exe".parse().unwrap()),
+                        message: String::from("Returned `404 Not Found`"),
+                    }],
+                },
+                VersionReport {
+                    version: "2.0.0".parse().unwrap(),
+                    status: Health::Healthy,
+                    issues: vec![Issue {
+                        kind: IssueKind::Schema,
+                        url: None,
+                        message: String::from("Uses schema 1.6.0, the latest is 1.10.0"),
+                    }],
+                },
+            ],
+        }]
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            render(&reports(), ReportFormat::Csv).unwrap(),
+            indoc! {"
+                identifier,version,status,kind,url,message
+                Contoso.App,1.0.0,dead,url,https://example.com/setup.exe,Returned `404 Not Found`
+                Contoso.App,2.0.0,healthy,schema,,\"Uses schema 1.6.0, the latest is 1.10.0\"
+            "}
+        );
+        assert_eq!(csv_field(r#"a "b""#), r#""a ""b""""#);
+    }
+
+    #[test]
+    fn markdown() {
+        assert_eq!(
+            render(&reports(), ReportFormat::Markdown).unwrap(),
+            indoc! {"
+                # Package health report
+
+                Checked 1 packages and 2 versions: 1 dead, 0 changed, 0 unknown and 1 healthy.
+
+                | Package | Version | Check | URL | Details |
+                |---------|---------|-------|-----|---------|
+                | Contoso.App | 1.0.0 | url | https://example.com/setup.exe | Returned `404 Not Found` |
+                | Contoso.App | 2.0.0 | schema |  | Uses schema 1.6.0, the latest is 1.10.0 |
+            "}
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let json = render(&reports(), ReportFormat::Json).unwrap();
+        let reports = serde_json::from_str::<Vec<PackageReport>>(&json).unwrap();
+        assert_eq!(reports[0].versions[0].issues[0].kind, IssueKind::Url);
+    }
+}
@@ -9,14 +9,20 @@ use inquire::Select;
 use itertools::Itertools;
 use owo_colors::OwoColorize;
 use walkdir::WalkDir;
-use winget_types::{GenericManifest, ManifestType};
 
 use crate::{
-    commands::utils::{SPINNER_TICK_RATE, SubmitOption},
-    credential::handle_token,
+    commands::utils::{
+        SPINNER_TICK_RATE, SubmitOption, TemplateArgs, prompt_existing_pull_request,
+        pull_request_to_amend,
+    },
+    credential::{GitHubToken, handle_token},
     github::{
-        github_client::{GitHub, WINGET_PKGS_FULL_NAME},
-        utils::{PackagePath, pull_request::pr_changes},
+        github_client::{GitHub, PackageChanges, WINGET_PKGS_FULL_NAME},
+        utils::{
+            PackagePath,
+            pull_request::pr_changes,
+            template::{PullRequestTemplates, installer_summary},
+        },
     },
     manifests::{Manifests, manifest::Manifest},
     prompts::handle_inquire_error,
@@ -36,6 +42,9 @@ pub struct Submit {
     #[arg(long)]
     resolves: Option<Vec<NonZeroU32>>,
 
+    #[command(flatten)]
+    pr_templates: TemplateArgs,
+
     /// Open pull request link automatically
     #[arg(long, env = "OPEN_PR")]
     open_pr: bool,
@@ -44,6 +53,18 @@ pub struct Submit {
     #[arg(long, env = "DRY_RUN")]
     dry_run: bool,
 
+    /// Push the manifests to the open pull request for this version instead of creating a new one
+    #[arg(long)]
+    amend_pr: bool,
+
+    /// Comment to add to the pull request after amending it
+    #[arg(long, requires = "amend_pr")]
+    amend_comment: Option<String>,
+
+    /// Submit every package and version found in the path in a single pull request
+    #[arg(long, conflicts_with = "amend_pr")]
+    single_pr: bool,
+
     /// GitHub personal access token with the `public_repo` scope
     #[arg(short, long, env = "GITHUB_TOKEN")]
     token: Option<String>,
@@ -52,6 +73,7 @@ pub struct Submit {
 impl Submit {
     pub async fn run(self) -> Result<()> {
         let token = handle_token(self.token.as_deref());
+        let pr_templates = self.pr_templates.templates()?;
 
         let yaml_entries = self.get_yaml_file_paths()?;
 
@@ -61,19 +83,7 @@ impl Submit {
                 // Read file to string so we can read it twice - once for the manifest type and
                 // second for the full manifest
                 let manifest = io::read_to_string(File::open(path)?)?;
-
-                // Deserialize the manifest into just the manifest type so that it can be determined
-                // which manifest to properly deserialize into
-                let manifest = match serde_yaml::from_str::<GenericManifest>(&manifest)?.r#type {
-                    ManifestType::Installer => {
-                        Manifest::Installer(serde_yaml::from_str(&manifest)?)
-                    }
-                    ManifestType::DefaultLocale => {
-                        Manifest::DefaultLocale(serde_yaml::from_str(&manifest)?)
-                    }
-                    ManifestType::Locale => Manifest::Locale(serde_yaml::from_str(&manifest)?),
-                    ManifestType::Version => Manifest::Version(serde_yaml::from_str(&manifest)?),
-                };
+                let manifest = Manifest::from_yaml(&manifest)?;
                 Ok::<Manifest, eyre::Error>(manifest)
             })
             .chunk_by(|manifest| {
@@ -84,34 +94,13 @@ impl Submit {
                 )
             })
             .into_iter()
-            .filter_map(|(_, manifests)| {
-                // Now that we solely have manifests related to a package and its version, we can
-                // rebuild it into a Manifests struct
-                let mut installer = None;
-                let mut default_locale = None;
-                let mut locales = Vec::new();
-                let mut version = None;
-                for manifest in manifests {
-                    match manifest {
-                        Manifest::Installer(installer_manifest) => {
-                            installer = Some(installer_manifest);
-                        }
-                        Manifest::DefaultLocale(default_locale_manifest) => {
-                            default_locale = Some(default_locale_manifest);
-                        }
-                        Manifest::Locale(locale) => locales.push(locale),
-                        Manifest::Version(version_manifest) => version = Some(version_manifest),
-                    }
-                }
-                Some(Manifests {
-                    installer: installer?,
-                    default_locale: default_locale?,
-                    locales,
-                    version: version?,
-                })
-            })
+            .filter_map(|(_, manifests)| Manifests::from_manifests(manifests))
             .collect::<Vec<_>>();
 
+        if self.single_pr && packages.len() > 1 {
+            return self.submit_single_pr(packages, token, &pr_templates).await;
+        }
+
         // If there's only one package, use that. Otherwise, prompt for which package to submit
         let manifests = match packages.iter_mut().at_most_one() {
             Ok(None) => {
@@ -147,6 +136,7 @@ impl Submit {
             version,
             self.skip_prompt,
             self.dry_run,
+            None,
         )?;
 
         if submit_option == SubmitOption::Exit {
@@ -154,6 +144,46 @@ impl Submit {
         }
 
         let github = GitHub::new(&token.await?)?;
+
+        if self.amend_pr {
+            let pull_request = pull_request_to_amend(
+                identifier,
+                version,
+                github
+                    .get_pull_request_to_amend(identifier, version)
+                    .await?,
+            )?;
+
+            let pr_progress = ProgressBar::new_spinner().with_message(format!(
+                "Amending the pull request for {identifier} version {version}",
+            ));
+            pr_progress.enable_steady_tick(SPINNER_TICK_RATE);
+
+            let pull_request_url = github
+                .amend_pull_request()
+                .identifier(identifier)
+                .version(version)
+                .pull_request(&pull_request)
+                .changes(changes)
+                .maybe_comment(self.amend_comment.as_deref())
+                .send()
+                .await?;
+
+            pr_progress.finish_and_clear();
+
+            println!(
+                "{} amended the {} to {WINGET_PKGS_FULL_NAME}",
+                "Successfully".green(),
+                "pull request".hyperlink(&pull_request_url)
+            );
+
+            if self.open_pr {
+                open::that(pull_request_url.as_str())?;
+            }
+
+            return Ok(());
+        }
+
         let versions = github.get_versions(identifier).await.unwrap_or_default();
 
         // Create an indeterminate progress bar to show as a pull request is being created
@@ -169,6 +199,8 @@ impl Submit {
             .versions(&versions)
             .changes(changes)
             .maybe_issue_resolves(self.resolves)
+            .templates(&pr_templates)
+            .installers(&installer_summary(&manifests.installer))
             .send()
             .await?;
 
@@ -187,6 +219,108 @@ impl Submit {
         Ok(())
     }
 
+    async fn submit_single_pr(
+        &self,
+        mut packages: Vec<Manifests>,
+        token: impl Future<Output = Result<GitHubToken>>,
+        pr_templates: &PullRequestTemplates,
+    ) -> Result<()> {
+        let mut submissions = Vec::with_capacity(packages.len());
+        for manifests in &mut packages {
+            let identifier = &manifests.version.package_identifier;
+            let version = &manifests.version.package_version;
+
+            // Reorder the keys in case the manifests weren't created by komac
+            manifests.installer.optimize();
+
+            let package_path = PackagePath::new(identifier, Some(version), None);
+            let mut changes = pr_changes()
+                .package_identifier(identifier)
+                .manifests(manifests)
+                .package_path(&package_path)
+                .create()?;
+
+            let submit_option = SubmitOption::prompt(
+                &mut changes,
+                identifier,
+                version,
+                self.skip_prompt,
+                self.dry_run,
+                None,
+            )?;
+
+            // Show the changes of every package in a dry run
+            if submit_option == SubmitOption::Exit && !self.dry_run {
+                return Ok(());
+            }
+
+            submissions.push(PackageChanges {
+                identifier: identifier.clone(),
+                version: version.clone(),
+                versions: None,
+                changes,
+                replace_version: None,
+                installers: Some(installer_summary(&manifests.installer)),
+            });
+        }
+
+        if self.dry_run {
+            return Ok(());
+        }
+
+        let github = GitHub::new(&token.await?)?;
+
+        for package in &mut submissions {
+            let (existing_pr, versions) = tokio::join!(
+                github.get_existing_pull_request(&package.identifier, &package.version),
+                github.get_versions(&package.identifier)
+            );
+            if let Some(pull_request) = existing_pr?
+                && !prompt_existing_pull_request(
+                    &package.identifier,
+                    &package.version,
+                    &pull_request,
+                )?
+            {
+                return Ok(());
+            }
+            package.versions = versions.ok();
+        }
+
+        // Create an indeterminate progress bar to show as a pull request is being created
+        let pr_progress = ProgressBar::new_spinner().with_message(format!(
+            "Creating a pull request for {}",
+            submissions
+                .iter()
+                .map(|package| format!("{} version {}", package.identifier, package.version))
+                .join(", ")
+        ));
+        pr_progress.enable_steady_tick(SPINNER_TICK_RATE);
+
+        let pull_request_url = github
+            .add_versions()
+            .packages(&submissions)
+            .maybe_issue_resolves(self.resolves.clone())
+            .templates(pr_templates)
+            .send()
+            .await?;
+
+        pr_progress.finish_and_clear();
+
+        println!(
+            "{} created a {} with {} packages to {WINGET_PKGS_FULL_NAME}",
+            "Successfully".green(),
+            "pull request".hyperlink(&pull_request_url),
+            submissions.len()
+        );
+
+        if self.open_pr {
+            open::that(pull_request_url.as_str())?;
+        }
+
+        Ok(())
+    }
+
     fn get_yaml_file_paths(&self) -> walkdir::Result<Vec<Utf8PathBuf>> {
         WalkDir::new(&self.path)
             .into_iter()
@@ -1,5 +1,5 @@
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeSet, HashMap},
     io::{Read, Seek},
     mem,
     num::{NonZeroU32, NonZeroUsize},
@@ -14,26 +14,33 @@ use indicatif::ProgressBar;
 use itertools::Itertools;
 use owo_colors::OwoColorize;
 use strsim::levenshtein;
+use tracing::warn;
 use winget_types::{
     PackageIdentifier, PackageVersion,
-    installer::{InstallerType, MinimumOSVersion, NestedInstallerFiles},
+    installer::{
+        Installer, InstallerManifest, InstallerType, MinimumOSVersion, NestedInstallerFiles,
+    },
     url::{DecodedUrl, ReleaseNotesUrl},
 };
 
 use crate::{
     commands::utils::{
-        SPINNER_TICK_RATE, SubmitOption, prompt_existing_pull_request, write_changes_to_dir,
+        HeaderArgs, SPINNER_TICK_RATE, SubmitOption, TemplateArgs, print_migrations,
+        prompt_existing_pull_request, pull_request_to_amend, warn_version_mismatches,
+        write_changes_to_dir,
     },
     credential::handle_token,
     download::{Download, Downloader},
     download_file::process_files,
+    file_analyser::FileAnalyser,
     github::{
         github_client::{GITHUB_HOST, GitHub, WINGET_PKGS_FULL_NAME},
-        utils::{PackagePath, pull_request::pr_changes},
+        utils::{PackagePath, pull_request::pr_changes, template::installer_summary},
     },
-    installers::zip::Zip,
-    manifests::Url,
-    match_installers::match_installers,
+    installers::{authenticode::Signer, zip::Zip},
+    manifests::{Url, diff::ManifestSnapshot},
+    match_installers::{InstallerMatch, MatchOverride, MatchedInstallers, match_installers},
+    prompts::text::confirm_prompt,
     terminal::Hyperlinkable,
     traits::{LocaleExt, path::NormalizePath},
 };
@@ -54,17 +61,38 @@ pub struct UpdateVersion {
     urls: Vec<Url>,
 
     /// Number of installers to download at the same time
-    #[arg(long, default_value_t = NonZeroUsize::new(num_cpus::get()).unwrap())]
+    #[arg(long, env = "KOMAC_CONCURRENT_DOWNLOADS", default_value_t = NonZeroUsize::new(num_cpus::get()).unwrap())]
     concurrent_downloads: NonZeroUsize,
 
+    #[command(flatten)]
+    download_headers: HeaderArgs,
+
+    /// Skip downloading the previous version's installers to warn if their signer differs from the
+    /// new installers' signer
+    #[arg(long, env = "KOMAC_SKIP_SIGNER_CHECK")]
+    skip_signer_check: bool,
+
+    /// Match the previous installer at a 1-based index with the installer from a URL, such as
+    /// `2=https://example.com/setup.exe`
+    #[arg(long = "match", value_name = "PREVIOUS_INDEX=URL")]
+    matches: Vec<MatchOverride>,
+
     /// List of issues that updating this package would resolve
     #[arg(long)]
     resolves: Option<Vec<NonZeroU32>>,
 
+    #[command(flatten)]
+    pr_templates: TemplateArgs,
+
     /// Automatically submit a pull request
     #[arg(short, long)]
     submit: bool,
 
+    /// Allow previous installers without a counterpart to be removed when submitting
+    /// automatically
+    #[arg(long, requires = "submit")]
+    allow_installer_removal: bool,
+
     /// URL to package's release notes
     #[arg(long, value_hint = clap::ValueHint::Url)]
     release_notes_url: Option<ReleaseNotesUrl>,
@@ -89,6 +117,10 @@ pub struct UpdateVersion {
     #[arg(long, env = "DRY_RUN")]
     dry_run: bool,
 
+    /// Show a field by field comparison with the previous version instead of the full manifests
+    #[arg(long)]
+    diff: bool,
+
     /// Package version to replace
     #[arg(short, long, num_args = 0..=1, default_missing_value = "latest")]
     replace: Option<PackageVersion>,
@@ -97,6 +129,14 @@ pub struct UpdateVersion {
     #[arg(long, env)]
     skip_pr_check: bool,
 
+    /// Push the changes to the open pull request for this version instead of creating a new one
+    #[arg(long, conflicts_with = "replace")]
+    amend_pr: bool,
+
+    /// Comment to add to the pull request after amending it
+    #[arg(long, requires = "amend_pr")]
+    amend_comment: Option<String>,
+
     /// GitHub personal access token with the `public_repo` scope
     #[arg(short, long, env = "GITHUB_TOKEN")]
     token: Option<String>,
@@ -105,6 +145,7 @@ pub struct UpdateVersion {
 impl UpdateVersion {
     pub async fn run(self) -> Result<()> {
         let token = handle_token(self.token.as_deref()).await?;
+        let pr_templates = self.pr_templates.templates()?;
         let github = GitHub::new(&token)?;
 
         let package_identifier = Arc::new(self.package_identifier);
@@ -143,8 +184,18 @@ impl UpdateVersion {
             }
         }
 
-        if let Some(pull_request) = existing_pr.await?? {
-            if !(self.skip_pr_check || self.dry_run)
+        let existing_pr = existing_pr.await??;
+        let amend_pr = if self.amend_pr {
+            Some(pull_request_to_amend(
+                &package_identifier,
+                &package_version,
+                github
+                    .get_pull_request_to_amend(&package_identifier, &package_version)
+                    .await?,
+            )?)
+        } else {
+            if let Some(pull_request) = existing_pr
+                && !(self.skip_pr_check || self.dry_run)
                 && !prompt_existing_pull_request(
                     &package_identifier,
                     &package_version,
@@ -153,7 +204,8 @@ impl UpdateVersion {
             {
                 return Ok(());
             }
-        }
+            None
+        };
 
         let manifests = tokio::spawn({
             let github = github.clone();
@@ -161,7 +213,7 @@ impl UpdateVersion {
             let latest_version = latest_version.clone();
             async move {
                 github
-                    .get_manifests(&package_identifier, &latest_version)
+                    .get_migrated_manifests(&package_identifier, &latest_version)
                     .await
             }
         });
@@ -181,7 +233,8 @@ impl UpdateVersion {
             }
         });
 
-        let downloader = Downloader::new_with_concurrent(self.concurrent_downloads);
+        let downloader = Downloader::new_with_concurrent(self.concurrent_downloads)
+            .with_headers(self.download_headers.download_headers()?);
         let mut files = downloader
             .download(
                 &self
@@ -197,7 +250,9 @@ impl UpdateVersion {
             .iter_mut()
             .flat_map(|(_url, analyser)| mem::take(&mut analyser.installers))
             .collect::<Vec<_>>();
-        let mut manifests = manifests.await??;
+        let (mut manifests, migrations) = manifests.await??;
+        print_migrations(&package_identifier, latest_version, &migrations);
+        let previous_manifests = self.diff.then(|| ManifestSnapshot::new(&manifests));
         let previous_installers = mem::take(&mut manifests.installer.installers)
             .into_iter()
             .map(|mut installer| {
@@ -214,36 +269,85 @@ impl UpdateVersion {
             })
             .collect::<Vec<_>>();
         manifests.default_locale.package_version = (*package_version).clone();
-        let matched_installers = match_installers(previous_installers, &installer_results);
-        let installers = matched_installers
+        let mut matched_installers =
+            match_installers(previous_installers, &installer_results, &self.matches)?;
+        report_matches(&matched_installers);
+        if !confirm_installer_changes(
+            &matched_installers,
+            self.submit,
+            self.allow_installer_removal,
+        )? {
+            return Ok(());
+        }
+        warn_invalid_signatures(&download_results);
+        warn_version_mismatches(&download_results, &package_version);
+        if !self.skip_signer_check {
+            verify_signers(&downloader, &matched_installers.matches, &download_results).await;
+        }
+        let mut installers = mem::take(&mut matched_installers.matches)
             .into_iter()
-            .map(|(previous_installer, new_installer)| {
-                let analyser = &download_results[&new_installer.url];
-                let installer_type = match previous_installer.r#type {
-                    Some(InstallerType::Portable) => previous_installer.r#type,
-                    _ => match new_installer.r#type {
+            .map(
+                |InstallerMatch {
+                     previous: previous_installer,
+                     new: new_installer,
+                     ..
+                 }| {
+                    let analyser = &download_results[&new_installer.url];
+                    let installer_type = match previous_installer.r#type {
                         Some(InstallerType::Portable) => previous_installer.r#type,
-                        _ => new_installer.r#type,
-                    },
-                };
-                let mut installer = new_installer.clone().merge_with(previous_installer);
-                installer.r#type = installer_type;
-                installer.url.clone_from(&new_installer.url);
-                installer.nested_installer_files = fix_relative_paths(
-                    if installer.nested_installer_files.is_empty() {
-                        manifests.installer.nested_installer_files.clone()
-                    } else {
-                        installer.nested_installer_files
-                    },
-                    analyser.zip.as_ref(),
-                );
-                for entry in &mut installer.apps_and_features_entries {
-                    entry.deduplicate(&manifests.default_locale);
-                }
-                installer
-            })
+                        _ => match new_installer.r#type {
+                            Some(InstallerType::Portable) => previous_installer.r#type,
+                            _ => new_installer.r#type,
+                        },
+                    };
+                    let mut installer = new_installer.clone().merge_with(previous_installer);
+                    installer.r#type = installer_type;
+                    analyser.enrich_portable(&mut installer);
+                    installer.url.clone_from(&new_installer.url);
+                    installer.nested_installer_files = fix_relative_paths(
+                        if installer.nested_installer_files.is_empty() {
+                            manifests.installer.nested_installer_files.clone()
+                        } else {
+                            installer.nested_installer_files
+                        },
+                        analyser.zip.as_ref(),
+                    );
+                    for entry in &mut installer.apps_and_features_entries {
+                        entry.deduplicate(&manifests.default_locale);
+                    }
+                    installer
+                },
+            )
             .collect::<Vec<_>>();
 
+        if !matched_installers.unmatched.is_empty() {
+            // Copy the root-level values onto every installer so that optimizing the manifest moves
+            // only the values that the added installers share back to the root
+            let root_installer = root_installer(&manifests.installer);
+            installers = installers
+                .into_iter()
+                .map(|installer| installer.merge_with(root_installer.clone()))
+                .chain(
+                    matched_installers
+                        .unmatched
+                        .into_iter()
+                        .map(|new_installer| {
+                            let analyser = &download_results[&new_installer.url];
+                            let mut installer = new_installer.merge_with(root_installer.clone());
+                            analyser.enrich_portable(&mut installer);
+                            installer.nested_installer_files = fix_relative_paths(
+                                manifests.installer.nested_installer_files.clone(),
+                                analyser.zip.as_ref(),
+                            );
+                            for entry in &mut installer.apps_and_features_entries {
+                                entry.deduplicate(&manifests.default_locale);
+                            }
+                            installer
+                        }),
+                )
+                .collect();
+        }
+
         manifests.installer.package_version = (*package_version).clone();
         manifests.installer.minimum_os_version = manifests
             .installer
@@ -295,12 +399,44 @@ impl UpdateVersion {
             &package_version,
             self.submit,
             self.dry_run,
+            previous_manifests.as_ref(),
         )?;
 
         if submit_option == SubmitOption::Exit {
             return Ok(());
         }
 
+        if let Some(pull_request) = amend_pr {
+            let pr_progress = ProgressBar::new_spinner().with_message(format!(
+                "Amending the pull request for {package_identifier} {package_version}",
+            ));
+            pr_progress.enable_steady_tick(SPINNER_TICK_RATE);
+
+            let pull_request_url = github
+                .amend_pull_request()
+                .identifier(&package_identifier)
+                .version(&package_version)
+                .pull_request(&pull_request)
+                .changes(changes)
+                .maybe_comment(self.amend_comment.as_deref())
+                .send()
+                .await?;
+
+            pr_progress.finish_and_clear();
+
+            println!(
+                "{} amended the {} to {WINGET_PKGS_FULL_NAME}",
+                "Successfully".green(),
+                "pull request".hyperlink(&pull_request_url)
+            );
+
+            if self.open_pr {
+                open::that(pull_request_url.as_str())?;
+            }
+
+            return Ok(());
+        }
+
         // Create an indeterminate progress bar to show as a pull request is being created
         let pr_progress = ProgressBar::new_spinner().with_message(format!(
             "Creating a pull request for {package_identifier} {package_version}",
@@ -317,6 +453,8 @@ impl UpdateVersion {
             .maybe_issue_resolves(self.resolves)
             .maybe_created_with(self.created_with)
             .maybe_created_with_url(self.created_with_url)
+            .templates(&pr_templates)
+            .installers(&installer_summary(&manifests.installer))
             .send()
             .await?;
 
@@ -336,6 +474,220 @@ impl UpdateVersion {
     }
 }
 
+fn warn_invalid_signatures(download_results: &HashMap<DecodedUrl, FileAnalyser>) {
+    for (url, analyser) in download_results {
+        if let Some(signer) = analyser
+            .signer
+            .as_ref()
+            .filter(|signer| signer.digest_valid == Some(false))
+        {
+            warn!("The signature of {url} by {signer} does not match the file's digest");
+        }
+    }
+}
+
+/// Compares the signers of the previous version's installers against the signers of the installers
+/// they were matched with, warning if the signer has changed or a signature has been removed.
+///
+/// A previous installer is only trusted if it still matches the previous version's
+/// `InstallerSha256`. Otherwise, a versionless or hijacked URL would serve the new installer, which
+/// would then be compared with itself.
+async fn verify_signers(
+    downloader: &Downloader,
+    matched_installers: &[InstallerMatch],
+    download_results: &HashMap<DecodedUrl, FileAnalyser<'_>>,
+) {
+    let previous_hashes = matched_installers
+        .iter()
+        .map(|installer_match| {
+            (
+                &installer_match.previous.url,
+                &installer_match.previous.sha_256,
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    let downloads = previous_hashes
+        .keys()
+        .map(|&url| Download::new(Url::from(url.clone())))
+        .collect::<Vec<_>>();
+    let files = match downloader.download(&downloads).await {
+        Ok(files) => files,
+        Err(error) => {
+            warn!(%error, "The signers of the previous installers could not be verified");
+            return;
+        }
+    };
+
+    let mut previous_signers = HashMap::new();
+    for file in files {
+        let Some((&url, &sha_256)) = previous_hashes.get_key_value(file.url.inner()) else {
+            continue;
+        };
+        if file.sha_256 != *sha_256 {
+            warn!(
+                "The signer of {url} could not be verified as it no longer matches the previous version's InstallerSha256"
+            );
+            continue;
+        }
+        match Signer::from_file(&file.mmap, &file.file_name) {
+            Ok(signer) => {
+                previous_signers.insert(url, signer);
+            }
+            Err(error) => warn!(%error, "The signer of {url} could not be read"),
+        }
+    }
+
+    for installer_match in matched_installers {
+        let Some(Some(previous_signer)) = previous_signers.get(&installer_match.previous.url)
+        else {
+            continue;
+        };
+        let new_url = &installer_match.new.url;
+        match download_results
+            .get(new_url)
+            .and_then(|analyser| analyser.signer.as_ref())
+        {
+            Some(new_signer) if new_signer.is_same_publisher(previous_signer) => {}
+            Some(new_signer) => warn!(
+                "{new_url} is signed by {new_signer} but the previous version was signed by {previous_signer}"
+            ),
+            None => warn!(
+                "{new_url} is not signed but the previous version was signed by {previous_signer}"
+            ),
+        }
+    }
+}
+
+/// Shows which new installer each previous installer was matched with and why, warning about
+/// matches with a low score
+fn report_matches(matched_installers: &MatchedInstallers) {
+    let MatchedInstallers {
+        matches,
+        unmatched,
+        removed,
+    } = matched_installers;
+    if matches.len() > 1 || !unmatched.is_empty() || !removed.is_empty() {
+        println!("Matched installers:");
+        for installer_match in matches {
+            println!(
+                "  #{} {} → {} {}{}",
+                installer_match.index + 1,
+                installer_match.previous.url.as_str().dimmed(),
+                installer_match.new.url,
+                format!("score {}", installer_match.score).dimmed(),
+                if installer_match.overridden {
+                    " (--match)"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+
+    for installer_match in matches.iter().filter(|installer_match| {
+        !installer_match.overridden && !installer_match.score.is_confident()
+    }) {
+        warn!(
+            "Previous installer #{} was matched with {} with a low score of {}. Use --match {}=<URL> if this is wrong",
+            installer_match.index + 1,
+            installer_match.new.url,
+            installer_match.score.total(),
+            installer_match.index + 1
+        );
+    }
+}
+
+/// Summarizes the installers that will be added or removed because they have no counterpart,
+/// asking for confirmation unless the pull request is submitted automatically. Installers are only
+/// removed automatically if that has been explicitly allowed.
+fn confirm_installer_changes(
+    matched_installers: &MatchedInstallers,
+    submit: bool,
+    allow_removal: bool,
+) -> Result<bool> {
+    let MatchedInstallers {
+        unmatched, removed, ..
+    } = matched_installers;
+    if unmatched.is_empty() && removed.is_empty() {
+        return Ok(true);
+    }
+
+    for installer in unmatched {
+        println!(
+            "  {} {} {}",
+            "+".green(),
+            installer.architecture,
+            installer.url
+        );
+    }
+    for (index, installer) in removed {
+        println!(
+            "  {} #{} {} {}",
+            "-".red(),
+            index + 1,
+            installer.architecture,
+            installer.url
+        );
+    }
+    println!(
+        "{} installers will be added and {} removed because they have no counterpart in the other version",
+        unmatched.len(),
+        removed.len()
+    );
+
+    if submit {
+        if !removed.is_empty() && !allow_removal {
+            bail!(
+                "{} previous installers have no counterpart and would be removed. Pass --allow-installer-removal to submit anyway",
+                removed.len()
+            );
+        }
+        return Ok(true);
+    }
+
+    Ok(confirm_prompt(
+        "Would you like to continue with these changes?",
+    )?)
+}
+
+/// Creates an installer from the root-level values of an installer manifest that apply to
+/// installers of any architecture
+fn root_installer(manifest: &InstallerManifest) -> Installer {
+    Installer {
+        locale: manifest.locale.clone(),
+        platform: manifest.platform,
+        minimum_os_version: manifest.minimum_os_version,
+        r#type: manifest.r#type,
+        nested_installer_type: manifest.nested_installer_type,
+        scope: manifest.scope,
+        install_modes: manifest.install_modes,
+        switches: manifest.switches.clone(),
+        success_codes: manifest.success_codes.clone(),
+        expected_return_codes: manifest.expected_return_codes.clone(),
+        upgrade_behavior: manifest.upgrade_behavior,
+        commands: manifest.commands.clone(),
+        protocols: manifest.protocols.clone(),
+        file_extensions: manifest.file_extensions.clone(),
+        dependencies: manifest.dependencies.clone(),
+        capabilities: manifest.capabilities.clone(),
+        restricted_capabilities: manifest.restricted_capabilities.clone(),
+        markets: manifest.markets.clone(),
+        aborts_terminal: manifest.aborts_terminal,
+        install_location_required: manifest.install_location_required,
+        require_explicit_upgrade: manifest.require_explicit_upgrade,
+        display_install_warnings: manifest.display_install_warnings,
+        unsupported_os_architectures: manifest.unsupported_os_architectures,
+        unsupported_arguments: manifest.unsupported_arguments,
+        elevation_requirement: manifest.elevation_requirement,
+        installation_metadata: manifest.installation_metadata.clone(),
+        download_command_prohibited: manifest.download_command_prohibited,
+        repair_behavior: manifest.repair_behavior,
+        archive_binaries_depend_on_path: manifest.archive_binaries_depend_on_path,
+        ..Installer::default()
+    }
+}
+
 fn fix_relative_paths<R: Read + Seek>(
     nested_installer_files: BTreeSet<NestedInstallerFiles>,
     zip: Option<&Zip<R>>,
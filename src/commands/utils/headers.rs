@@ -0,0 +1,30 @@
+use camino::Utf8PathBuf;
+use clap::Args;
+use color_eyre::Result;
+
+use crate::download::{DownloadHeaders, HeaderRule};
+
+#[derive(Args)]
+pub struct HeaderArgs {
+    /// Custom header to send when downloading from a host, in the format of host:Name=Value.
+    /// Hosts can be prefixed with `*.` to match subdomains
+    #[arg(long = "header", value_name = "HOST:NAME=VALUE")]
+    headers: Vec<HeaderRule>,
+
+    /// YAML file mapping hosts or URL prefixes to custom download headers, cookies or basic auth
+    #[arg(long, env = "KOMAC_HEADERS_FILE", value_hint = clap::ValueHint::FilePath)]
+    headers_file: Option<Utf8PathBuf>,
+}
+
+impl HeaderArgs {
+    /// Combines the headers file with headers passed on the command line, with the command line
+    /// taking precedence
+    pub fn download_headers(self) -> Result<DownloadHeaders> {
+        let mut headers = match self.headers_file {
+            Some(path) => DownloadHeaders::from_file(&path)?,
+            None => DownloadHeaders::default(),
+        };
+        headers.extend(self.headers);
+        Ok(headers)
+    }
+}
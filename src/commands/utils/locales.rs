@@ -0,0 +1,168 @@
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+};
+
+use camino::Utf8PathBuf;
+use color_eyre::{Result, eyre::eyre};
+use tracing::warn;
+use winget_types::{
+    LanguageTag,
+    locale::{DefaultLocaleManifest, LocaleManifest},
+};
+
+use crate::{
+    manifests::spec::LocaleSpec,
+    prompts::{
+        prompter::Prompter,
+        text::{TextPrompt, optional_prompt, review_prompt},
+    },
+    translation::Translator,
+};
+
+/// An additional locale to add to a new package, in the format of `de-DE` or `de-DE=path.yaml`
+/// where the file is a partial locale manifest
+#[derive(Clone, Debug)]
+pub struct LocaleArg {
+    pub locale: LanguageTag,
+    pub path: Option<Utf8PathBuf>,
+}
+
+impl FromStr for LocaleArg {
+    type Err = String;
+
+    fn from_str(arg: &str) -> Result<Self, Self::Err> {
+        let (locale, path) = arg
+            .split_once('=')
+            .map_or((arg, None), |(locale, path)| (locale, Some(path)));
+        Ok(Self {
+            locale: locale.parse().map_err(|err| format!("{locale}: {err}"))?,
+            path: path.filter(|path| !path.is_empty()).map(Utf8PathBuf::from),
+        })
+    }
+}
+
+/// A locale manifest along with the names of the fields that were machine translated without
+/// being reviewed
+pub struct DraftedLocale {
+    pub manifest: LocaleManifest,
+    pub unreviewed: Vec<&'static str>,
+}
+
+struct Drafter<'a> {
+    locale: &'a LanguageTag,
+    source_locale: &'a LanguageTag,
+    translator: Option<&'a Translator>,
+    prompter: &'a Prompter,
+    unreviewed: Vec<&'static str>,
+}
+
+impl Drafter<'_> {
+    /// Returns the value if it was given. Otherwise, the default locale's value is translated and
+    /// offered for review, or the value is prompted for if there is nothing to translate.
+    async fn field<T>(&mut self, value: Option<T>, source: Option<&T>) -> Result<Option<T>>
+    where
+        T: FromStr + TextPrompt + Display,
+        <T as FromStr>::Err: Display + Debug + Sync + Send + 'static,
+    {
+        if value.is_some() {
+            return Ok(value);
+        }
+
+        let draft = match (self.translator, source) {
+            (Some(translator), Some(source)) => translator
+                .translate(&source.to_string(), self.source_locale, self.locale)
+                .await
+                .inspect_err(|err| {
+                    warn!("Failed to translate {} to {}: {err}", T::NAME, self.locale)
+                })
+                .ok(),
+            _ => None,
+        };
+
+        if self.prompter.is_interactive() {
+            Ok(match draft {
+                Some(draft) => review_prompt(&draft)?,
+                None => optional_prompt(None)?,
+            })
+        } else if let Some(draft) = draft {
+            self.unreviewed.push(T::NAME);
+            draft.parse().map(Some).map_err(|err| {
+                eyre!(
+                    "The {} translated to {} is not valid: {err}",
+                    T::NAME,
+                    self.locale
+                )
+            })
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Creates a locale manifest for an additional locale from a partial locale manifest. The short
+/// description, description and release notes that are not given are drafted with the translator
+/// from the default locale, and reviewed when prompting is enabled.
+pub async fn draft_locale(
+    locale: LanguageTag,
+    spec: LocaleSpec,
+    default_locale: &DefaultLocaleManifest,
+    translator: Option<&Translator>,
+    prompter: &Prompter,
+) -> Result<DraftedLocale> {
+    let mut drafter = Drafter {
+        locale: &locale,
+        source_locale: &default_locale.package_locale,
+        translator,
+        prompter,
+        unreviewed: Vec::new(),
+    };
+
+    let mut spec = spec;
+    spec.short_description = drafter
+        .field(
+            spec.short_description,
+            Some(&default_locale.short_description),
+        )
+        .await?;
+    spec.description = drafter
+        .field(spec.description, default_locale.description.as_ref())
+        .await?;
+    // Release notes are only asked for when the default locale has them
+    if let Some(release_notes) = &default_locale.release_notes {
+        spec.release_notes = drafter
+            .field(spec.release_notes, Some(release_notes))
+            .await?;
+    }
+
+    let unreviewed = drafter.unreviewed;
+    Ok(DraftedLocale {
+        manifest: spec.into_manifest(
+            default_locale.package_identifier.clone(),
+            default_locale.package_version.clone(),
+            locale,
+        ),
+        unreviewed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::LocaleArg;
+
+    #[rstest]
+    #[case("de-DE", "de-DE", None)]
+    #[case("ja-JP=locales/ja.yaml", "ja-JP", Some("locales/ja.yaml"))]
+    fn parse_locale_arg(#[case] arg: &str, #[case] locale: &str, #[case] path: Option<&str>) {
+        let arg = arg.parse::<LocaleArg>().unwrap();
+        assert_eq!(arg.locale.to_string(), locale);
+        assert_eq!(arg.path.as_deref().map(|path| path.as_str()), path);
+    }
+
+    #[test]
+    fn invalid_locale_arg() {
+        assert!("not a locale=file.yaml".parse::<LocaleArg>().is_err());
+    }
+}
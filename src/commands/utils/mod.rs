@@ -1,21 +1,31 @@
 pub mod environment;
+mod headers;
+mod locales;
 mod submit_option;
+mod templates;
 
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
 use anstream::println;
 use camino::Utf8Path;
 use chrono::Local;
-use color_eyre::Result;
+use color_eyre::{Result, eyre::eyre};
 use futures_util::{StreamExt, TryStreamExt, stream};
+pub use headers::HeaderArgs;
 use inquire::error::InquireResult;
+pub use locales::{DraftedLocale, LocaleArg, draft_locale};
 use owo_colors::OwoColorize;
 pub use submit_option::SubmitOption;
+pub use templates::TemplateArgs;
 use tokio::{fs, fs::File, io::AsyncWriteExt};
-use winget_types::{PackageIdentifier, PackageVersion};
+use tracing::warn;
+use winget_types::{ManifestVersion, PackageIdentifier, PackageVersion, url::DecodedUrl};
 
 use crate::{
-    commands::utils::environment::CI, github::graphql::get_existing_pull_request::PullRequest,
+    commands::utils::environment::CI,
+    file_analyser::FileAnalyser,
+    github::graphql::{get_branches::PullRequestState, get_existing_pull_request::PullRequest},
+    manifests::migrate::Migration,
     prompts::text::confirm_prompt,
 };
 
@@ -44,6 +54,60 @@ pub fn prompt_existing_pull_request(
     }
 }
 
+/// Returns the pull request to amend, which must still be open
+pub fn pull_request_to_amend(
+    identifier: &PackageIdentifier,
+    version: &PackageVersion,
+    pull_request: Option<PullRequest>,
+) -> Result<PullRequest> {
+    pull_request
+        .filter(|pull_request| pull_request.state == PullRequestState::Open)
+        .ok_or_else(|| eyre!("You have no open pull request for {identifier} {version} to amend"))
+}
+
+/// Warns if the version info of a portable does not match the package version, which usually means
+/// that a URL points to a different version than the one being submitted
+pub fn warn_version_mismatches(
+    download_results: &HashMap<DecodedUrl, FileAnalyser>,
+    package_version: &PackageVersion,
+) {
+    for (url, analyser) in download_results {
+        if let Some(versions) = analyser.portable_version_mismatch(package_version) {
+            warn!("{url} has version {versions} but the package version is {package_version}");
+        }
+    }
+}
+
+/// Reports the manifest version that manifests were migrated from and what changed in them
+pub fn print_migrations(
+    identifier: &PackageIdentifier,
+    version: &PackageVersion,
+    migrations: &[(String, Migration)],
+) {
+    let Some(from) = migrations
+        .iter()
+        .filter(|(_, migration)| migration.is_needed())
+        .map(|(_, migration)| migration.from)
+        .min()
+    else {
+        return;
+    };
+
+    println!(
+        "Migrated {identifier} {version} from manifest version {from} to {}",
+        ManifestVersion::DEFAULT
+    );
+    for (file_name, migration) in migrations {
+        for note in &migration.notes {
+            if note.lossy {
+                warn!("{file_name}: {note}");
+            } else {
+                println!("  {file_name}: {note}");
+            }
+        }
+    }
+}
+
 pub async fn write_changes_to_dir(changes: &[(String, String)], output: &Utf8Path) -> Result<()> {
     fs::create_dir_all(output).await?;
     stream::iter(changes.iter())
@@ -1,11 +1,22 @@
-use color_eyre::Result;
+use anstream::print;
+use camino::Utf8Path;
+use color_eyre::{Result, eyre::bail};
 use derive_more::Display;
 use inquire::Select;
 use strum::{EnumIter, IntoEnumIterator};
+use tracing::warn;
 use winget_types::{PackageIdentifier, PackageVersion};
 
 use crate::{
-    commands::utils::environment::VHS, editor::Editor, manifests::print_changes,
+    commands::utils::environment::VHS,
+    editor::Editor,
+    manifests::{
+        Manifests,
+        diff::{ManifestDiff, ManifestSnapshot},
+        manifest::Manifest,
+        print_changes,
+        schema::{self, SchemaError},
+    },
     prompts::handle_inquire_error,
 };
 
@@ -23,20 +34,44 @@ impl SubmitOption {
         version: &PackageVersion,
         submit: bool,
         dry_run: bool,
+        previous_manifests: Option<&ManifestSnapshot>,
     ) -> Result<SubmitOption> {
         let mut submit_option;
 
         loop {
+            // The changes are parsed again on every loop as they may have been edited
+            let diff = previous_manifests.and_then(|previous_manifests| {
+                let manifests = changes
+                    .iter()
+                    .map(|(_, content)| Manifest::from_yaml(content))
+                    .collect::<serde_yaml::Result<Vec<_>>>()
+                    .ok()?;
+                Some(ManifestDiff::new(
+                    previous_manifests,
+                    &ManifestSnapshot::new(&Manifests::from_manifests(manifests)?),
+                ))
+            });
+
             let changes_iter = changes.iter().map(|(_, content)| content.as_str());
-            if *VHS {
+            if let Some(diff) = diff {
+                print!("{diff}");
+            } else if *VHS {
                 print_changes(changes_iter.take(1));
             } else {
                 print_changes(changes_iter);
             }
 
+            let violations = schema_violations(changes);
+            for (file_name, violation) in &violations {
+                warn!("{file_name} {violation}");
+            }
+
             submit_option = if dry_run {
                 SubmitOption::Exit
             } else if submit {
+                if !violations.is_empty() {
+                    bail!("{identifier} {version} does not match the winget manifest schema")
+                }
                 SubmitOption::Submit
             } else {
                 Select::new(
@@ -57,3 +92,24 @@ impl SubmitOption {
         Ok(submit_option)
     }
 }
+
+/// Validates each change against the bundled schema for its manifest type and manifest version
+fn schema_violations(changes: &[(String, String)]) -> Vec<(&str, String)> {
+    changes
+        .iter()
+        .flat_map(|(path, content)| {
+            let file_name = Utf8Path::new(path).file_name().unwrap_or(path);
+            let violations = match schema::validate(content) {
+                Ok(violations) => violations.iter().map(ToString::to_string).collect(),
+                Err(error @ SchemaError::NoSchema { .. }) => {
+                    warn!("{file_name} {error}");
+                    Vec::new()
+                }
+                Err(error) => vec![error.to_string()],
+            };
+            violations
+                .into_iter()
+                .map(move |violation| (file_name, violation))
+        })
+        .collect()
+}
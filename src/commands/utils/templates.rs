@@ -0,0 +1,40 @@
+use camino::Utf8PathBuf;
+use clap::Args;
+use color_eyre::Result;
+
+use crate::github::utils::template::{PullRequestTemplates, Template};
+
+#[derive(Args)]
+pub struct TemplateArgs {
+    /// Template for the pull request and commit title, such as `[ABC-123] {update_state}: {identifier} version {version}`.
+    /// Available variables are {identifier}, {version}, {update_state}, {resolves}, {installers}, {created_with} and {uuid}
+    #[arg(long, env = "KOMAC_PR_TITLE_TEMPLATE", value_name = "TEMPLATE")]
+    pr_title_template: Option<Template>,
+
+    /// Template for the pull request body
+    #[arg(long, env = "KOMAC_PR_BODY_TEMPLATE", value_name = "TEMPLATE")]
+    pr_body_template: Option<Template>,
+
+    /// Template for the branch name, such as `{identifier}-{version}-{uuid}`
+    #[arg(long, env = "KOMAC_BRANCH_TEMPLATE", value_name = "TEMPLATE")]
+    branch_template: Option<Template>,
+
+    /// YAML file with `title`, `body` and `branch` templates
+    #[arg(long, env = "KOMAC_TEMPLATES_FILE", value_hint = clap::ValueHint::FilePath)]
+    templates_file: Option<Utf8PathBuf>,
+}
+
+impl TemplateArgs {
+    /// Combines the templates file with templates passed on the command line, with the command line
+    /// taking precedence
+    pub fn templates(&self) -> Result<PullRequestTemplates> {
+        let mut templates = match &self.templates_file {
+            Some(path) => PullRequestTemplates::from_file(path)?,
+            None => PullRequestTemplates::default(),
+        };
+        templates.title = self.pr_title_template.clone().or(templates.title);
+        templates.body = self.pr_body_template.clone().or(templates.body);
+        templates.branch = self.branch_template.clone().or(templates.branch);
+        Ok(templates)
+    }
+}
@@ -0,0 +1,383 @@
+use std::{collections::BTreeMap, env, ffi::OsString, fs, io, num::NonZeroUsize, str::FromStr};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+use thiserror::Error;
+use winget_types::{LanguageTag, url::DecodedUrl};
+
+use crate::github::{github_client::Upstream, utils::template::Template};
+
+const KOMAC_CONFIG: &str = "KOMAC_CONFIG";
+const KOMAC_PROFILE: &str = "KOMAC_PROFILE";
+const GITHUB_TOKEN: &str = "GITHUB_TOKEN";
+const KOMAC_TOKEN_FILE: &str = "KOMAC_TOKEN_FILE";
+const KOMAC_TOKEN_COMMAND: &str = "KOMAC_TOKEN_COMMAND";
+const PROFILE_ARG: &str = "--profile";
+
+/// The profile used when no profile is selected and no default profile is set
+pub const DEFAULT_PROFILE: &str = "default";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error(
+        "Could not find a configuration directory. Set {KOMAC_CONFIG} to the path of the config file"
+    )]
+    NoConfigDirectory,
+    #[error("Failed to read config file {path}: {source}")]
+    ReadFile {
+        path: Utf8PathBuf,
+        source: io::Error,
+    },
+    #[error("Failed to write config file {path}: {source}")]
+    WriteFile {
+        path: Utf8PathBuf,
+        source: io::Error,
+    },
+    #[error("Failed to parse config file {path}: {source}")]
+    ParseFile {
+        path: Utf8PathBuf,
+        source: serde_yaml::Error,
+    },
+    #[error(transparent)]
+    Serialize(#[from] serde_yaml::Error),
+    #[error("Profile `{0}` does not exist in the config file")]
+    ProfileNonExistent(String),
+    #[error("Invalid value `{value}` for {key}: {reason}")]
+    InvalidValue {
+        key: ConfigKey,
+        value: String,
+        reason: String,
+    },
+}
+
+/// A setting that a profile can provide a default for. Each setting is applied through the
+/// environment variable that the equivalent command line argument reads from.
+#[derive(
+    Copy, Clone, Debug, Display, Eq, PartialEq, Ord, PartialOrd, Deserialize, Serialize, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum ConfigKey {
+    ForkOwner,
+    Upstream,
+    DefaultLocale,
+    ConcurrentDownloads,
+    Output,
+    OpenPr,
+    CreatedWith,
+    CreatedWithUrl,
+    TemplatesFile,
+    PrTitleTemplate,
+    PrBodyTemplate,
+    BranchTemplate,
+    Token,
+    GithubAppId,
+    GithubAppPrivateKey,
+    GithubAppInstallationId,
+    TranslateCommand,
+    TranslateUrl,
+}
+
+impl ConfigKey {
+    pub const fn env_var(self) -> &'static str {
+        match self {
+            Self::ForkOwner => "KOMAC_FORK_OWNER",
+            Self::Upstream => "KOMAC_UPSTREAM",
+            Self::DefaultLocale => "KOMAC_DEFAULT_LOCALE",
+            Self::ConcurrentDownloads => "KOMAC_CONCURRENT_DOWNLOADS",
+            Self::Output => "OUTPUT_DIRECTORY",
+            Self::OpenPr => "OPEN_PR",
+            Self::CreatedWith => "KOMAC_CREATED_WITH",
+            Self::CreatedWithUrl => "KOMAC_CREATED_WITH_URL",
+            Self::TemplatesFile => "KOMAC_TEMPLATES_FILE",
+            Self::PrTitleTemplate => "KOMAC_PR_TITLE_TEMPLATE",
+            Self::PrBodyTemplate => "KOMAC_PR_BODY_TEMPLATE",
+            Self::BranchTemplate => "KOMAC_BRANCH_TEMPLATE",
+            Self::Token => GITHUB_TOKEN,
+            Self::GithubAppId => "KOMAC_GITHUB_APP_ID",
+            Self::GithubAppPrivateKey => "KOMAC_GITHUB_APP_PRIVATE_KEY",
+            Self::GithubAppInstallationId => "KOMAC_GITHUB_APP_INSTALLATION_ID",
+            Self::TranslateCommand => "KOMAC_TRANSLATE_COMMAND",
+            Self::TranslateUrl => "KOMAC_TRANSLATE_URL",
+        }
+    }
+
+    /// Checks that the value can be parsed by the setting it is for
+    pub fn validate(self, value: &str) -> Result<(), ConfigError> {
+        fn check<T: FromStr>(value: &str) -> Result<(), String>
+        where
+            T::Err: ToString,
+        {
+            T::from_str(value)
+                .map(|_| ())
+                .map_err(|err| err.to_string())
+        }
+
+        match self {
+            Self::Upstream => check::<Upstream>(value),
+            Self::DefaultLocale => check::<LanguageTag>(value),
+            Self::ConcurrentDownloads => check::<NonZeroUsize>(value),
+            Self::GithubAppId | Self::GithubAppInstallationId => check::<u64>(value),
+            Self::OpenPr => check::<bool>(value),
+            Self::CreatedWithUrl => check::<DecodedUrl>(value),
+            Self::TranslateUrl => check::<url::Url>(value),
+            Self::PrTitleTemplate | Self::PrBodyTemplate | Self::BranchTemplate => {
+                check::<Template>(value)
+            }
+            Self::Token => check::<TokenSource>(value),
+            Self::ForkOwner
+            | Self::Output
+            | Self::CreatedWith
+            | Self::TemplatesFile
+            | Self::GithubAppPrivateKey
+            | Self::TranslateCommand => Ok(()),
+        }
+        .map_err(|reason| ConfigError::InvalidValue {
+            key: self,
+            value: value.to_owned(),
+            reason,
+        })
+    }
+}
+
+/// Where the GitHub token for a profile comes from
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TokenSource {
+    /// The token stored in the platform's secure storage by `komac token update`
+    Keyring,
+    /// The token in the given environment variable, such as `env:BOT_GITHUB_TOKEN`
+    Env(String),
+    /// The token in the given file, such as `file:/run/secrets/github-token`
+    File(String),
+    /// The token printed by the given credential helper command, such as `command:gh auth token`
+    Command(String),
+}
+
+impl FromStr for TokenSource {
+    type Err = &'static str;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        match source.split_once(':') {
+            None if source == "keyring" => Ok(Self::Keyring),
+            Some((_, "")) => Err("Expected a value after `:`"),
+            Some(("env", variable)) => Ok(Self::Env(variable.to_owned())),
+            Some(("file", path)) => Ok(Self::File(path.to_owned())),
+            Some(("command", command)) => Ok(Self::Command(command.to_owned())),
+            _ => Err("Expected `keyring`, `env:<VARIABLE>`, `file:<PATH>` or `command:<COMMAND>`"),
+        }
+    }
+}
+
+pub type Profile = BTreeMap<ConfigKey, String>;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Config {
+    /// Returns the path of the config file, which can be overridden with `KOMAC_CONFIG`
+    pub fn path() -> Result<Utf8PathBuf, ConfigError> {
+        if let Some(path) = env::var_os(KOMAC_CONFIG) {
+            return Utf8PathBuf::from_path_buf(path.into())
+                .map_err(|_| ConfigError::NoConfigDirectory);
+        }
+
+        let config_dir = if cfg!(windows) {
+            env::var("APPDATA").ok().map(Utf8PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            env::var("HOME")
+                .ok()
+                .map(|home| Utf8Path::new(&home).join("Library/Application Support"))
+        } else {
+            env::var("XDG_CONFIG_HOME")
+                .ok()
+                .filter(|dir| !dir.is_empty())
+                .map(Utf8PathBuf::from)
+                .or_else(|| {
+                    env::var("HOME")
+                        .ok()
+                        .map(|home| Utf8Path::new(&home).join(".config"))
+                })
+        };
+
+        config_dir
+            .map(|dir| dir.join(clap::crate_name!()).join("config.yaml"))
+            .ok_or(ConfigError::NoConfigDirectory)
+    }
+
+    /// Loads the config file, returning an empty config if it does not exist
+    pub fn load() -> Result<Self, ConfigError> {
+        let path = Self::path()?;
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_yaml::from_str(&contents)
+                .map_err(|source| ConfigError::ParseFile { path, source }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(source) => Err(ConfigError::ReadFile { path, source }),
+        }
+    }
+
+    pub fn save(&self) -> Result<Utf8PathBuf, ConfigError> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|source| ConfigError::WriteFile {
+                path: path.clone(),
+                source,
+            })?;
+        }
+        fs::write(&path, serde_yaml::to_string(self)?).map_err(|source| {
+            ConfigError::WriteFile {
+                path: path.clone(),
+                source,
+            }
+        })?;
+        Ok(path)
+    }
+
+    /// Returns the name of the selected profile: the one passed to `--profile`, then
+    /// `KOMAC_PROFILE`, then the default profile
+    pub fn profile_name(&self, selected: Option<&str>) -> String {
+        selected
+            .map(str::to_owned)
+            .or_else(|| env::var(KOMAC_PROFILE).ok())
+            .or_else(|| self.default_profile.clone())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_owned())
+    }
+
+    /// Returns an error if a profile was explicitly selected with `--profile` or `KOMAC_PROFILE`
+    /// but does not exist
+    pub fn check_selected(&self, selected: Option<&str>) -> Result<(), ConfigError> {
+        if selected.is_none() && env::var_os(KOMAC_PROFILE).is_none() {
+            return Ok(());
+        }
+        let name = self.profile_name(selected);
+        if self.profiles.contains_key(&name) {
+            Ok(())
+        } else {
+            Err(ConfigError::ProfileNonExistent(name))
+        }
+    }
+
+    /// Applies the settings of the selected profile as defaults for environment variables that are
+    /// not already set, so that command line arguments and the environment take precedence.
+    ///
+    /// Nothing is applied if any of the profile's settings are invalid. This must be called before
+    /// any other threads are spawned.
+    pub fn apply(&self, selected: Option<&str>) -> Result<(), ConfigError> {
+        let Some(profile) = self.profiles.get(&self.profile_name(selected)) else {
+            return Ok(());
+        };
+
+        for (key, value) in profile {
+            key.validate(value)?;
+        }
+
+        for (&key, value) in profile {
+            let (variable, value) = match key {
+                ConfigKey::Token => match value.parse::<TokenSource>() {
+                    Ok(TokenSource::Env(variable)) => match env::var_os(variable) {
+                        Some(token) => (GITHUB_TOKEN, token),
+                        None => continue,
+                    },
+                    Ok(TokenSource::File(path)) => (KOMAC_TOKEN_FILE, OsString::from(path)),
+                    Ok(TokenSource::Command(command)) => {
+                        (KOMAC_TOKEN_COMMAND, OsString::from(command))
+                    }
+                    _ => continue,
+                },
+                _ => (key.env_var(), OsString::from(value)),
+            };
+            if env::var_os(variable).is_none() {
+                // SAFETY: the config is applied at startup before any other threads are spawned
+                unsafe { env::set_var(variable, value) };
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Finds the value of `--profile` in the command line arguments before they are parsed, as the
+/// profile provides defaults for the other arguments
+pub fn profile_arg() -> Option<String> {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            break;
+        } else if arg == PROFILE_ARG {
+            return args.next();
+        } else if let Some(profile) = arg
+            .strip_prefix(PROFILE_ARG)
+            .and_then(|arg| arg.strip_prefix('='))
+        {
+            return Some(profile.to_owned());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rstest::rstest;
+
+    use super::{Config, ConfigKey, TokenSource};
+
+    #[test]
+    fn parse_config() {
+        const CONFIG: &str = indoc! {"
+            default-profile: bot
+            profiles:
+              personal:
+                open-pr: 'true'
+              bot:
+                fork-owner: komac-bot
+                concurrent-downloads: '2'
+                token: env:BOT_GITHUB_TOKEN
+        "};
+
+        let config = serde_yaml::from_str::<Config>(CONFIG).unwrap();
+        assert_eq!(config.profile_name(None), "bot");
+        assert_eq!(config.profile_name(Some("personal")), "personal");
+        assert!(config.check_selected(Some("personal")).is_ok());
+        assert!(config.check_selected(Some("persnal")).is_err());
+        assert_eq!(
+            config.profiles["bot"]
+                .get(&ConfigKey::ForkOwner)
+                .map(String::as_str),
+            Some("komac-bot")
+        );
+    }
+
+    #[test]
+    fn unknown_key() {
+        assert!(serde_yaml::from_str::<Config>("profiles: { bot: { unknown: value } }").is_err());
+    }
+
+    #[rstest]
+    #[case(ConfigKey::ConcurrentDownloads, "0")]
+    #[case(ConfigKey::Upstream, "winget-pkgs")]
+    #[case(ConfigKey::OpenPr, "yes please")]
+    #[case(ConfigKey::PrTitleTemplate, "{unknown}")]
+    #[case(ConfigKey::Token, "file")]
+    #[case(ConfigKey::Token, "env:")]
+    fn invalid_value(#[case] key: ConfigKey, #[case] value: &str) {
+        assert!(key.validate(value).is_err());
+    }
+
+    #[rstest]
+    #[case("keyring", TokenSource::Keyring)]
+    #[case("env:BOT_TOKEN", TokenSource::Env(String::from("BOT_TOKEN")))]
+    #[case(
+        "command:gh auth token",
+        TokenSource::Command(String::from("gh auth token"))
+    )]
+    fn token_source(#[case] source: &str, #[case] expected: TokenSource) {
+        assert_eq!(source.parse::<TokenSource>().unwrap(), expected);
+    }
+}
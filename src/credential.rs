@@ -1,6 +1,6 @@
-use std::borrow::Cow;
+use std::{env, fs, ops::Deref, process::Command, sync::Arc};
 
-use color_eyre::eyre::{Result, bail};
+use color_eyre::eyre::{Result, bail, eyre};
 use inquire::{Password, error::InquireResult, validator::Validation};
 use keyring::Entry;
 use reqwest::{
@@ -9,40 +9,119 @@ use reqwest::{
 };
 use tokio::runtime::Handle;
 
-use crate::prompts::handle_inquire_error;
+use crate::{github::app::GitHubApp, prompts::handle_inquire_error};
 
 const SERVICE: &str = "komac";
 const USERNAME: &str = "github-access-token";
 const GITHUB_API_ENDPOINT: &str = "https://api.github.com/octocat";
+const KOMAC_TOKEN_FILE: &str = "KOMAC_TOKEN_FILE";
+const KOMAC_TOKEN_COMMAND: &str = "KOMAC_TOKEN_COMMAND";
+const OAUTH_SCOPES: &str = "x-oauth-scopes";
+
+/// Scopes of classic tokens that allow creating branches in a public fork
+const REPO_SCOPES: [&str; 2] = ["public_repo", "repo"];
 
 pub fn get_komac_credential() -> keyring::Result<Entry> {
     Entry::new(SERVICE, USERNAME)
 }
 
-pub async fn handle_token(token: Option<&str>) -> Result<Cow<str>> {
+/// A GitHub token, along with the GitHub App that issued it if it is an installation token that
+/// has to be refreshed before it expires
+pub struct GitHubToken {
+    token: String,
+    app: Option<Arc<GitHubApp>>,
+}
+
+impl GitHubToken {
+    const fn new(token: String) -> Self {
+        Self { token, app: None }
+    }
+
+    pub fn app(&self) -> Option<&Arc<GitHubApp>> {
+        self.app.as_ref()
+    }
+}
+
+impl Deref for GitHubToken {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.token
+    }
+}
+
+/// Finds a GitHub token from, in order of precedence, the `--token` argument or `GITHUB_TOKEN`,
+/// the file in `KOMAC_TOKEN_FILE`, the output of `KOMAC_TOKEN_COMMAND`, a GitHub App installation
+/// and the platform's secure storage. If none are found, a token is prompted for and stored.
+pub async fn handle_token(token: Option<&str>) -> Result<GitHubToken> {
     let client = Client::builder()
         .default_headers(get_default_headers(None))
         .build()?;
 
     if let Some(token) = token {
-        return validate_token(&client, token)
-            .await
-            .map(|()| Cow::Borrowed(token));
+        validate_token(&client, token).await?;
+        return Ok(GitHubToken::new(token.to_owned()));
+    }
+
+    if let Ok(path) = env::var(KOMAC_TOKEN_FILE) {
+        let token = fs::read_to_string(&path)
+            .map_err(|err| eyre!("Failed to read the token file {path}: {err}"))?
+            .trim()
+            .to_owned();
+        validate_token(&client, &token).await?;
+        return Ok(GitHubToken::new(token));
+    }
+
+    if let Ok(command) = env::var(KOMAC_TOKEN_COMMAND) {
+        let token = run_token_command(&command)?;
+        validate_token(&client, &token).await?;
+        return Ok(GitHubToken::new(token));
+    }
+
+    if let Some(app) = GitHubApp::from_env()? {
+        let app = Arc::new(app);
+        return Ok(GitHubToken {
+            token: app.token().await?,
+            app: Some(app),
+        });
     }
 
     let credential_entry = get_komac_credential()?;
 
     if let Ok(stored_token) = credential_entry.get_password() {
-        validate_token(&client, &stored_token)
-            .await
-            .map(|()| Cow::Owned(stored_token))
+        validate_token(&client, &stored_token).await?;
+        Ok(GitHubToken::new(stored_token))
     } else {
         let token = token_prompt(client, None)?;
         if credential_entry.set_password(&token).is_ok() {
             println!("Successfully stored token in platform's secure storage");
         }
-        Ok(Cow::Owned(token))
+        Ok(GitHubToken::new(token))
+    }
+}
+
+/// Runs a credential helper command, such as `gh auth token`, that prints a token to stdout
+fn run_token_command(command: &str) -> Result<String> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let output = Command::new(shell)
+        .args([flag, command])
+        .output()
+        .map_err(|err| eyre!("Failed to run {KOMAC_TOKEN_COMMAND} `{command}`: {err}"))?;
+    if !output.status.success() {
+        bail!(
+            "{KOMAC_TOKEN_COMMAND} `{command}` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    let token = String::from_utf8(output.stdout)?.trim().to_owned();
+    if token.is_empty() {
+        bail!("{KOMAC_TOKEN_COMMAND} `{command}` did not print a token");
     }
+    Ok(token)
 }
 
 pub fn token_prompt(client: Client, prompt: Option<&str>) -> InquireResult<String> {
@@ -70,7 +149,12 @@ pub async fn validate_token(client: &Client, token: &str) -> Result<()> {
     {
         Ok(response) => match response.status() {
             StatusCode::UNAUTHORIZED => bail!("GitHub token is invalid"),
-            _ => Ok(()),
+            _ => check_scopes(
+                response
+                    .headers()
+                    .get(OAUTH_SCOPES)
+                    .and_then(|scopes| scopes.to_str().ok()),
+            ),
         },
         Err(error) => {
             if error.is_connect() {
@@ -81,6 +165,25 @@ pub async fn validate_token(client: &Client, token: &str) -> Result<()> {
     }
 }
 
+/// Checks the scopes of classic tokens. Fine-grained tokens and installation tokens do not report
+/// their permissions, so missing permissions are reported when a request fails instead.
+fn check_scopes(scopes: Option<&str>) -> Result<()> {
+    let Some(scopes) = scopes else {
+        return Ok(());
+    };
+    if scopes
+        .split(',')
+        .map(str::trim)
+        .any(|scope| REPO_SCOPES.contains(&scope))
+    {
+        Ok(())
+    } else if scopes.trim().is_empty() {
+        bail!("GitHub token has no scopes. It needs the `public_repo` scope")
+    } else {
+        bail!("GitHub token is missing the `public_repo` scope. It only has: {scopes}")
+    }
+}
+
 const MICROSOFT_DELIVERY_OPTIMIZATION: HeaderValue =
     HeaderValue::from_static("Microsoft-Delivery-Optimization/10.1");
 const SEC_GPC: &str = "Sec-GPC";
@@ -97,3 +200,20 @@ pub fn get_default_headers(github_token: Option<&str>) -> HeaderMap {
     }
     default_headers
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::check_scopes;
+
+    #[rstest]
+    #[case(None, true)]
+    #[case(Some("public_repo, workflow"), true)]
+    #[case(Some("repo"), true)]
+    #[case(Some("read:org, gist"), false)]
+    #[case(Some(""), false)]
+    fn token_scopes(#[case] scopes: Option<&str>, #[case] valid: bool) {
+        assert_eq!(check_scopes(scopes).is_ok(), valid);
+    }
+}
@@ -20,10 +20,11 @@ use tokio::{
 };
 use winget_types::Sha256String;
 
-use super::{Download, DownloadedFile};
+use super::{Download, DownloadHeaders, DownloadedFile};
 
 pub struct Downloader {
     concurrent_downloads: NonZeroUsize,
+    headers: DownloadHeaders,
 }
 
 impl Downloader {
@@ -34,9 +35,17 @@ impl Downloader {
     pub const fn new_with_concurrent(concurrent_downloads: NonZeroUsize) -> Self {
         Self {
             concurrent_downloads,
+            headers: DownloadHeaders::new(Vec::new()),
         }
     }
 
+    /// Sets custom headers that are sent to matching hosts, such as cookies or authorization that
+    /// a vendor requires to serve an installer
+    pub fn with_headers(mut self, headers: DownloadHeaders) -> Self {
+        self.headers = headers;
+        self
+    }
+
     pub async fn download(&self, downloads: &[Download]) -> Result<Vec<DownloadedFile>> {
         let client = Client::builder().default_headers(Self::headers()).build()?;
 
@@ -73,9 +82,13 @@ impl Downloader {
     ) -> Result<DownloadedFile> {
         download.convert_to_github_versioned().await?;
 
-        download.upgrade_to_https(client).await;
+        download.upgrade_to_https(client, &self.headers).await;
 
-        let res = client.get(download.url.as_str()).send().await?;
+        let res = client
+            .get(download.url.as_str())
+            .headers(self.headers.for_url(&download.url))
+            .send()
+            .await?;
 
         if let Err(err) = res.error_for_status_ref() {
             bail!(
@@ -0,0 +1,284 @@
+use std::{fs, io, str::FromStr};
+
+use base64ct::{Base64, Encoding};
+use camino::Utf8Path;
+use indexmap::IndexMap;
+use reqwest::header::{
+    AUTHORIZATION, HeaderMap, HeaderName, HeaderValue, InvalidHeaderName, InvalidHeaderValue,
+};
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::debug;
+
+#[derive(Debug, Error)]
+pub enum HeaderRuleError {
+    #[error("Expected a header in the format of host:Name=Value but got {0}")]
+    InvalidFormat(String),
+    #[error(transparent)]
+    InvalidName(#[from] InvalidHeaderName),
+    #[error(transparent)]
+    InvalidValue(#[from] InvalidHeaderValue),
+    #[error("Failed to read headers file {path}: {source}")]
+    ReadFile { path: String, source: io::Error },
+    #[error("Failed to parse headers file {path}: {source}")]
+    ParseFile {
+        path: String,
+        source: serde_yaml::Error,
+    },
+}
+
+/// A header that is only sent to URLs matching a host or URL prefix.
+///
+/// Hosts may start with `*.` to also match any subdomain. Values are always marked as sensitive so
+/// that they are redacted when a request is debug printed.
+#[derive(Clone, Debug)]
+pub struct HeaderRule {
+    pattern: String,
+    name: HeaderName,
+    value: HeaderValue,
+}
+
+impl HeaderRule {
+    pub fn new(pattern: &str, name: &str, value: &str) -> Result<Self, HeaderRuleError> {
+        let mut value = HeaderValue::from_str(value)?;
+        value.set_sensitive(true);
+        Ok(Self {
+            pattern: pattern.trim().to_ascii_lowercase(),
+            name: HeaderName::from_str(name.trim())?,
+            value,
+        })
+    }
+
+    pub fn matches(&self, url: &url::Url) -> bool {
+        if self.pattern.contains("://") {
+            return url.as_str().to_ascii_lowercase().starts_with(&self.pattern);
+        }
+
+        let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+            return false;
+        };
+
+        match self.pattern.strip_prefix("*.") {
+            Some(domain) => {
+                host == domain
+                    || host
+                        .strip_suffix(domain)
+                        .is_some_and(|subdomain| subdomain.ends_with('.'))
+            }
+            None => host == self.pattern,
+        }
+    }
+}
+
+impl FromStr for HeaderRule {
+    type Err = HeaderRuleError;
+
+    /// Parses a header in the format of `host:Name=Value`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pattern, header) = s
+            .split_once(':')
+            .filter(|(pattern, _)| !pattern.is_empty())
+            .ok_or_else(|| HeaderRuleError::InvalidFormat(s.to_owned()))?;
+        let (name, value) = header
+            .split_once('=')
+            .filter(|(name, _)| !name.is_empty())
+            .ok_or_else(|| HeaderRuleError::InvalidFormat(s.to_owned()))?;
+        Self::new(pattern, name, value)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct HeaderFileEntry {
+    #[serde(default)]
+    headers: IndexMap<String, String>,
+    cookie: Option<String>,
+    referer: Option<String>,
+    user_agent: Option<String>,
+    basic_auth: Option<BasicAuth>,
+}
+
+#[derive(Deserialize)]
+struct BasicAuth {
+    username: String,
+    #[serde(default)]
+    password: String,
+}
+
+/// The set of custom headers to send when downloading installers
+#[derive(Clone, Debug, Default)]
+pub struct DownloadHeaders(Vec<HeaderRule>);
+
+impl DownloadHeaders {
+    pub const fn new(rules: Vec<HeaderRule>) -> Self {
+        Self(rules)
+    }
+
+    /// Reads header rules from a YAML file that maps hosts or URL prefixes to headers:
+    ///
+    /// ```yaml
+    /// example.com:
+    ///   cookie: session=abc
+    ///   referer: https://example.com/downloads
+    /// "https://cdn.example.com/private/":
+    ///   basic-auth:
+    ///     username: user
+    ///     password: pass
+    ///   headers:
+    ///     X-Api-Key: key
+    /// ```
+    pub fn from_file(path: &Utf8Path) -> Result<Self, HeaderRuleError> {
+        let contents = fs::read_to_string(path).map_err(|source| HeaderRuleError::ReadFile {
+            path: path.to_string(),
+            source,
+        })?;
+        Self::from_yaml(&contents).map_err(|error| match error {
+            HeaderRuleError::ParseFile { source, .. } => HeaderRuleError::ParseFile {
+                path: path.to_string(),
+                source,
+            },
+            error => error,
+        })
+    }
+
+    fn from_yaml(yaml: &str) -> Result<Self, HeaderRuleError> {
+        let entries =
+            serde_yaml::from_str::<IndexMap<String, HeaderFileEntry>>(yaml).map_err(|source| {
+                HeaderRuleError::ParseFile {
+                    path: String::new(),
+                    source,
+                }
+            })?;
+
+        let mut rules = Vec::new();
+        for (pattern, entry) in entries {
+            for (name, value) in &entry.headers {
+                rules.push(HeaderRule::new(&pattern, name, value)?);
+            }
+            if let Some(cookie) = &entry.cookie {
+                rules.push(HeaderRule::new(&pattern, "Cookie", cookie)?);
+            }
+            if let Some(referer) = &entry.referer {
+                rules.push(HeaderRule::new(&pattern, "Referer", referer)?);
+            }
+            if let Some(user_agent) = &entry.user_agent {
+                rules.push(HeaderRule::new(&pattern, "User-Agent", user_agent)?);
+            }
+            if let Some(BasicAuth { username, password }) = &entry.basic_auth {
+                let credentials =
+                    Base64::encode_string(format!("{username}:{password}").as_bytes());
+                rules.push(HeaderRule::new(
+                    &pattern,
+                    AUTHORIZATION.as_str(),
+                    &format!("Basic {credentials}"),
+                )?);
+            }
+        }
+        Ok(Self(rules))
+    }
+
+    pub fn extend(&mut self, rules: impl IntoIterator<Item = HeaderRule>) {
+        self.0.extend(rules);
+    }
+
+    /// Returns the headers that apply to a URL. Later rules override earlier ones with the same
+    /// name so that headers given on the command line take precedence over a headers file.
+    pub fn for_url(&self, url: &url::Url) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for rule in self.0.iter().filter(|rule| rule.matches(url)) {
+            headers.insert(rule.name.clone(), rule.value.clone());
+        }
+        if !headers.is_empty() {
+            debug!(
+                "Sending custom headers to {}: {:?}",
+                url.host_str().unwrap_or_default(),
+                headers.keys().collect::<Vec<_>>()
+            );
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use reqwest::header::{AUTHORIZATION, COOKIE, REFERER};
+    use rstest::rstest;
+    use url::Url;
+
+    use super::{DownloadHeaders, HeaderRule};
+
+    #[rstest]
+    #[case("example.com", "https://example.com/file.exe", true)]
+    #[case("example.com", "https://cdn.example.com/file.exe", false)]
+    #[case("*.example.com", "https://cdn.example.com/file.exe", true)]
+    #[case("*.example.com", "https://example.com/file.exe", true)]
+    #[case("*.example.com", "https://badexample.com/file.exe", false)]
+    #[case(
+        "https://example.com/private/",
+        "https://example.com/private/a.exe",
+        true
+    )]
+    #[case(
+        "https://example.com/private/",
+        "https://example.com/public/a.exe",
+        false
+    )]
+    fn rule_matches(#[case] pattern: &str, #[case] url: &str, #[case] expected: bool) {
+        let rule = HeaderRule::new(pattern, "Cookie", "a=b").unwrap();
+        assert_eq!(rule.matches(&Url::parse(url).unwrap()), expected);
+    }
+
+    #[test]
+    fn parse_cli_rule() {
+        let rule = "example.com:Cookie=session=abc"
+            .parse::<HeaderRule>()
+            .unwrap();
+        assert_eq!(rule.pattern, "example.com");
+        assert_eq!(rule.name, COOKIE);
+        assert_eq!(rule.value, "session=abc");
+        assert!(rule.value.is_sensitive());
+    }
+
+    #[rstest]
+    #[case("example.com")]
+    #[case("example.com:Cookie")]
+    #[case(":Cookie=a")]
+    #[case("example.com:=a")]
+    fn parse_invalid_cli_rule(#[case] rule: &str) {
+        assert!(rule.parse::<HeaderRule>().is_err());
+    }
+
+    #[test]
+    fn headers_from_yaml() {
+        const YAML: &str = indoc! {"
+            example.com:
+              referer: https://example.com/downloads
+              headers:
+                Cookie: a=b
+            '*.vendor.com':
+              basic-auth:
+                username: user
+                password: pass
+        "};
+
+        let headers = DownloadHeaders::from_yaml(YAML).unwrap();
+
+        let example = headers.for_url(&Url::parse("https://example.com/setup.exe").unwrap());
+        assert_eq!(example[COOKIE], "a=b");
+        assert_eq!(example[REFERER], "https://example.com/downloads");
+        assert!(!example.contains_key(AUTHORIZATION));
+
+        let vendor = headers.for_url(&Url::parse("https://dl.vendor.com/setup.exe").unwrap());
+        assert_eq!(vendor[AUTHORIZATION], "Basic dXNlcjpwYXNz");
+        assert_eq!(vendor.len(), 1);
+    }
+
+    #[test]
+    fn later_rules_take_precedence() {
+        let mut headers = DownloadHeaders::new(vec!["example.com:Cookie=old".parse().unwrap()]);
+        headers.extend(["example.com:Cookie=new".parse().unwrap()]);
+        let map = headers.for_url(&Url::parse("https://example.com").unwrap());
+        assert_eq!(map[COOKIE], "new");
+    }
+}
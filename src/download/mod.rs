@@ -1,10 +1,12 @@
 mod downloader;
 mod file;
+mod headers;
 
 use camino::Utf8Path;
 use const_format::formatcp;
 pub use downloader::Downloader;
 pub use file::DownloadedFile;
+pub use headers::{DownloadHeaders, HeaderRule};
 use reqwest::{Client, ClientBuilder, Response, header::HeaderValue, redirect::Policy};
 use uuid::Uuid;
 use winget_types::installer::VALID_FILE_EXTENSIONS;
@@ -86,7 +88,7 @@ impl Download {
             .map_or_else(|| Uuid::new_v4().to_string(), str::to_owned)
     }
 
-    pub async fn upgrade_to_https(&mut self, client: &Client) {
+    pub async fn upgrade_to_https(&mut self, client: &Client, headers: &DownloadHeaders) {
         const HTTP: &str = "http";
         const HTTPS: &str = "https";
 
@@ -96,6 +98,7 @@ impl Download {
                 .unwrap_or_else(|()| unreachable!());
             if client
                 .head(self.url.as_str())
+                .headers(headers.for_url(&self.url))
                 .send()
                 .await
                 .and_then(Response::error_for_status)
@@ -3,9 +3,11 @@ use std::{io::Cursor, mem};
 use camino::Utf8Path;
 use color_eyre::eyre::{Result, bail};
 use inno::error::InnoError;
+use itertools::Itertools;
 use memmap2::Mmap;
 use tracing::debug;
 use winget_types::{
+    PackageVersion, Version,
     installer::{Architecture, Installer, InstallerType},
     locale::{Copyright, PackageName, Publisher},
 };
@@ -13,12 +15,16 @@ use yara_x::mods::PE;
 
 use crate::{
     installers::{
+        authenticode::Signer,
         burn::{Burn, BurnError},
         inno::Inno,
         msi::Msi,
         msix_family::{Msix, bundle::MsixBundle},
         nsis::{Nsis, NsisError},
+        portable::PortableMetadata,
         possible_installers::PossibleInstaller,
+        qt_ifw::{QtIfw, QtIfwError},
+        switch_rules::SwitchRules,
         zip::Zip,
     },
     traits::{FromMachine, FromVSVersionInfo},
@@ -32,8 +38,10 @@ pub const MSIX_BUNDLE: &str = "msixbundle";
 pub const APPX_BUNDLE: &str = "appxbundle";
 pub const ZIP: &str = "zip";
 
-const ORIGINAL_FILENAME: &str = "OriginalFilename";
+pub const ORIGINAL_FILENAME: &str = "OriginalFilename";
 const FILE_DESCRIPTION: &str = "FileDescription";
+const PRODUCT_VERSION: &str = "ProductVersion";
+const FILE_VERSION: &str = "FileVersion";
 const BASIC_INSTALLER_KEYWORDS: [&str; 4] = ["installer", "setup", "7zs.sfx", "7zsd.sfx"];
 
 pub struct FileAnalyser<'data> {
@@ -41,8 +49,12 @@ pub struct FileAnalyser<'data> {
     pub copyright: Option<Copyright>,
     pub package_name: Option<PackageName>,
     pub publisher: Option<Publisher>,
+    pub signer: Option<Signer>,
+    pub product_version: Option<Version>,
+    pub file_version: Option<Version>,
     pub installers: Vec<Installer>,
     pub zip: Option<Zip<Cursor<&'data [u8]>>>,
+    pub portable: Option<PortableMetadata>,
 }
 
 impl<'data> FileAnalyser<'data> {
@@ -55,8 +67,18 @@ impl<'data> FileAnalyser<'data> {
         let mut copyright = None;
         let mut package_name = None;
         let mut publisher = None;
+        let mut signer = None;
+        let mut product_version = None;
+        let mut file_version = None;
+        let mut portable = None;
         let installer = match extension.as_str() {
-            MSI => PossibleInstaller::Msi(Msi::new(Cursor::new(data.as_ref()))?),
+            MSI => {
+                signer = Signer::from_msi(data.as_ref()).unwrap_or_else(|error| {
+                    debug!(%error, "Failed to read MSI signature");
+                    None
+                });
+                PossibleInstaller::Msi(Msi::new(Cursor::new(data.as_ref()))?)
+            }
             MSIX | APPX => PossibleInstaller::Msix(Msix::new(Cursor::new(data.as_ref()))?),
             MSIX_BUNDLE | APPX_BUNDLE => {
                 PossibleInstaller::MsixBundle(MsixBundle::new(Cursor::new(data.as_ref()))?)
@@ -73,35 +95,39 @@ impl<'data> FileAnalyser<'data> {
                 copyright = Copyright::from_version_info(&pe.version_info);
                 package_name = PackageName::from_version_info(&pe.version_info);
                 publisher = Publisher::from_version_info(&pe.version_info);
+                signer = Signer::from_pe(&pe);
+                product_version = version_from_version_info(&pe, PRODUCT_VERSION);
+                file_version = version_from_version_info(&pe, FILE_VERSION);
+                portable = Some(PortableMetadata::new(
+                    data,
+                    &pe,
+                    file_name,
+                    Architecture::from_machine(pe.machine()),
+                ));
                 match Burn::new(data.as_ref(), &pe) {
                     Ok(burn) => PossibleInstaller::Burn(burn),
                     Err(BurnError::NotBurnFile) => match Nsis::new(data.as_ref(), &pe) {
                         Ok(nsis_file) => PossibleInstaller::Nsis(nsis_file),
                         Err(NsisError::NotNsisFile) => match Inno::new(data.as_ref()) {
                             Ok(inno_file) => PossibleInstaller::Inno(inno_file),
-                            Err(InnoError::NotInnoFile) => PossibleInstaller::Other(Installer {
-                                architecture: Architecture::from_machine(pe.machine()),
-                                r#type: pe
-                                    .version_info_list
-                                    .iter()
-                                    .filter(|key_value| {
-                                        matches!(
-                                            key_value.key(),
-                                            FILE_DESCRIPTION | ORIGINAL_FILENAME
-                                        )
-                                    })
-                                    .filter_map(|key_value| {
-                                        key_value.value.as_deref().map(str::to_ascii_lowercase)
-                                    })
-                                    .any(|value| {
-                                        BASIC_INSTALLER_KEYWORDS
-                                            .iter()
-                                            .any(|keyword| value.contains(keyword))
+                            Err(InnoError::NotInnoFile) => match QtIfw::new(data, &pe) {
+                                Ok(qt_ifw) => PossibleInstaller::QtIfw(qt_ifw),
+                                Err(QtIfwError::NotQtIfwFile) => {
+                                    PossibleInstaller::Other(match SwitchRules::find(data, &pe) {
+                                        Some(rule) => Installer {
+                                            architecture: Architecture::from_machine(pe.machine()),
+                                            r#type: Some(rule.r#type),
+                                            switches: rule.switches(),
+                                            ..Installer::default()
+                                        },
+                                        None => Installer {
+                                            architecture: Architecture::from_machine(pe.machine()),
+                                            r#type: basic_installer_type(&pe),
+                                            ..Installer::default()
+                                        },
                                     })
-                                    .then_some(InstallerType::Exe)
-                                    .or(Some(InstallerType::Portable)),
-                                ..Installer::default()
-                            }),
+                                }
+                            },
                             Err(inno_error) => return Err(inno_error.into()),
                         },
                         Err(nsis_error) => return Err(nsis_error.into()),
@@ -111,13 +137,85 @@ impl<'data> FileAnalyser<'data> {
             }
             _ => bail!(r#"Unsupported file extension: "{extension}""#),
         };
+        let mut installers = installer.installers();
+        if let Some(portable) = &portable {
+            installers
+                .iter_mut()
+                .for_each(|installer| portable.enrich(installer));
+        }
         Ok(Self {
-            installers: installer.installers(),
+            installers,
             file_name: String::new(),
             copyright,
             package_name,
             publisher,
+            signer,
+            product_version,
+            file_version,
             zip,
+            portable,
         })
     }
 }
+
+/// Guesses whether an EXE is an installer or a portable from its version info
+fn basic_installer_type(pe: &PE) -> Option<InstallerType> {
+    pe.version_info_list
+        .iter()
+        .filter(|key_value| matches!(key_value.key(), FILE_DESCRIPTION | ORIGINAL_FILENAME))
+        .filter_map(|key_value| key_value.value.as_deref().map(str::to_ascii_lowercase))
+        .any(|value| {
+            BASIC_INSTALLER_KEYWORDS
+                .iter()
+                .any(|keyword| value.contains(keyword))
+        })
+        .then_some(InstallerType::Exe)
+        .or(Some(InstallerType::Portable))
+}
+
+/// Reads a value from version info, ignoring values that are empty or only whitespace
+pub fn version_info<'pe>(pe: &'pe PE, key: &str) -> Option<&'pe str> {
+    pe.version_info
+        .get(key)
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+}
+
+/// Reads a version from version info, where older resource compilers separate parts with commas
+fn version_from_version_info(pe: &PE, key: &str) -> Option<Version> {
+    version_info(pe, key)
+        .map(|version| version.replace(", ", ".").replace(',', "."))
+        .map(Version::new)
+}
+
+impl FileAnalyser<'_> {
+    /// Fills in the metadata of a portable that an installer does not already have, for when an
+    /// installer from this file becomes a portable after it was analysed
+    pub fn enrich_portable(&self, installer: &mut Installer) {
+        if let Some(portable) = &self.portable {
+            portable.enrich(installer);
+        }
+    }
+
+    /// Returns the version info versions if neither of them match the package version
+    pub fn mismatched_versions(&self, package_version: &PackageVersion) -> Option<Vec<&Version>> {
+        let versions = [&self.product_version, &self.file_version]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        (!versions.is_empty() && versions.iter().all(|&version| version != package_version))
+            .then_some(versions)
+    }
+
+    /// Returns the version info versions of a portable if neither of them match the package
+    /// version. Other installers often have a version info of their own, so only a portable's
+    /// version is expected to be the package version.
+    pub fn portable_version_mismatch(&self, package_version: &PackageVersion) -> Option<String> {
+        self.installers
+            .iter()
+            .any(|installer| installer.r#type == Some(InstallerType::Portable))
+            .then(|| self.mismatched_versions(package_version))
+            .flatten()
+            .map(|versions| versions.into_iter().unique().join(" / "))
+    }
+}
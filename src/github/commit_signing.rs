@@ -0,0 +1,196 @@
+use std::{
+    env,
+    fmt::Write as _,
+    io,
+    io::Write,
+    process::{Command, Stdio},
+    str::FromStr,
+};
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+const KOMAC_SIGNING_KEY: &str = "KOMAC_SIGNING_KEY";
+const KOMAC_SIGNING_FORMAT: &str = "KOMAC_SIGNING_FORMAT";
+const KOMAC_COMMIT_AUTHOR_NAME: &str = "KOMAC_COMMIT_AUTHOR_NAME";
+const KOMAC_COMMIT_AUTHOR_EMAIL: &str = "KOMAC_COMMIT_AUTHOR_EMAIL";
+
+/// The namespace used by git for SSH signatures
+const SSH_NAMESPACE: &str = "git";
+
+#[derive(Debug, Error)]
+pub enum SigningError {
+    #[error("{0} must be set to sign commits")]
+    MissingVariable(&'static str),
+    #[error("Unknown signing format `{0}`. Expected `openpgp` or `ssh`")]
+    UnknownFormat(String),
+    #[error("Failed to run {program}: {source}")]
+    Io {
+        program: &'static str,
+        source: io::Error,
+    },
+    #[error("{program} failed to sign the commit: {stderr}")]
+    Failed {
+        program: &'static str,
+        stderr: String,
+    },
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum SigningFormat {
+    #[default]
+    OpenPgp,
+    Ssh,
+}
+
+impl FromStr for SigningFormat {
+    type Err = SigningError;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_ascii_lowercase().as_str() {
+            "openpgp" | "gpg" => Ok(Self::OpenPgp),
+            "ssh" => Ok(Self::Ssh),
+            _ => Err(SigningError::UnknownFormat(format.to_owned())),
+        }
+    }
+}
+
+/// Signs commits with a locally held OpenPGP or SSH key, in the same way as git's `gpg.format`
+/// and `user.signingKey` options.
+pub struct CommitSigner {
+    pub format: SigningFormat,
+    /// The OpenPGP key ID or the path to the SSH private key
+    pub key: String,
+    pub name: String,
+    pub email: String,
+}
+
+impl CommitSigner {
+    /// Returns a signer if `KOMAC_SIGNING_KEY` is set. The author's name and email must also be set
+    /// so that the signature can be verified against the author's account.
+    pub fn from_env() -> Result<Option<Self>, SigningError> {
+        let Ok(key) = env::var(KOMAC_SIGNING_KEY) else {
+            return Ok(None);
+        };
+        let format = env::var(KOMAC_SIGNING_FORMAT)
+            .ok()
+            .map(|format| format.parse())
+            .transpose()?
+            .unwrap_or_default();
+        let name = env::var(KOMAC_COMMIT_AUTHOR_NAME)
+            .map_err(|_| SigningError::MissingVariable(KOMAC_COMMIT_AUTHOR_NAME))?;
+        let email = env::var(KOMAC_COMMIT_AUTHOR_EMAIL)
+            .map_err(|_| SigningError::MissingVariable(KOMAC_COMMIT_AUTHOR_EMAIL))?;
+        Ok(Some(Self {
+            format,
+            key,
+            name,
+            email,
+        }))
+    }
+
+    /// Creates an armored detached signature of the payload
+    pub fn sign(&self, payload: &str) -> Result<String, SigningError> {
+        let (program, args) = match self.format {
+            SigningFormat::OpenPgp => ("gpg", vec!["--detach-sign", "--armor", "--local-user"]),
+            SigningFormat::Ssh => ("ssh-keygen", vec!["-Y", "sign", "-n", SSH_NAMESPACE, "-f"]),
+        };
+
+        let mut child = Command::new(program)
+            .args(args)
+            .arg(&self.key)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|source| SigningError::Io { program, source })?;
+
+        // ssh-keygen writes the signature to stdout when the message is read from stdin
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(payload.as_bytes())
+                .map_err(|source| SigningError::Io { program, source })?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|source| SigningError::Io { program, source })?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(SigningError::Failed {
+                program,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            })
+        }
+    }
+}
+
+/// Builds the commit object that git hashes and that the signature must cover
+pub fn commit_payload(
+    tree: &str,
+    parent: &str,
+    signer: &CommitSigner,
+    date: DateTime<Utc>,
+    message: &str,
+) -> String {
+    let identity = format!(
+        "{} <{}> {} +0000",
+        signer.name,
+        signer.email,
+        date.timestamp()
+    );
+    let mut payload = String::new();
+    let _ = writeln!(payload, "tree {tree}");
+    let _ = writeln!(payload, "parent {parent}");
+    let _ = writeln!(payload, "author {identity}");
+    let _ = writeln!(payload, "committer {identity}");
+    let _ = writeln!(payload);
+    payload.push_str(message);
+    payload
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use indoc::indoc;
+    use rstest::rstest;
+
+    use super::{CommitSigner, SigningFormat, commit_payload};
+
+    #[rstest]
+    #[case("openpgp", SigningFormat::OpenPgp)]
+    #[case("GPG", SigningFormat::OpenPgp)]
+    #[case("ssh", SigningFormat::Ssh)]
+    fn signing_format(#[case] format: &str, #[case] expected: SigningFormat) {
+        assert_eq!(format.parse::<SigningFormat>().unwrap(), expected);
+    }
+
+    #[test]
+    fn payload() {
+        let signer = CommitSigner {
+            format: SigningFormat::Ssh,
+            key: String::new(),
+            name: String::from("Komac"),
+            email: String::from("komac@example.com"),
+        };
+
+        assert_eq!(
+            commit_payload(
+                "4b825dc642cb6eb9a060e54bf8d69288fbee4904",
+                "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391",
+                &signer,
+                DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+                "New version: Package.Identifier version 1.2.3\n",
+            ),
+            indoc! {"
+                tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904
+                parent e69de29bb2d1d6434b8b29ae775ad8c2e48c5391
+                author Komac <komac@example.com> 1700000000 +0000
+                committer Komac <komac@example.com> 1700000000 +0000
+
+                New version: Package.Identifier version 1.2.3
+            "}
+        );
+    }
+}
@@ -1,20 +1,35 @@
-use std::{borrow::Cow, collections::BTreeSet, env, num::NonZeroU32, str::FromStr};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    env, fmt,
+    num::NonZeroU32,
+    str::FromStr,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
 
 use base64ct::{Base64, Encoding};
 use bon::bon;
-use const_format::{formatcp, str_repeat};
+use chrono::{DateTime, Local, SecondsFormat, TimeDelta, Timelike, Utc};
+use const_format::str_repeat;
 use cynic::{
-    GraphQlError, GraphQlResponse, Id, MutationBuilder, QueryBuilder,
-    http::{CynicReqwestError, ReqwestExt},
+    GraphQlError, GraphQlResponse, Id, MutationBuilder, Operation, QueryBuilder,
+    http::CynicReqwestError,
 };
+use derive_more::Display;
 use futures_util::future::OptionFuture;
 use indexmap::IndexMap;
 use indicatif::ProgressBar;
 use itertools::Itertools;
 use owo_colors::OwoColorize;
-use reqwest::{Client, header::ACCEPT};
-use serde::Deserialize;
+use reqwest::{
+    Client, IntoUrl, Method, Request, RequestBuilder, Response, StatusCode,
+    header::{ACCEPT, HeaderMap},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use thiserror::Error;
+use tokio::time::sleep;
+use tracing::warn;
 use url::Url;
 use winget_types::{
     Manifest, ManifestType, ManifestTypeWithLocale, PackageIdentifier, PackageVersion,
@@ -26,20 +41,27 @@ use winget_types::{
 
 use crate::{
     commands::{cleanup::MergeState, utils::SPINNER_TICK_RATE},
-    credential::get_default_headers,
+    credential::{GitHubToken, get_default_headers},
     github::{
+        app::{GitHubApp, GitHubAppError},
+        commit_signing::{CommitSigner, SigningError, commit_payload},
         graphql::{
+            add_comment::{AddComment, AddCommentVariables},
+            create_commit,
             create_commit::{
                 CommitMessage, CommittableBranch, CreateCommit, CreateCommitOnBranchInput,
                 CreateCommitVariables, FileAddition, FileChanges, FileDeletion,
             },
+            create_pull_request,
             create_pull_request::{
                 CreatePullRequest, CreatePullRequestInput, CreatePullRequestVariables,
             },
+            create_ref,
             create_ref::{CreateRef, CreateRefVariables, Ref as CreateBranchRef},
             get_all_values::{GetAllValues, GetAllValuesGitObject, GetAllValuesVariables, Tree},
             get_branches::{
-                GetBranches, GetBranchesVariables, PullRequest, PullRequestState, RefConnection,
+                GetBranches, GetBranchesVariables, PullRequest, PullRequestBranchRef,
+                PullRequestState, RefConnection,
             },
             get_current_user_login::GetCurrentUserLogin,
             get_directory_content::{GetDirectoryContent, GetDirectoryContentVariables},
@@ -47,26 +69,110 @@ use crate::{
             get_existing_pull_request,
             get_existing_pull_request::{GetExistingPullRequest, GetExistingPullRequestVariables},
             get_file_content::GetFileContent,
+            get_pull_request,
+            get_pull_request::{GetPullRequest, GetPullRequestVariables},
+            get_ref::{GetRef, GetRefVariables, Ref as ExistingRef},
             get_repository_info::{GetRepositoryInfo, RepositoryVariables, TargetGitObject},
             merge_upstream::{MergeUpstream, MergeUpstreamVariables},
             types::{Base64String, GitObjectId, GitRefName},
             update_refs::{RefUpdate, UpdateRefs, UpdateRefsVariables},
         },
-        rest::{GITHUB_JSON_MIME, get_tree::GitTree},
+        rate_limit::{
+            MAX_RETRIES, MAX_WAIT, PullRequestGovernor, RateLimits, backoff, graphql_retry_delay,
+            retry_delay,
+        },
+        rest::{
+            GITHUB_JSON_MIME,
+            get_tree::GitTree,
+            git_database::{
+                CommitIdentity, CreateCommit as CreateGitCommit, CreateTree, CreatedCommit,
+                CreatedTree, TreeEntry as GitTreeEntry,
+            },
+        },
         utils::{
-            PackagePath, get_branch_name, get_commit_title, is_manifest_file, pull_request_body,
+            PackagePath, is_manifest_file, pull_request_body,
+            template::{PullRequestTemplates, TemplateValues},
         },
     },
-    manifests::Manifests,
+    manifests::{
+        Manifests,
+        migrate::{Migration, migrate},
+    },
     traits::FromHtml,
     update_state::UpdateState,
 };
 
 pub const MICROSOFT: &str = "microsoft";
 pub const WINGET_PKGS: &str = "winget-pkgs";
-pub const WINGET_PKGS_FULL_NAME: &str = formatcp!("{MICROSOFT}/{WINGET_PKGS}");
+/// Displays the full name of the [`Upstream`] repository
+pub const WINGET_PKGS_FULL_NAME: UpstreamFullName = UpstreamFullName;
 pub const GITHUB_HOST: &str = "github.com";
 const GITHUB_GRAPHQL_URL: &str = "https://api.github.com/graphql";
+/// The rate limit resource that GraphQL queries count against, by the points each one costs
+const GRAPHQL_RESOURCE: &str = "graphql";
+/// The rate limit resource that most REST requests count against
+const CORE_RESOURCE: &str = "core";
+const KOMAC_UPSTREAM: &str = "KOMAC_UPSTREAM";
+
+/// The repository that pull requests are created in. This is `microsoft/winget-pkgs` unless
+/// `KOMAC_UPSTREAM` is set to another repository with the same layout, such as a private mirror.
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+#[display("{owner}/{name}")]
+pub struct Upstream {
+    pub owner: String,
+    pub name: String,
+}
+
+impl Default for Upstream {
+    fn default() -> Self {
+        Self {
+            owner: MICROSOFT.to_owned(),
+            name: WINGET_PKGS.to_owned(),
+        }
+    }
+}
+
+impl FromStr for Upstream {
+    type Err = &'static str;
+
+    fn from_str(repository: &str) -> Result<Self, Self::Err> {
+        match repository.split_once('/') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+                Ok(Self {
+                    owner: owner.to_owned(),
+                    name: name.to_owned(),
+                })
+            }
+            _ => Err("Expected a repository in the format of owner/name"),
+        }
+    }
+}
+
+impl Upstream {
+    pub fn get() -> &'static Self {
+        static UPSTREAM: LazyLock<Upstream> = LazyLock::new(|| {
+            env::var(KOMAC_UPSTREAM)
+                .ok()
+                .and_then(|repository| {
+                    repository
+                        .parse()
+                        .inspect_err(|err| warn!("Ignoring {KOMAC_UPSTREAM}: {err}"))
+                        .ok()
+                })
+                .unwrap_or_default()
+        });
+        &UPSTREAM
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct UpstreamFullName;
+
+impl fmt::Display for UpstreamFullName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Upstream::get().fmt(f)
+    }
+}
 
 #[derive(Debug, Error)]
 pub enum GitHubError {
@@ -74,6 +180,8 @@ pub enum GitHubError {
     GraphQL(Vec<GraphQlError>),
     #[error("{0} does not exist in {WINGET_PKGS_FULL_NAME}")]
     PackageNonExistent(PackageIdentifier),
+    #[error("No packages by {0} exist in {WINGET_PKGS_FULL_NAME}")]
+    PublisherNonExistent(String),
     #[error("No {type} manifest was found in {path}")]
     ManifestNotFound {
         r#type: ManifestType,
@@ -81,6 +189,20 @@ pub enum GitHubError {
     },
     #[error("No valid files were found for {path}")]
     NoValidFiles { path: PackagePath },
+    #[error("The contents of a file to commit are not valid Base64")]
+    InvalidFileContents,
+    #[error(transparent)]
+    Signing(#[from] SigningError),
+    #[error("Pull request #{0} does not exist in {WINGET_PKGS_FULL_NAME}")]
+    PullRequestNonExistent(i32),
+    #[error("The branch of {0} no longer exists")]
+    PullRequestBranchDeleted(Url),
+    #[error("{url} was not opened from {user}'s fork and cannot be amended")]
+    NotPullRequestAuthor { url: Url, user: String },
+    #[error("{0} already contains these changes")]
+    NothingToAmend(Url),
+    #[error("No packages were given to submit")]
+    NoPackages,
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
     #[error(transparent)]
@@ -89,20 +211,312 @@ pub enum GitHubError {
     YamlError(#[from] serde_yaml::Error),
     #[error(transparent)]
     UrlParseError(#[from] url::ParseError),
+    #[error(transparent)]
+    GitHubApp(#[from] GitHubAppError),
+    #[error("The GitHub API rate limit has been reached. Try again after {}", .0.format("%X"))]
+    RateLimited(DateTime<Local>),
+    #[error(
+        "The GitHub token is missing the `{permission}` permission on {repository}. Fine-grained tokens need `contents: write` and `pull_requests: write` on your fork"
+    )]
+    MissingPermission {
+        permission: Cow<'static, str>,
+        repository: String,
+    },
+}
+
+/// The message of errors caused by a fine-grained or installation token lacking a permission
+const RESOURCE_NOT_ACCESSIBLE: &str = "Resource not accessible by";
+/// The header that lists the permissions a fine-grained token needs for a REST request
+const ACCEPTED_PERMISSIONS: &str = "x-accepted-github-permissions";
+const CONTENTS_WRITE: &str = "contents: write";
+const PULL_REQUESTS_WRITE: &str = "pull_requests: write";
+
+impl GitHubError {
+    /// Reports GraphQL errors caused by the token lacking a permission as
+    /// [`GitHubError::MissingPermission`]
+    fn from_graphql(
+        errors: Option<Vec<GraphQlError>>,
+        permission: &'static str,
+        repository: String,
+    ) -> Self {
+        let errors = errors.unwrap_or_default();
+        if errors
+            .iter()
+            .any(|error| error.message.contains(RESOURCE_NOT_ACCESSIBLE))
+        {
+            Self::MissingPermission {
+                permission: Cow::Borrowed(permission),
+                repository,
+            }
+        } else {
+            Self::GraphQL(errors)
+        }
+    }
+}
+
+fn fork_description() -> String {
+    format!("your fork of {WINGET_PKGS_FULL_NAME}")
+}
+
+/// Reports REST requests to the fork's Git database rejected because the token lacks a
+/// permission as [`GitHubError::MissingPermission`], naming the permission that GitHub accepts
+fn require_permission(response: Response) -> Result<Response, GitHubError> {
+    if response.status() == StatusCode::FORBIDDEN
+        && let Some(permission) = response
+            .headers()
+            .get(ACCEPTED_PERMISSIONS)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_accepted_permissions)
+    {
+        Err(GitHubError::MissingPermission {
+            permission: Cow::Owned(permission),
+            repository: fork_description(),
+        })
+    } else {
+        Ok(response.error_for_status()?)
+    }
+}
+
+/// Formats the value of the [`ACCEPTED_PERMISSIONS`] header, such as `contents=write;
+/// metadata=read`, like `contents: write, metadata: read`. When the header lists alternative sets
+/// of permissions separated by commas, only the first set is used.
+fn parse_accepted_permissions(value: &str) -> Option<String> {
+    let (alternative, _) = value.split_once(',').unwrap_or((value, ""));
+    let permissions = alternative
+        .split(';')
+        .map(|permission| {
+            let (name, access) = permission.split_once('=')?;
+            Some(format!("{}: {}", name.trim(), access.trim()))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(permissions.join(", "))
 }
 
 #[derive(Clone)]
-#[repr(transparent)]
-pub struct GitHub(Client);
+pub struct GitHub {
+    client: Client,
+    app: Option<Arc<GitHubApp>>,
+    rate_limits: Arc<RateLimits>,
+    pull_requests: Arc<PullRequestGovernor>,
+}
 
 #[bon]
 impl GitHub {
-    pub fn new(token: &str) -> Result<Self, GitHubError> {
-        Ok(Self(
-            Client::builder()
+    pub fn new(token: &GitHubToken) -> Result<Self, GitHubError> {
+        Ok(Self {
+            client: Client::builder()
                 .default_headers(get_default_headers(Some(token)))
                 .build()?,
-        ))
+            app: token.app().cloned(),
+            rate_limits: Arc::default(),
+            pull_requests: Arc::default(),
+        })
+    }
+
+    /// The governor that spaces out pull requests created by any clone of this client
+    pub fn pull_requests(&self) -> &PullRequestGovernor {
+        &self.pull_requests
+    }
+
+    /// Starts a request, using a fresh installation token when authenticated as a GitHub App so
+    /// that long-running commands are not interrupted by the token expiring
+    async fn request(
+        &self,
+        method: Method,
+        url: impl IntoUrl,
+    ) -> Result<RequestBuilder, GitHubError> {
+        let request = self.client.request(method, url);
+        match &self.app {
+            Some(app) => Ok(request.bearer_auth(app.token().await?)),
+            None => Ok(request),
+        }
+    }
+
+    async fn post(&self, url: impl IntoUrl) -> Result<RequestBuilder, GitHubError> {
+        self.request(Method::POST, url).await
+    }
+
+    async fn get(&self, url: impl IntoUrl) -> Result<RequestBuilder, GitHubError> {
+        self.request(Method::GET, url).await
+    }
+
+    /// Sends a request, waiting for the rate limit to reset if it has been used up. `GET` requests
+    /// are also retried after transient server errors and rate limited responses.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, GitHubError> {
+        let request = request.build()?;
+        let retry = request.method() == Method::GET;
+        self.execute(request, retry).await
+    }
+
+    /// Sends a request that creates Git objects. Git objects are addressed by their content, so
+    /// sending the same request again cannot create a duplicate and it is retried like a `GET`.
+    async fn send_idempotent(&self, request: RequestBuilder) -> Result<Response, GitHubError> {
+        self.execute(request.build()?, true).await
+    }
+
+    async fn execute(&self, request: Request, retry: bool) -> Result<Response, GitHubError> {
+        let resource = if request.url().as_str() == GITHUB_GRAPHQL_URL {
+            GRAPHQL_RESOURCE
+        } else {
+            CORE_RESOURCE
+        };
+
+        let mut attempt = 0;
+        loop {
+            if let Some(wait_time) = self
+                .rate_limits
+                .get(resource)
+                .and_then(|rate_limit| rate_limit.wait_time(Utc::now()))
+            {
+                Self::wait_for_rate_limit(wait_time).await?;
+            }
+
+            let Some(next) = request.try_clone().filter(|_| retry) else {
+                let response = self.client.execute(request).await?;
+                self.rate_limits.update(response.headers());
+                return Ok(response);
+            };
+            let response = self.client.execute(next).await?;
+            self.rate_limits.update(response.headers());
+
+            if attempt < MAX_RETRIES
+                && let Some(delay) =
+                    retry_delay(response.status(), response.headers(), attempt, Utc::now())
+            {
+                warn!(
+                    "GitHub responded with {}. Retrying in {delay:?}",
+                    response.status()
+                );
+                Self::wait_for_rate_limit(delay).await?;
+                attempt += 1;
+                continue;
+            }
+
+            return Ok(response);
+        }
+    }
+
+    /// Sends a GraphQL query through [`GitHub::send`], also retrying queries that GitHub reports as
+    /// rate limited in the response's errors. Mutations are sent once, as GitHub may have applied a
+    /// mutation that it responds to with an error. Use [`GitHub::graphql_mutation`] to retry them.
+    async fn graphql<ResponseData, Vars>(
+        &self,
+        operation: Operation<ResponseData, Vars>,
+    ) -> Result<GraphQlResponse<ResponseData>, GitHubError>
+    where
+        ResponseData: DeserializeOwned,
+        Vars: Serialize,
+    {
+        let is_query = !operation.query.starts_with("mutation");
+        let mut attempt = 0;
+        loop {
+            let response = self.graphql_once(&operation, is_query).await?;
+
+            if is_query
+                && attempt < MAX_RETRIES
+                && let Some(errors) = &response.errors
+                && let Some(delay) = graphql_retry_delay(
+                    errors.iter().map(|error| &*error.message),
+                    self.rate_limits.get(GRAPHQL_RESOURCE),
+                    Utc::now(),
+                )
+            {
+                warn!("GitHub's GraphQL API is rate limited. Retrying in {delay:?}");
+                Self::wait_for_rate_limit(delay).await?;
+                attempt += 1;
+                continue;
+            }
+
+            return Ok(response);
+        }
+    }
+
+    /// Sends a GraphQL mutation, retrying it after transient errors only if `applied` finds that
+    /// the earlier attempt did not take effect. GitHub can apply a mutation and still respond
+    /// with a server error, so sending it again blindly could create duplicates.
+    async fn graphql_mutation<ResponseData, Vars, Applied>(
+        &self,
+        operation: Operation<ResponseData, Vars>,
+        applied: impl Fn() -> Applied,
+    ) -> Result<GraphQlResponse<ResponseData>, GitHubError>
+    where
+        ResponseData: DeserializeOwned,
+        Vars: Serialize,
+        Applied: Future<Output = Result<Option<ResponseData>, GitHubError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = self.graphql_once(&operation, false).await;
+            let delay = match &result {
+                Err(GitHubError::Reqwest(_)) => Some(backoff(attempt)),
+                Err(GitHubError::CynicRequest(CynicReqwestError::ErrorResponse(status, _))) => {
+                    retry_delay(*status, &HeaderMap::new(), attempt, Utc::now())
+                }
+                Ok(GraphQlResponse {
+                    errors: Some(errors),
+                    ..
+                }) => graphql_retry_delay(
+                    errors.iter().map(|error| &*error.message),
+                    self.rate_limits.get(GRAPHQL_RESOURCE),
+                    Utc::now(),
+                ),
+                _ => None,
+            };
+
+            let Some(delay) = delay.filter(|_| attempt < MAX_RETRIES) else {
+                return result;
+            };
+            warn!("A request to GitHub failed. Checking whether it took effect in {delay:?}");
+            Self::wait_for_rate_limit(delay).await?;
+            if let Some(data) = applied().await? {
+                return Ok(GraphQlResponse {
+                    data: Some(data),
+                    errors: None,
+                });
+            }
+            attempt += 1;
+        }
+    }
+
+    async fn graphql_once<ResponseData, Vars>(
+        &self,
+        operation: &Operation<ResponseData, Vars>,
+        retry: bool,
+    ) -> Result<GraphQlResponse<ResponseData>, GitHubError>
+    where
+        ResponseData: DeserializeOwned,
+        Vars: Serialize,
+    {
+        let request = self
+            .post(GITHUB_GRAPHQL_URL)
+            .await?
+            .json(operation)
+            .build()?;
+        let response = self.execute(request, retry).await?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(response.json::<GraphQlResponse<ResponseData>>().await?)
+        } else {
+            let text = response.text().await?;
+            serde_json::from_str(&text)
+                .map_err(|_| CynicReqwestError::ErrorResponse(status, text).into())
+        }
+    }
+
+    async fn wait_for_rate_limit(wait_time: Duration) -> Result<(), GitHubError> {
+        if wait_time > MAX_WAIT {
+            return Err(GitHubError::RateLimited(
+                Local::now() + TimeDelta::from_std(wait_time).unwrap_or(TimeDelta::MAX),
+            ));
+        }
+        let spinner = ProgressBar::new_spinner().with_message(format!(
+            "GitHub rate limit reached. Waiting for {:?}",
+            Duration::from_secs(wait_time.as_secs().max(1))
+        ));
+        spinner.enable_steady_tick(SPINNER_TICK_RATE);
+        sleep(wait_time).await;
+        spinner.finish_and_clear();
+        Ok(())
     }
 
     pub async fn get_username(&self) -> Result<String, GitHubError> {
@@ -111,11 +525,8 @@ impl GitHub {
         if let Ok(login) = env::var(KOMAC_FORK_OWNER) {
             Ok(login)
         } else {
-            let GraphQlResponse { data, errors } = self
-                .0
-                .post(GITHUB_GRAPHQL_URL)
-                .run_graphql(GetCurrentUserLogin::build(()))
-                .await?;
+            let GraphQlResponse { data, errors } =
+                self.graphql(GetCurrentUserLogin::build(())).await?;
             data.map(|data| data.viewer.login)
                 .ok_or_else(|| GitHubError::GraphQL(errors.unwrap_or_default()))
         }
@@ -126,8 +537,8 @@ impl GitHub {
         package_identifier: &PackageIdentifier,
     ) -> Result<BTreeSet<PackageVersion>, GitHubError> {
         self.get_all_versions(
-            MICROSOFT,
-            WINGET_PKGS,
+            &Upstream::get().owner,
+            &Upstream::get().name,
             &PackagePath::new(package_identifier, None, None),
         )
         .await
@@ -148,10 +559,7 @@ impl GitHub {
         );
 
         let GitTree { tree, .. } = self
-            .0
-            .get(endpoint)
-            .header(ACCEPT, GITHUB_JSON_MIME)
-            .send()
+            .send(self.get(endpoint).await?.header(ACCEPT, GITHUB_JSON_MIME))
             .await?
             .json::<GitTree>()
             .await?;
@@ -178,84 +586,224 @@ impl GitHub {
             .ok_or_else(|| GitHubError::NoValidFiles { path: path.clone() })
     }
 
+    /// Returns every version of every package whose identifier starts with a publisher, such as
+    /// `Contoso` for `Contoso.App`
+    pub async fn get_publisher_packages(
+        &self,
+        publisher: &str,
+    ) -> Result<BTreeMap<PackageIdentifier, BTreeSet<PackageVersion>>, GitHubError> {
+        const TREE: &str = "tree";
+
+        let first_character = publisher
+            .chars()
+            .next()
+            .ok_or_else(|| GitHubError::PublisherNonExistent(publisher.to_owned()))?
+            .to_ascii_lowercase();
+
+        // Publishers are case-insensitive, so their directory is found in the tree of their first
+        // character rather than by its exact path
+        let publishers = self
+            .get_tree(&format!("HEAD:manifests/{first_character}"), false)
+            .await?
+            .ok_or_else(|| GitHubError::PublisherNonExistent(publisher.to_owned()))?;
+        let directory = publishers
+            .tree
+            .into_iter()
+            .find(|object| object.r#type == TREE && object.path.eq_ignore_ascii_case(publisher))
+            .ok_or_else(|| GitHubError::PublisherNonExistent(publisher.to_owned()))?;
+
+        let tree = self
+            .get_tree(&directory.sha, true)
+            .await?
+            .ok_or_else(|| GitHubError::PublisherNonExistent(publisher.to_owned()))?;
+        if tree.truncated {
+            warn!(
+                "The tree of {} is too large to be listed completely",
+                directory.path
+            );
+        }
+
+        Ok(tree
+            .packages()
+            .into_iter()
+            .filter(|(identifier, _versions)| {
+                identifier
+                    .as_str()
+                    .split_once('.')
+                    .is_some_and(|(prefix, _rest)| prefix.eq_ignore_ascii_case(publisher))
+            })
+            .collect())
+    }
+
+    /// Gets a tree of the upstream repository, or `None` if it does not exist
+    async fn get_tree(&self, tree: &str, recursive: bool) -> Result<Option<GitTree>, GitHubError> {
+        let mut endpoint = format!(
+            "https://api.github.com/repos/{}/{}/git/trees/{tree}",
+            Upstream::get().owner,
+            Upstream::get().name
+        );
+        if recursive {
+            endpoint.push_str("?recursive=true");
+        }
+
+        let response = self
+            .send(self.get(endpoint).await?.header(ACCEPT, GITHUB_JSON_MIME))
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json::<GitTree>().await?))
+    }
+
     pub async fn get_manifests(
         &self,
         identifier: &PackageIdentifier,
         latest_version: &PackageVersion,
     ) -> Result<Manifests, GitHubError> {
-        let full_package_path = PackagePath::new(identifier, Some(latest_version), None);
-        let content = self
-            .get_directory_content_with_text(MICROSOFT, WINGET_PKGS, &full_package_path)
-            .await?
-            .collect::<Vec<_>>();
+        self.get_manifests_with_files(identifier, latest_version)
+            .await
+            .map(|(manifests, _)| manifests)
+    }
 
-        let version_manifest = content
-            .iter()
-            .find(|file| is_manifest_file::<VersionManifest>(&file.name, identifier, None))
-            .map(|file| serde_yaml::from_str::<VersionManifest>(&file.text))
-            .ok_or_else(|| GitHubError::ManifestNotFound {
-                r#type: ManifestType::Version,
-                path: full_package_path.clone(),
-            })??;
-
-        let locale_manifests = content
-            .iter()
-            .filter(|file| {
-                is_manifest_file::<LocaleManifest>(
-                    &file.name,
-                    identifier,
-                    Some(&version_manifest.default_locale),
-                )
+    /// Gets the manifests of a package version along with the files they were read from, in the
+    /// order installer, default locale, locales and version
+    pub async fn get_manifests_with_files(
+        &self,
+        identifier: &PackageIdentifier,
+        version: &PackageVersion,
+    ) -> Result<(Manifests, Vec<GitHubFile>), GitHubError> {
+        let (version_manifest, files) = self
+            .get_manifest_files(identifier, version, |file| {
+                serde_yaml::from_str::<VersionManifest>(&file.text)
             })
-            .map(|file| serde_yaml::from_str::<LocaleManifest>(&file.text))
-            .collect::<serde_yaml::Result<_>>()?;
+            .await?;
 
-        let default_locale_manifest = content
-            .iter()
-            .find(|file| {
-                is_manifest_file::<DefaultLocaleManifest>(
-                    &file.name,
-                    identifier,
-                    Some(&version_manifest.default_locale),
-                )
+        let manifests = Manifests {
+            installer: serde_yaml::from_str(&files.installer.text)?,
+            default_locale: serde_yaml::from_str(&files.default_locale.text)?,
+            locales: files
+                .locales
+                .iter()
+                .map(|file| serde_yaml::from_str(&file.text))
+                .collect::<serde_yaml::Result<_>>()?,
+            version: version_manifest,
+        };
+        let files = [files.installer, files.default_locale]
+            .into_iter()
+            .chain(files.locales)
+            .chain([files.version])
+            .collect();
+        Ok((manifests, files))
+    }
+
+    /// Gets the manifests of a package version as the latest manifest version, along with what
+    /// changed in each manifest file that was on an older manifest version
+    pub async fn get_migrated_manifests(
+        &self,
+        identifier: &PackageIdentifier,
+        version: &PackageVersion,
+    ) -> Result<(Manifests, Vec<(String, Migration)>), GitHubError> {
+        fn migrate_file<T: DeserializeOwned + Serialize>(
+            file: &GitHubFile,
+            migrations: &mut Vec<(String, Migration)>,
+        ) -> serde_yaml::Result<T> {
+            let (manifest, migration) = migrate::<T>(&file.text)?;
+            migrations.push((file.name.clone(), migration));
+            Ok(manifest)
+        }
+
+        let mut migrations = Vec::new();
+        let (version_manifest, files) = self
+            .get_manifest_files(identifier, version, |file| {
+                migrate_file::<VersionManifest>(file, &mut migrations)
             })
-            .map(|file| serde_yaml::from_str::<DefaultLocaleManifest>(&file.text))
-            .ok_or_else(|| GitHubError::ManifestNotFound {
-                r#type: ManifestType::DefaultLocale,
-                path: full_package_path.clone(),
-            })??;
+            .await?;
 
-        let installer_manifest = content
-            .into_iter()
-            .find(|file| is_manifest_file::<InstallerManifest>(&file.name, identifier, None))
-            .map(|file| serde_yaml::from_str::<InstallerManifest>(&file.text))
-            .ok_or_else(|| GitHubError::ManifestNotFound {
-                r#type: ManifestType::Installer,
-                path: full_package_path.clone(),
-            })??;
-
-        Ok(Manifests {
-            installer: installer_manifest,
-            default_locale: default_locale_manifest,
-            locales: locale_manifests,
+        let manifests = Manifests {
+            installer: migrate_file(&files.installer, &mut migrations)?,
+            default_locale: migrate_file(&files.default_locale, &mut migrations)?,
+            locales: files
+                .locales
+                .iter()
+                .map(|file| migrate_file(file, &mut migrations))
+                .collect::<serde_yaml::Result<_>>()?,
             version: version_manifest,
-        })
+        };
+        Ok((manifests, migrations))
+    }
+
+    /// Finds the manifest files of a package version by their file names, reading the version
+    /// manifest with `read_version` to find which locale is the default
+    async fn get_manifest_files(
+        &self,
+        identifier: &PackageIdentifier,
+        version: &PackageVersion,
+        read_version: impl FnOnce(&GitHubFile) -> serde_yaml::Result<VersionManifest>,
+    ) -> Result<(VersionManifest, ManifestFiles), GitHubError> {
+        let full_package_path = PackagePath::new(identifier, Some(version), None);
+        let mut content = self
+            .get_directory_content_with_text(
+                &Upstream::get().owner,
+                &Upstream::get().name,
+                "HEAD",
+                &full_package_path,
+            )
+            .await?
+            .collect::<Vec<_>>();
+
+        let not_found = |r#type| GitHubError::ManifestNotFound {
+            r#type,
+            path: full_package_path.clone(),
+        };
+
+        let version_file = content
+            .iter()
+            .position(|file| is_manifest_file::<VersionManifest>(&file.name, identifier, None))
+            .map(|index| content.swap_remove(index))
+            .ok_or_else(|| not_found(ManifestType::Version))?;
+        let version_manifest = read_version(&version_file)?;
+        let default_locale = Some(&version_manifest.default_locale);
+
+        let mut installer = None;
+        let mut default_locale_file = None;
+        let mut locales = Vec::new();
+        for file in content {
+            if is_manifest_file::<InstallerManifest>(&file.name, identifier, None) {
+                installer = Some(file);
+            } else if is_manifest_file::<DefaultLocaleManifest>(
+                &file.name,
+                identifier,
+                default_locale,
+            ) {
+                default_locale_file = Some(file);
+            } else if is_manifest_file::<LocaleManifest>(&file.name, identifier, default_locale) {
+                locales.push(file);
+            }
+        }
+
+        let files = ManifestFiles {
+            installer: installer.ok_or_else(|| not_found(ManifestType::Installer))?,
+            default_locale: default_locale_file
+                .ok_or_else(|| not_found(ManifestType::DefaultLocale))?,
+            locales,
+            version: version_file,
+        };
+        Ok((version_manifest, files))
     }
 
     async fn get_directory_content_with_text(
         &self,
         owner: &str,
         repo: &str,
+        branch_name: &str,
         path: &PackagePath,
     ) -> Result<impl Iterator<Item = GitHubFile>, GitHubError> {
         let GraphQlResponse { data, errors } = self
-            .0
-            .post(GITHUB_GRAPHQL_URL)
-            .run_graphql(GetDirectoryContentWithText::build(
+            .graphql(GetDirectoryContentWithText::build(
                 GetDirectoryContentVariables {
                     owner,
                     name: repo,
-                    expression: &format!("HEAD:{path}"),
+                    expression: &format!("{branch_name}:{path}"),
                 },
             ))
             .await?;
@@ -279,7 +827,9 @@ impl GitHub {
         manifest_type: ManifestTypeWithLocale,
     ) -> Result<T, GitHubError> {
         let path = PackagePath::new(identifier, Some(version), Some(&manifest_type));
-        let content = self.get_file_content(MICROSOFT, WINGET_PKGS, &path).await?;
+        let content = self
+            .get_file_content(&Upstream::get().owner, &Upstream::get().name, &path)
+            .await?;
         let manifest = serde_yaml::from_str::<T>(&content)?;
         Ok(manifest)
     }
@@ -291,9 +841,7 @@ impl GitHub {
         path: &PackagePath,
     ) -> Result<String, GitHubError> {
         let GraphQlResponse { data, errors } = self
-            .0
-            .post(GITHUB_GRAPHQL_URL)
-            .run_graphql(GetFileContent::build(GetDirectoryContentVariables {
+            .graphql(GetFileContent::build(GetDirectoryContentVariables {
                 owner,
                 name: repo,
                 expression: &format!("HEAD:{path}"),
@@ -308,7 +856,8 @@ impl GitHub {
         &self,
         #[builder(into)] owner: Option<Cow<'_, str>>,
     ) -> Result<RepositoryData, GitHubError> {
-        self.get_repository_info(owner.as_deref().unwrap_or(MICROSOFT), WINGET_PKGS)
+        let upstream = Upstream::get();
+        self.get_repository_info(owner.as_deref().unwrap_or(&upstream.owner), &upstream.name)
             .await
     }
 
@@ -318,9 +867,7 @@ impl GitHub {
         name: &str,
     ) -> Result<RepositoryData, GitHubError> {
         let GraphQlResponse { data, errors } = self
-            .0
-            .post(GITHUB_GRAPHQL_URL)
-            .run_graphql(GetRepositoryInfo::build(RepositoryVariables {
+            .graphql(GetRepositoryInfo::build(RepositoryVariables {
                 owner,
                 name,
             }))
@@ -357,17 +904,49 @@ impl GitHub {
         branch_name: &str,
         oid: GitObjectId,
     ) -> Result<CreateBranchRef, GitHubError> {
+        let name = format!("refs/heads/{branch_name}");
         let GraphQlResponse { data, errors } = self
-            .0
-            .post(GITHUB_GRAPHQL_URL)
-            .run_graphql(CreateRef::build(CreateRefVariables {
-                name: &format!("refs/heads/{branch_name}"),
-                oid,
-                repository_id: fork_id,
-            }))
+            .graphql_mutation(
+                CreateRef::build(CreateRefVariables {
+                    name: &name,
+                    oid: oid.clone(),
+                    repository_id: fork_id,
+                }),
+                || async {
+                    Ok(self.get_ref(fork_id, &name).await?.and_then(|ref_| {
+                        let target = ref_.target.filter(|target| target.oid == oid)?;
+                        Some(CreateRef {
+                            create_ref: Some(create_ref::CreateRefPayload {
+                                ref_: Some(CreateBranchRef {
+                                    id: ref_.id,
+                                    name: ref_.name,
+                                    target: Some(create_ref::GitObject { oid: target.oid }),
+                                }),
+                            }),
+                        })
+                    }))
+                },
+            )
             .await?;
         data.and_then(|data| data.create_ref?.ref_)
-            .ok_or_else(|| GitHubError::GraphQL(errors.unwrap_or_default()))
+            .ok_or_else(|| GitHubError::from_graphql(errors, CONTENTS_WRITE, fork_description()))
+    }
+
+    /// Gets a ref by its ID, or by its qualified name in the repository with the given ID
+    async fn get_ref(
+        &self,
+        id: &Id,
+        qualified_name: &str,
+    ) -> Result<Option<ExistingRef>, GitHubError> {
+        let GraphQlResponse { data, errors } = self
+            .graphql(GetRef::build(GetRefVariables { id, qualified_name }))
+            .await?;
+        match errors {
+            Some(errors) if data.is_none() => Err(GitHubError::GraphQL(errors)),
+            _ => Ok(data
+                .and_then(|data| data.node)
+                .and_then(|node| node.into_ref())),
+        }
     }
 
     #[builder(finish_fn = send)]
@@ -379,27 +958,144 @@ impl GitHub {
         additions: Option<Vec<FileAddition<'_>>>,
         deletions: Option<Vec<FileDeletion<'_>>>,
     ) -> Result<Url, GitHubError> {
+        if let Some(signer) = CommitSigner::from_env()? {
+            return self
+                .create_signed_commit(
+                    &signer,
+                    branch_id,
+                    head_sha,
+                    message,
+                    additions.as_deref().unwrap_or_default(),
+                    deletions.as_deref().unwrap_or_default(),
+                )
+                .await;
+        }
+
         let GraphQlResponse { data, errors } = self
-            .0
-            .post(GITHUB_GRAPHQL_URL)
-            .run_graphql(CreateCommit::build(CreateCommitVariables {
-                input: CreateCommitOnBranchInput {
-                    branch: CommittableBranch { id: branch_id },
-                    expected_head_oid: head_sha,
-                    file_changes: Some(FileChanges {
-                        additions,
-                        deletions,
-                    }),
-                    message: CommitMessage {
-                        body: None,
-                        headline: message,
+            .graphql_mutation(
+                CreateCommit::build(CreateCommitVariables {
+                    input: CreateCommitOnBranchInput {
+                        branch: CommittableBranch { id: branch_id },
+                        expected_head_oid: head_sha.clone(),
+                        file_changes: Some(FileChanges {
+                            additions,
+                            deletions,
+                        }),
+                        message: CommitMessage {
+                            body: None,
+                            headline: message,
+                        },
                     },
+                }),
+                // The commit was created if the branch no longer points at the expected head
+                || async {
+                    Ok(self
+                        .get_ref(branch_id, "")
+                        .await?
+                        .and_then(|ref_| ref_.target)
+                        .filter(|target| target.oid != head_sha)
+                        .map(|target| CreateCommit {
+                            create_commit_on_branch: Some(
+                                create_commit::CreateCommitOnBranchPayload {
+                                    commit: Some(create_commit::Commit {
+                                        url: target.commit_url,
+                                    }),
+                                },
+                            ),
+                        }))
                 },
-            }))
+            )
             .await?;
         data.and_then(|data| data.create_commit_on_branch?.commit)
             .map(|commit| commit.url)
-            .ok_or_else(|| GitHubError::GraphQL(errors.unwrap_or_default()))
+            .ok_or_else(|| GitHubError::from_graphql(errors, CONTENTS_WRITE, fork_description()))
+    }
+
+    /// Creates a commit signed with a local key through the Git database API, as commits created
+    /// with `createCommitOnBranch` can only be signed by GitHub
+    async fn create_signed_commit(
+        &self,
+        signer: &CommitSigner,
+        branch_id: &Id,
+        head_sha: GitObjectId,
+        message: &str,
+        additions: &[FileAddition<'_>],
+        deletions: &[FileDeletion<'_>],
+    ) -> Result<Url, GitHubError> {
+        let owner = self.get_username().await?;
+        let endpoint = format!(
+            "https://api.github.com/repos/{owner}/{}/git",
+            Upstream::get().name
+        );
+
+        let contents = additions
+            .iter()
+            .map(|addition| {
+                Base64::decode_vec(&addition.contents)
+                    .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| GitHubError::InvalidFileContents)?;
+        let tree = additions
+            .iter()
+            .zip(&contents)
+            .map(|(addition, content)| GitTreeEntry::addition(&addition.path, content.as_str()))
+            .chain(
+                deletions
+                    .iter()
+                    .map(|deletion| GitTreeEntry::deletion(&deletion.path)),
+            )
+            .collect::<Vec<_>>();
+        let CreatedTree { sha: tree_sha } = self
+            .send_idempotent(
+                self.post(format!("{endpoint}/trees"))
+                    .await?
+                    .header(ACCEPT, GITHUB_JSON_MIME)
+                    .json(&CreateTree {
+                        base_tree: &head_sha,
+                        tree,
+                    }),
+            )
+            .await
+            .and_then(require_permission)?
+            .json()
+            .await?;
+
+        // Git stores the time in seconds, so the signed payload must not include fractions
+        let date = Utc::now().with_nanosecond(0).unwrap_or_default();
+        let message = format!("{message}\n");
+        let signature = signer.sign(&commit_payload(
+            &tree_sha, &head_sha, signer, date, &message,
+        ))?;
+        let identity = CommitIdentity {
+            name: &signer.name,
+            email: &signer.email,
+            date: &date.to_rfc3339_opts(SecondsFormat::Secs, true),
+        };
+        let CreatedCommit { sha, html_url } = self
+            .send_idempotent(
+                self.post(format!("{endpoint}/commits"))
+                    .await?
+                    .header(ACCEPT, GITHUB_JSON_MIME)
+                    .json(&CreateGitCommit {
+                        message: &message,
+                        tree: &tree_sha,
+                        parents: [&head_sha],
+                        author: identity,
+                        committer: identity,
+                        signature: &signature,
+                    }),
+            )
+            .await
+            .and_then(require_permission)?
+            .json()
+            .await?;
+
+        // Moves the branch to the new commit, failing if it is not a fast-forward
+        self.merge_upstream(branch_id, GitObjectId::new(sha), false)
+            .await?;
+
+        Ok(html_url)
     }
 
     pub async fn get_directory_content(
@@ -409,11 +1105,9 @@ impl GitHub {
         path: &PackagePath,
     ) -> Result<impl Iterator<Item = String>, GitHubError> {
         let GraphQlResponse { data, errors } = self
-            .0
-            .post(GITHUB_GRAPHQL_URL)
-            .run_graphql(GetDirectoryContent::build(GetDirectoryContentVariables {
+            .graphql(GetDirectoryContent::build(GetDirectoryContentVariables {
                 expression: &format!("{branch_name}:{path}"),
-                name: WINGET_PKGS,
+                name: &Upstream::get().name,
                 owner,
             }))
             .await?;
@@ -429,16 +1123,68 @@ impl GitHub {
         user: T,
         merge_state: MergeState,
     ) -> Result<(IndexMap<PullRequest, String>, Id), GitHubError> {
+        let (branches, repository_id) = self.get_pull_request_branches(user).await?;
+
         let mut pr_branch_map = IndexMap::new();
+        for branch in branches.into_iter().filter(|branch| {
+            branch
+                .associated_pull_requests
+                .pull_requests
+                .iter()
+                .all(|pull_request| pull_request.state != PullRequestState::Open)
+        }) {
+            if let Some(pull_request) = branch
+                .associated_pull_requests
+                .pull_requests
+                .into_iter()
+                .filter(|pull_request| match merge_state {
+                    MergeState::MERGED => pull_request.state == PullRequestState::Merged,
+                    MergeState::CLOSED => pull_request.state == PullRequestState::Closed,
+                    _ => pull_request.state != PullRequestState::Open,
+                })
+                .find(|pull_request| {
+                    pull_request.repository.name_with_owner == WINGET_PKGS_FULL_NAME.to_string()
+                })
+            {
+                pr_branch_map.insert(pull_request, branch.name);
+            }
+        }
+
+        Ok((pr_branch_map, repository_id))
+    }
+
+    /// Returns the open pull requests to winget-pkgs from the user's fork, oldest first
+    pub async fn get_open_pull_requests<T: AsRef<str>>(
+        &self,
+        user: T,
+    ) -> Result<Vec<PullRequest>, GitHubError> {
+        let (branches, _repository_id) = self.get_pull_request_branches(user).await?;
+
+        Ok(branches
+            .into_iter()
+            .flat_map(|branch| branch.associated_pull_requests.pull_requests)
+            .filter(|pull_request| {
+                pull_request.state == PullRequestState::Open
+                    && pull_request.repository.name_with_owner == WINGET_PKGS_FULL_NAME.to_string()
+            })
+            .sorted_by_key(|pull_request| pull_request.created_at)
+            .collect())
+    }
+
+    /// Returns every branch of the user's fork except the default branch, along with the pull
+    /// requests associated with each branch
+    async fn get_pull_request_branches<T: AsRef<str>>(
+        &self,
+        user: T,
+    ) -> Result<(Vec<PullRequestBranchRef>, Id), GitHubError> {
+        let mut pull_request_branches = Vec::new();
         let mut cursor = None;
 
         loop {
             let GraphQlResponse { data, errors } = self
-                .0
-                .post(GITHUB_GRAPHQL_URL)
-                .run_graphql(GetBranches::build(GetBranchesVariables {
+                .graphql(GetBranches::build(GetBranchesVariables {
                     owner: user.as_ref(),
-                    name: WINGET_PKGS,
+                    name: &Upstream::get().name,
                     cursor: cursor.as_deref(),
                 }))
                 .await?;
@@ -458,38 +1204,16 @@ impl GitHub {
                 .refs
                 .ok_or_else(|| GitHubError::GraphQL(errors.unwrap_or_default()))?;
 
-            for branch in branches
-                .into_iter()
-                .filter(|branch| branch.name != default_branch.name)
-                .filter(|branch| {
-                    branch
-                        .associated_pull_requests
-                        .pull_requests
-                        .iter()
-                        .all(|pull_request| pull_request.state != PullRequestState::Open)
-                })
-            {
-                if let Some(pull_request) = branch
-                    .associated_pull_requests
-                    .pull_requests
+            pull_request_branches.extend(
+                branches
                     .into_iter()
-                    .filter(|pull_request| match merge_state {
-                        MergeState::MERGED => pull_request.state == PullRequestState::Merged,
-                        MergeState::CLOSED => pull_request.state == PullRequestState::Closed,
-                        _ => pull_request.state != PullRequestState::Open,
-                    })
-                    .find(|pull_request| {
-                        pull_request.repository.name_with_owner == WINGET_PKGS_FULL_NAME
-                    })
-                {
-                    pr_branch_map.insert(pull_request, branch.name);
-                }
-            }
+                    .filter(|branch| branch.name != default_branch.name),
+            );
 
             if page_info.has_next_page {
                 cursor = page_info.end_cursor;
             } else {
-                return Ok((pr_branch_map, repository.id));
+                return Ok((pull_request_branches, repository.id));
             }
         }
     }
@@ -503,6 +1227,7 @@ impl GitHub {
         title: &str,
         body: &str,
     ) -> Result<Url, GitHubError> {
+        self.pull_requests.acquire().await;
         let operation = CreatePullRequest::build(CreatePullRequestVariables {
             input: CreatePullRequestInput {
                 base_ref_name: branch_name,
@@ -515,14 +1240,29 @@ impl GitHub {
                 title,
             },
         });
+        let head_branch = fork_ref_name
+            .split_once(':')
+            .map_or(fork_ref_name, |(_, branch)| branch);
         let GraphQlResponse { data, errors } = self
-            .0
-            .post(GITHUB_GRAPHQL_URL)
-            .run_graphql(operation)
+            .graphql_mutation(operation, || async {
+                Ok(self
+                    .get_ref(fork_id, &format!("refs/heads/{head_branch}"))
+                    .await?
+                    .and_then(|ref_| ref_.associated_pull_requests.nodes.into_iter().next())
+                    .map(|pull_request| CreatePullRequest {
+                        create_pull_request: Some(create_pull_request::CreatePullRequestPayload {
+                            pull_request: Some(create_pull_request::PullRequest {
+                                url: pull_request.url,
+                            }),
+                        }),
+                    }))
+            })
             .await?;
         data.and_then(|data| data.create_pull_request?.pull_request)
             .map(|pull_request| pull_request.url)
-            .ok_or_else(|| GitHubError::GraphQL(errors.unwrap_or_default()))
+            .ok_or_else(|| {
+                GitHubError::from_graphql(errors, PULL_REQUESTS_WRITE, fork_description())
+            })
     }
 
     pub async fn delete_branches(
@@ -533,9 +1273,7 @@ impl GitHub {
         const DELETE_ID: &str = str_repeat!("0", 40);
 
         let GraphQlResponse { data: _, errors } = self
-            .0
-            .post(GITHUB_GRAPHQL_URL)
-            .run_graphql(UpdateRefs::build(UpdateRefsVariables {
+            .graphql(UpdateRefs::build(UpdateRefsVariables {
                 ref_updates: branch_names
                     .iter()
                     .map(|branch_name| RefUpdate {
@@ -556,37 +1294,86 @@ impl GitHub {
         identifier: &PackageIdentifier,
         version: &PackageVersion,
     ) -> Result<Option<get_existing_pull_request::PullRequest>, GitHubError> {
-        self
-            .0
-            .post(GITHUB_GRAPHQL_URL)
-            .run_graphql(GetExistingPullRequest::build(GetExistingPullRequestVariables {
-                query: &format!("repo:{WINGET_PKGS_FULL_NAME} is:pull-request in:title {identifier} {version}"),
+        Ok(self.search_pull_requests(identifier, version).await?.next())
+    }
+
+    /// Finds the current user's open pull request for a package version, which is the only one
+    /// that can be amended
+    pub async fn get_pull_request_to_amend(
+        &self,
+        identifier: &PackageIdentifier,
+        version: &PackageVersion,
+    ) -> Result<Option<get_existing_pull_request::PullRequest>, GitHubError> {
+        let (current_user, pull_requests) = tokio::try_join!(
+            self.get_username(),
+            self.search_pull_requests(identifier, version)
+        )?;
+        Ok(pull_requests
+            .filter(|pull_request| pull_request.state == PullRequestState::Open)
+            .find(|pull_request| {
+                pull_request
+                    .head_repository_owner
+                    .as_ref()
+                    .is_some_and(|owner| owner.login.eq_ignore_ascii_case(&current_user))
             }))
-            .await
-            .map(|response| {
-                response
-                    .data?
-                    .search
-                    .edges
-                    .into_iter()
-                    .filter_map(|edge| edge.node?.into_pull_request())
-                    .find(|pull_request| {
-                        let title = &*pull_request.title;
-                        // Check that the identifier is used in its entirety and not part of another
-                        // package identifier. For example, ensuring we match against
-                        // "Microsoft.Excel" not "Microsoft.Excel.Beta" as `in:title` in the query
-                        // only does a 'contains' rather than a word boundary match.
-                        title.match_indices(identifier.as_str()).any(|(index, matched)| {
-                            let before = title[..index].chars().next_back();
-                            let after = title[index + matched.len()..].chars().next();
-                            // Check whether the characters before and after the identifier are
-                            // either None (at the boundary of the title) or whitespace
-                            before.is_none_or(char::is_whitespace)
-                                && after.is_none_or(char::is_whitespace)
-                        })
+    }
+
+    /// Searches for pull requests whose title contains the package identifier and version
+    async fn search_pull_requests(
+        &self,
+        identifier: &PackageIdentifier,
+        version: &PackageVersion,
+    ) -> Result<impl Iterator<Item = get_existing_pull_request::PullRequest>, GitHubError> {
+        let response = self
+            .graphql(GetExistingPullRequest::build(
+                GetExistingPullRequestVariables {
+                    query: &format!(
+                        "repo:{WINGET_PKGS_FULL_NAME} is:pull-request in:title {identifier} {version}"
+                    ),
+                },
+            ))
+            .await?;
+        Ok(response
+            .data
+            .into_iter()
+            .flat_map(|data| data.search.edges)
+            .filter_map(|edge| edge.node?.into_pull_request())
+            .filter(move |pull_request| {
+                let title = &*pull_request.title;
+                // Check that the identifier is used in its entirety and not part of another
+                // package identifier. For example, ensuring we match against
+                // "Microsoft.Excel" not "Microsoft.Excel.Beta" as `in:title` in the query
+                // only does a 'contains' rather than a word boundary match.
+                title
+                    .match_indices(identifier.as_str())
+                    .any(|(index, matched)| {
+                        let before = title[..index].chars().next_back();
+                        let after = title[index + matched.len()..].chars().next();
+                        // Check whether the characters before and after the identifier are
+                        // either None (at the boundary of the title) or whitespace
+                        before.is_none_or(char::is_whitespace)
+                            && after.is_none_or(char::is_whitespace)
                     })
-            })
-            .map_err(GitHubError::CynicRequest)
+            }))
+    }
+
+    pub async fn get_pull_request(
+        &self,
+        number: i32,
+    ) -> Result<get_pull_request::PullRequest, GitHubError> {
+        let GraphQlResponse { data, errors } = self
+            .graphql(GetPullRequest::build(GetPullRequestVariables {
+                owner: &Upstream::get().owner,
+                name: &Upstream::get().name,
+                number,
+            }))
+            .await?;
+        let repository = data
+            .and_then(|data| data.repository)
+            .ok_or_else(|| GitHubError::GraphQL(errors.unwrap_or_default()))?;
+        repository
+            .pull_request
+            .ok_or(GitHubError::PullRequestNonExistent(number))
     }
 
     pub fn get_all_values_from_url(
@@ -615,9 +1402,7 @@ impl GitHub {
         tag_name: String,
     ) -> Result<GitHubValues, GitHubError> {
         let GraphQlResponse { data, errors } = self
-            .0
-            .post(GITHUB_GRAPHQL_URL)
-            .run_graphql(GetAllValues::build(GetAllValuesVariables {
+            .graphql(GetAllValues::build(GetAllValuesVariables {
                 name: &repo,
                 owner: &owner,
                 tag_name: &tag_name,
@@ -697,9 +1482,7 @@ impl GitHub {
         force: bool,
     ) -> Result<(), GitHubError> {
         let GraphQlResponse { data: _, errors } = self
-            .0
-            .post(GITHUB_GRAPHQL_URL)
-            .run_graphql(MergeUpstream::build(MergeUpstreamVariables {
+            .graphql(MergeUpstream::build(MergeUpstreamVariables {
                 branch_ref_id,
                 upstream_target_oid,
                 force,
@@ -717,6 +1500,7 @@ impl GitHub {
         fork: &RepositoryData,
         winget_pkgs: &RepositoryData,
         issue_resolves: Option<Vec<NonZeroU32>>,
+        templates: Option<&PullRequestTemplates>,
     ) -> Result<Url, GitHubError> {
         // Create an indeterminate progress bar to show as a pull request is being created
         let pr_progress = ProgressBar::new_spinner().with_message(format!(
@@ -724,7 +1508,17 @@ impl GitHub {
         ));
         pr_progress.enable_steady_tick(SPINNER_TICK_RATE);
 
-        let branch_name = get_branch_name(identifier, version);
+        let templates = templates.map_or_else(Cow::default, Cow::Borrowed);
+        let template_values = TemplateValues {
+            identifier,
+            version,
+            update_state: UpdateState::RemoveVersion,
+            resolves: issue_resolves.as_deref(),
+            installers: None,
+            created_with: None,
+            created_with_url: None,
+        };
+        let branch_name = templates.branch_name(&template_values);
         let pull_request_branch = self
             .create_branch(
                 &fork.id,
@@ -732,7 +1526,7 @@ impl GitHub {
                 winget_pkgs.default_branch_oid.clone(),
             )
             .await?;
-        let commit_title = get_commit_title(identifier, version, UpdateState::RemoveVersion);
+        let commit_title = templates.title(&template_values);
         let deletions = self
             .get_directory_content(
                 &fork.owner,
@@ -750,6 +1544,15 @@ impl GitHub {
             .deletions(deletions)
             .send()
             .await?;
+        // The removal reason is always the heading so that a body template cannot leave it out
+        let body = if let Some(template) = &templates.body {
+            format!("### {reason}\n\n{}", template.render(&template_values))
+        } else {
+            pull_request_body()
+                .maybe_issue_resolves(issue_resolves.clone())
+                .alternative_text(reason)
+                .get()
+        };
         let pull_request_url = self
             .create_pull_request(
                 &winget_pkgs.id,
@@ -757,10 +1560,7 @@ impl GitHub {
                 &format!("{}:{}", fork.owner, pull_request_branch.name),
                 &winget_pkgs.default_branch_name,
                 &commit_title,
-                &pull_request_body()
-                    .maybe_issue_resolves(issue_resolves)
-                    .alternative_text(reason)
-                    .get(),
+                &body,
             )
             .await?;
 
@@ -774,6 +1574,101 @@ impl GitHub {
         Ok(pull_request_url)
     }
 
+    pub async fn add_comment(&self, subject_id: &Id, body: &str) -> Result<(), GitHubError> {
+        let GraphQlResponse { data: _, errors } = self
+            .graphql(AddComment::build(AddCommentVariables { body, subject_id }))
+            .await?;
+        errors.map_or(Ok(()), |errors| Err(GitHubError::GraphQL(errors)))
+    }
+
+    /// Pushes a commit to the head branch of an existing pull request so that its manifests match
+    /// the given changes. Only files that differ from the branch are added, and manifests on the
+    /// branch that are no longer part of the changes are deleted.
+    #[builder(finish_fn = send)]
+    pub async fn amend_pull_request(
+        &self,
+        identifier: &PackageIdentifier,
+        version: &PackageVersion,
+        pull_request: &get_existing_pull_request::PullRequest,
+        changes: Vec<(String, String)>,
+        comment: Option<&str>,
+    ) -> Result<Url, GitHubError> {
+        let current_user = self.get_username().await?;
+        if pull_request
+            .head_repository_owner
+            .as_ref()
+            .is_none_or(|owner| !owner.login.eq_ignore_ascii_case(&current_user))
+        {
+            return Err(GitHubError::NotPullRequestAuthor {
+                url: pull_request.url.clone(),
+                user: current_user,
+            });
+        }
+
+        let head_ref = pull_request
+            .head_ref
+            .as_ref()
+            .ok_or_else(|| GitHubError::PullRequestBranchDeleted(pull_request.url.clone()))?;
+        let head_sha = head_ref
+            .target
+            .as_ref()
+            .map(|target| target.oid.clone())
+            .ok_or_else(|| GitHubError::PullRequestBranchDeleted(pull_request.url.clone()))?;
+
+        let package_path = PackagePath::new(identifier, Some(version), None);
+        let branch_files = match self
+            .get_directory_content_with_text(
+                &current_user,
+                &Upstream::get().name,
+                &head_ref.name,
+                &package_path,
+            )
+            .await
+        {
+            Ok(files) => files
+                .map(|file| (format!("{package_path}/{}", file.name), file.text))
+                .collect::<HashMap<_, _>>(),
+            // The version's directory doesn't exist on the branch, which isn't reported as an error
+            Err(GitHubError::GraphQL(errors)) if errors.is_empty() => HashMap::new(),
+            Err(error) => return Err(error),
+        };
+
+        let additions = changes
+            .iter()
+            .filter(|(path, content)| branch_files.get(path) != Some(content))
+            .map(|(path, content)| {
+                FileAddition::new(
+                    Base64String::new(Base64::encode_string(content.as_bytes())),
+                    path,
+                )
+            })
+            .collect::<Vec<_>>();
+        let deletions = branch_files
+            .keys()
+            .filter(|path| changes.iter().all(|(change_path, _)| change_path != *path))
+            .map(FileDeletion::new)
+            .collect::<Vec<_>>();
+
+        if additions.is_empty() && deletions.is_empty() {
+            return Err(GitHubError::NothingToAmend(pull_request.url.clone()));
+        }
+
+        self.create_commit()
+            .branch_id(&head_ref.id)
+            .head_sha(head_sha)
+            .message(&pull_request.title)
+            .additions(additions)
+            .deletions(deletions)
+            .send()
+            .await?;
+
+        if let Some(comment) = comment {
+            self.add_comment(&pull_request.id, comment).await?;
+        }
+
+        Ok(pull_request.url.clone())
+    }
+
     #[builder(finish_fn = send)]
     pub async fn add_version(
         &self,
@@ -785,19 +1680,68 @@ impl GitHub {
         issue_resolves: Option<Vec<NonZeroU32>>,
         created_with: Option<String>,
         created_with_url: Option<DecodedUrl>,
+        templates: Option<&PullRequestTemplates>,
+        installers: Option<&str>,
+        description: Option<&str>,
+    ) -> Result<Url, GitHubError> {
+        self.add_versions()
+            .packages(&[PackageChanges {
+                identifier: identifier.clone(),
+                version: version.clone(),
+                versions: versions.cloned(),
+                changes,
+                replace_version: replace_version.cloned(),
+                installers: installers.map(str::to_owned),
+            }])
+            .maybe_issue_resolves(issue_resolves)
+            .maybe_created_with(created_with)
+            .maybe_created_with_url(created_with_url)
+            .maybe_templates(templates)
+            .maybe_description(description)
+            .send()
+            .await
+    }
+
+    /// Creates a single pull request containing the manifests of one or more package versions
+    #[builder(finish_fn = send)]
+    pub async fn add_versions(
+        &self,
+        packages: &[PackageChanges],
+        issue_resolves: Option<Vec<NonZeroU32>>,
+        created_with: Option<String>,
+        created_with_url: Option<DecodedUrl>,
+        templates: Option<&PullRequestTemplates>,
+        description: Option<&str>,
     ) -> Result<Url, GitHubError> {
+        let template_values = packages
+            .iter()
+            .map(|package| TemplateValues {
+                identifier: &package.identifier,
+                version: &package.version,
+                update_state: UpdateState::get(&package.version, package.versions.as_ref()),
+                resolves: issue_resolves.as_deref(),
+                installers: package.installers.as_deref(),
+                created_with: created_with.as_deref(),
+                created_with_url: created_with_url.as_ref(),
+            })
+            .collect::<Vec<_>>();
+        let [first, ..] = template_values.as_slice() else {
+            return Err(GitHubError::NoPackages);
+        };
+
         let current_user = self.get_username();
         let winget_pkgs = self.get_winget_pkgs().send().await?;
         let current_user = current_user.await?;
         let fork = self.get_winget_pkgs().owner(&current_user).send().await?;
-        let branch_name = get_branch_name(identifier, version);
+        let templates = templates.map_or_else(Cow::default, Cow::Borrowed);
+        let branch_name = templates.branch_name(first);
         let pull_request_branch = self
             .create_branch(&fork.id, &branch_name, winget_pkgs.default_branch_oid)
             .await?;
-        let commit_title =
-            get_commit_title(identifier, version, UpdateState::get(version, versions));
-        let additions = changes
+        let commit_title = templates.combined_title(&template_values);
+        let additions = packages
             .iter()
+            .flat_map(|package| &package.changes)
             .map(|(path, content)| {
                 FileAddition::new(
                     Base64String::new(Base64::encode_string(content.as_bytes())),
@@ -805,45 +1749,58 @@ impl GitHub {
                 )
             })
             .collect::<Vec<_>>();
-        let deletions = if replace_version.is_some() {
-            Some(
-                self.get_directory_content(
-                    &current_user,
-                    &branch_name,
-                    &PackagePath::new(identifier, replace_version, None),
-                )
-                .await?
-                .map(FileDeletion::new)
-                .collect::<Vec<_>>(),
-            )
-        } else {
-            None
-        };
+        let mut deletions = Vec::new();
+        for package in packages {
+            if let Some(replace_version) = &package.replace_version {
+                deletions.extend(
+                    self.get_directory_content(
+                        &current_user,
+                        &branch_name,
+                        &PackagePath::new(&package.identifier, Some(replace_version), None),
+                    )
+                    .await?
+                    .map(FileDeletion::new),
+                );
+            }
+        }
         let _commit_url = self
             .create_commit()
             .branch_id(&pull_request_branch.id)
             .head_sha(pull_request_branch.target.map(|target| target.oid).unwrap())
             .message(&commit_title)
             .additions(additions)
-            .maybe_deletions(deletions)
+            .maybe_deletions((!deletions.is_empty()).then_some(deletions))
             .send()
             .await?;
+        let mut body = templates.combined_body(&template_values);
+        if let Some(description) = description {
+            body.push('\n');
+            body.push_str(description);
+        }
         self.create_pull_request(
             &winget_pkgs.id,
             &fork.id,
             &format!("{current_user}:{}", pull_request_branch.name),
             &winget_pkgs.default_branch_name,
             &commit_title,
-            &pull_request_body()
-                .maybe_issue_resolves(issue_resolves)
-                .maybe_created_with(created_with)
-                .maybe_created_with_url(created_with_url)
-                .get(),
+            &body,
         )
         .await
     }
 }
 
+/// The manifests of one package version to submit alongside others in a single pull request
+pub struct PackageChanges {
+    pub identifier: PackageIdentifier,
+    pub version: PackageVersion,
+    pub versions: Option<BTreeSet<PackageVersion>>,
+    pub changes: Vec<(String, String)>,
+    /// A version of the package whose manifests are deleted in the same commit
+    pub replace_version: Option<PackageVersion>,
+    /// A summary of the package's installers for the `{installers}` template variable
+    pub installers: Option<String>,
+}
+
 pub struct GitHubValues {
     pub publisher_url: PublisherUrl,
     pub publisher_support_url: Option<PublisherSupportUrl>,
@@ -860,6 +1817,14 @@ pub struct GitHubFile {
     pub text: String,
 }
 
+/// The manifest files of a package version
+struct ManifestFiles {
+    installer: GitHubFile,
+    default_locale: GitHubFile,
+    locales: Vec<GitHubFile>,
+    version: GitHubFile,
+}
+
 pub struct RepositoryData {
     pub id: Id,
     pub owner: String,
@@ -870,3 +1835,19 @@ pub struct RepositoryData {
     pub default_branch_ref_id: Id,
     pub commit_count: i32,
 }
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::parse_accepted_permissions;
+
+    #[rstest]
+    #[case("contents=write", Some("contents: write"))]
+    #[case("contents=read; metadata=read", Some("contents: read, metadata: read"))]
+    #[case("pull_requests=write, contents=write", Some("pull_requests: write"))]
+    #[case("", None)]
+    fn accepted_permissions(#[case] value: &str, #[case] expected: Option<&str>) {
+        assert_eq!(parse_accepted_permissions(value).as_deref(), expected);
+    }
+}
@@ -0,0 +1,49 @@
+use crate::github::graphql::github_schema::github_schema as schema;
+
+#[derive(cynic::QueryVariables)]
+pub struct AddCommentVariables<'a> {
+    pub body: &'a str,
+    pub subject_id: &'a cynic::Id,
+}
+
+#[derive(cynic::QueryFragment)]
+#[cynic(graphql_type = "Mutation", variables = "AddCommentVariables")]
+pub struct AddComment {
+    #[expect(dead_code)]
+    #[arguments(input: { body: $body, subjectId: $subject_id })]
+    pub add_comment: Option<AddCommentPayload>,
+}
+
+/// <https://docs.github.com/graphql/reference/mutations#addcomment>
+#[derive(cynic::QueryFragment)]
+pub struct AddCommentPayload {
+    #[expect(dead_code)]
+    pub client_mutation_id: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use cynic::{Id, MutationBuilder};
+    use indoc::indoc;
+
+    use crate::github::graphql::add_comment::{AddComment, AddCommentVariables};
+
+    #[test]
+    fn add_comment_output() {
+        const ADD_COMMENT_MUTATION: &str = indoc! {"
+            mutation AddComment($body: String!, $subjectId: ID!) {
+              addComment(input: {body: $body, subjectId: $subjectId}) {
+                clientMutationId
+              }
+            }
+        "};
+
+        let id = Id::new("");
+        let operation = AddComment::build(AddCommentVariables {
+            body: "",
+            subject_id: &id,
+        });
+
+        assert_eq!(operation.query, ADD_COMMENT_MUTATION);
+    }
+}
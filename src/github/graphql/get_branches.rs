@@ -1,5 +1,7 @@
 use std::fmt::{Display, Formatter};
 
+use chrono::{DateTime, Utc};
+use derive_more::Display;
 use url::Url;
 
 use crate::github::graphql::github_schema::github_schema as schema;
@@ -68,18 +70,109 @@ pub struct PullRequestConnection {
 /// <https://docs.github.com/graphql/reference/objects#pullrequest>
 #[derive(cynic::QueryFragment, Hash, PartialEq, Eq)]
 pub struct PullRequest {
+    pub number: i32,
     pub title: String,
     pub url: Url,
     pub state: PullRequestState,
+    pub created_at: DateTime<Utc>,
+    pub review_decision: Option<PullRequestReviewDecision>,
+    #[arguments(first: 20)]
+    pub labels: Option<LabelConnection>,
+    #[arguments(last: 1)]
+    pub commits: PullRequestCommitConnection,
     pub repository: PullRequestRepository,
 }
 
+impl PullRequest {
+    pub fn label_names(&self) -> impl Iterator<Item = &str> {
+        self.labels
+            .iter()
+            .flat_map(|labels| &labels.labels)
+            .map(|label| label.name.as_str())
+    }
+
+    /// The combined state of the checks on the pull request's latest commit
+    pub fn check_state(&self) -> Option<StatusState> {
+        self.commits
+            .commits
+            .last()?
+            .commit
+            .status_check_rollup
+            .as_ref()
+            .map(|rollup| rollup.state)
+    }
+}
+
 impl Display for PullRequest {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.title)
     }
 }
 
+/// <https://docs.github.com/graphql/reference/objects#labelconnection>
+#[derive(cynic::QueryFragment, Hash, PartialEq, Eq)]
+pub struct LabelConnection {
+    #[cynic(rename = "nodes", flatten)]
+    pub labels: Vec<Label>,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#label>
+#[derive(cynic::QueryFragment, Hash, PartialEq, Eq)]
+pub struct Label {
+    pub name: String,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#pullrequestcommitconnection>
+#[derive(cynic::QueryFragment, Hash, PartialEq, Eq)]
+pub struct PullRequestCommitConnection {
+    #[cynic(rename = "nodes", flatten)]
+    pub commits: Vec<PullRequestCommit>,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#pullrequestcommit>
+#[derive(cynic::QueryFragment, Hash, PartialEq, Eq)]
+pub struct PullRequestCommit {
+    pub commit: Commit,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#commit>
+#[derive(cynic::QueryFragment, Hash, PartialEq, Eq)]
+pub struct Commit {
+    pub status_check_rollup: Option<StatusCheckRollup>,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#statuscheckrollup>
+#[derive(cynic::QueryFragment, Hash, PartialEq, Eq)]
+pub struct StatusCheckRollup {
+    pub state: StatusState,
+}
+
+/// <https://docs.github.com/graphql/reference/enums#statusstate>
+#[derive(cynic::Enum, Clone, Copy, Debug, Display, Hash, PartialEq, Eq)]
+pub enum StatusState {
+    #[display("error")]
+    Error,
+    #[display("expected")]
+    Expected,
+    #[display("failing")]
+    Failure,
+    #[display("pending")]
+    Pending,
+    #[display("passing")]
+    Success,
+}
+
+/// <https://docs.github.com/graphql/reference/enums#pullrequestreviewdecision>
+#[derive(cynic::Enum, Clone, Copy, Debug, Display, Hash, PartialEq, Eq)]
+pub enum PullRequestReviewDecision {
+    #[display("approved")]
+    Approved,
+    #[display("changes requested")]
+    ChangesRequested,
+    #[display("review required")]
+    ReviewRequired,
+}
+
 /// <https://docs.github.com/graphql/reference/objects#repository>
 #[derive(cynic::QueryFragment, Hash, PartialEq, Eq)]
 #[cynic(graphql_type = "Repository")]
@@ -133,9 +226,26 @@ mod tests {
                     name
                     associatedPullRequests(first: 5) {
                       nodes {
+                        number
                         title
                         url
                         state
+                        createdAt
+                        reviewDecision
+                        labels(first: 20) {
+                          nodes {
+                            name
+                          }
+                        }
+                        commits(last: 1) {
+                          nodes {
+                            commit {
+                              statusCheckRollup {
+                                state
+                              }
+                            }
+                          }
+                        }
                         repository {
                           nameWithOwner
                         }
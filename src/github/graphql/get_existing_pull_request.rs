@@ -2,7 +2,7 @@ use chrono::{DateTime, Utc};
 use url::Url;
 
 use crate::github::graphql::{
-    get_branches::PullRequestState, github_schema::github_schema as schema,
+    create_ref::Ref, get_branches::PullRequestState, github_schema::github_schema as schema,
 };
 
 #[derive(cynic::QueryVariables)]
@@ -30,13 +30,23 @@ pub struct SearchResultItemEdge {
 
 #[derive(cynic::QueryFragment)]
 pub struct PullRequest {
+    pub id: cynic::Id,
     pub title: String,
     pub url: Url,
     pub state: PullRequestState,
     pub created_at: DateTime<Utc>,
+    pub head_ref: Option<Ref>,
+    pub head_repository_owner: Option<RepositoryOwner>,
+}
+
+/// <https://docs.github.com/graphql/reference/interfaces#repositoryowner>
+#[derive(cynic::QueryFragment)]
+pub struct RepositoryOwner {
+    pub login: String,
 }
 
 #[derive(cynic::InlineFragments)]
+#[expect(clippy::large_enum_variant)]
 pub enum SearchResultItem {
     PullRequest(PullRequest),
     #[cynic(fallback)]
@@ -70,10 +80,21 @@ mod tests {
                   node {
                     __typename
                     ... on PullRequest {
+                      id
                       title
                       url
                       state
                       createdAt
+                      headRef {
+                        id
+                        name
+                        target {
+                          oid
+                        }
+                      }
+                      headRepositoryOwner {
+                        login
+                      }
                     }
                   }
                 }
@@ -0,0 +1,159 @@
+use chrono::{DateTime, Utc};
+use itertools::Itertools;
+use url::Url;
+use winget_types::{PackageIdentifier, PackageVersion};
+
+use crate::github::{
+    graphql::{
+        get_branches::{LabelConnection, PullRequestState},
+        github_schema::github_schema as schema,
+    },
+    utils::parse_manifest_path,
+};
+
+#[derive(cynic::QueryVariables)]
+pub struct GetPullRequestVariables<'a> {
+    pub owner: &'a str,
+    pub name: &'a str,
+    pub number: i32,
+}
+
+#[derive(cynic::QueryFragment)]
+#[cynic(graphql_type = "Query", variables = "GetPullRequestVariables")]
+pub struct GetPullRequest {
+    #[arguments(owner: $owner, name: $name)]
+    pub repository: Option<Repository>,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#repository>
+#[derive(cynic::QueryFragment)]
+#[cynic(variables = "GetPullRequestVariables")]
+pub struct Repository {
+    #[arguments(number: $number)]
+    pub pull_request: Option<PullRequest>,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#pullrequest>
+#[derive(cynic::QueryFragment)]
+pub struct PullRequest {
+    pub number: i32,
+    pub title: String,
+    pub url: Url,
+    pub state: PullRequestState,
+    #[arguments(first: 20)]
+    pub labels: Option<LabelConnection>,
+    #[arguments(last: 50)]
+    pub comments: IssueCommentConnection,
+    #[arguments(first: 100)]
+    pub files: Option<PullRequestChangedFileConnection>,
+}
+
+impl PullRequest {
+    pub fn label_names(&self) -> impl Iterator<Item = &str> {
+        self.labels
+            .iter()
+            .flat_map(|labels| &labels.labels)
+            .map(|label| label.name.as_str())
+    }
+
+    /// The package changed by the pull request, if all of its changed files belong to one package
+    pub fn package(&self) -> Option<(PackageIdentifier, PackageVersion)> {
+        self.files
+            .iter()
+            .flat_map(|files| &files.files)
+            .map(|file| parse_manifest_path(&file.path))
+            .all_equal_value()
+            .ok()
+            .flatten()
+    }
+}
+
+/// <https://docs.github.com/graphql/reference/objects#pullrequestchangedfileconnection>
+#[derive(cynic::QueryFragment)]
+pub struct PullRequestChangedFileConnection {
+    #[cynic(rename = "nodes", flatten)]
+    pub files: Vec<PullRequestChangedFile>,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#pullrequestchangedfile>
+#[derive(cynic::QueryFragment)]
+pub struct PullRequestChangedFile {
+    pub path: String,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#issuecommentconnection>
+#[derive(cynic::QueryFragment)]
+pub struct IssueCommentConnection {
+    #[cynic(rename = "nodes", flatten)]
+    pub comments: Vec<IssueComment>,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#issuecomment>
+#[derive(cynic::QueryFragment)]
+pub struct IssueComment {
+    pub author: Option<Actor>,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub url: Url,
+}
+
+/// <https://docs.github.com/graphql/reference/interfaces#actor>
+#[derive(cynic::QueryFragment)]
+pub struct Actor {
+    pub login: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use cynic::QueryBuilder;
+    use indoc::indoc;
+
+    use crate::github::{
+        github_client::{MICROSOFT, WINGET_PKGS},
+        graphql::get_pull_request::{GetPullRequest, GetPullRequestVariables},
+    };
+
+    #[test]
+    fn get_pull_request_output() {
+        const GET_PULL_REQUEST_QUERY: &str = indoc! {"
+            query GetPullRequest($owner: String!, $name: String!, $number: Int!) {
+              repository(owner: $owner, name: $name) {
+                pullRequest(number: $number) {
+                  number
+                  title
+                  url
+                  state
+                  labels(first: 20) {
+                    nodes {
+                      name
+                    }
+                  }
+                  comments(last: 50) {
+                    nodes {
+                      author {
+                        login
+                      }
+                      body
+                      createdAt
+                      url
+                    }
+                  }
+                  files(first: 100) {
+                    nodes {
+                      path
+                    }
+                  }
+                }
+              }
+            }
+        "};
+
+        let operation = GetPullRequest::build(GetPullRequestVariables {
+            owner: MICROSOFT,
+            name: WINGET_PKGS,
+            number: 0,
+        });
+
+        assert_eq!(operation.query, GET_PULL_REQUEST_QUERY);
+    }
+}
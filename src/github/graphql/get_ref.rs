@@ -0,0 +1,130 @@
+use url::Url;
+
+use crate::github::graphql::{github_schema::github_schema as schema, types::GitObjectId};
+
+/// Looks up a ref by its own ID, or by its qualified name in the repository with the given ID
+#[derive(cynic::QueryVariables)]
+pub struct GetRefVariables<'a> {
+    pub id: &'a cynic::Id,
+    pub qualified_name: &'a str,
+}
+
+#[derive(cynic::QueryFragment)]
+#[cynic(graphql_type = "Query", variables = "GetRefVariables")]
+pub struct GetRef {
+    #[arguments(id: $id)]
+    pub node: Option<Node>,
+}
+
+#[derive(cynic::InlineFragments)]
+#[cynic(variables = "GetRefVariables")]
+pub enum Node {
+    Repository(Repository),
+    Ref(Ref),
+    #[cynic(fallback)]
+    Unknown,
+}
+
+impl Node {
+    pub fn into_ref(self) -> Option<Ref> {
+        match self {
+            Self::Repository(repository) => repository.ref_,
+            Self::Ref(ref_) => Some(ref_),
+            Self::Unknown => None,
+        }
+    }
+}
+
+/// <https://docs.github.com/graphql/reference/objects#repository>
+#[derive(cynic::QueryFragment)]
+#[cynic(variables = "GetRefVariables")]
+pub struct Repository {
+    #[arguments(qualifiedName: $qualified_name)]
+    #[cynic(rename = "ref")]
+    pub ref_: Option<Ref>,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#ref>
+#[derive(cynic::QueryFragment)]
+pub struct Ref {
+    pub id: cynic::Id,
+    pub name: String,
+    pub target: Option<GitObject>,
+    #[arguments(first: 1, states: [OPEN])]
+    pub associated_pull_requests: PullRequestConnection,
+}
+
+/// <https://docs.github.com/graphql/reference/interfaces#gitobject>
+#[derive(cynic::QueryFragment)]
+pub struct GitObject {
+    pub oid: GitObjectId,
+    pub commit_url: Url,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#pullrequestconnection>
+#[derive(cynic::QueryFragment)]
+pub struct PullRequestConnection {
+    #[cynic(flatten)]
+    pub nodes: Vec<PullRequest>,
+}
+
+/// <https://docs.github.com/graphql/reference/objects#pullrequest>
+#[derive(cynic::QueryFragment)]
+pub struct PullRequest {
+    pub url: Url,
+}
+
+#[cfg(test)]
+mod tests {
+    use cynic::{Id, QueryBuilder};
+    use indoc::indoc;
+
+    use crate::github::graphql::get_ref::{GetRef, GetRefVariables};
+
+    #[test]
+    fn get_ref_output() {
+        const GET_REF_QUERY: &str = indoc! {r#"
+            query GetRef($id: ID!, $qualifiedName: String!) {
+              node(id: $id) {
+                __typename
+                ... on Repository {
+                  ref(qualifiedName: $qualifiedName) {
+                    id
+                    name
+                    target {
+                      oid
+                      commitUrl
+                    }
+                    associatedPullRequests(first: 1, states: [OPEN]) {
+                      nodes {
+                        url
+                      }
+                    }
+                  }
+                }
+                ... on Ref {
+                  id
+                  name
+                  target {
+                    oid
+                    commitUrl
+                  }
+                  associatedPullRequests(first: 1, states: [OPEN]) {
+                    nodes {
+                      url
+                    }
+                  }
+                }
+              }
+            }
+        "#};
+
+        let id = Id::new("");
+        let operation = GetRef::build(GetRefVariables {
+            id: &id,
+            qualified_name: "",
+        });
+
+        assert_eq!(operation.query, GET_REF_QUERY);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod add_comment;
 pub mod create_commit;
 pub mod create_pull_request;
 pub mod create_ref;
@@ -8,6 +9,8 @@ pub mod get_directory_content;
 pub mod get_directory_content_with_text;
 pub mod get_existing_pull_request;
 pub mod get_file_content;
+pub mod get_pull_request;
+pub mod get_ref;
 pub mod get_repository_info;
 pub mod github_schema;
 pub mod merge_upstream;
@@ -4,11 +4,11 @@ use derive_new::new;
 use crate::github::graphql::github_schema::github_schema as schema;
 
 /// <https://docs.github.com/graphql/reference/scalars#base64string>
-#[derive(cynic::Scalar, new)]
+#[derive(cynic::Scalar, Deref, new)]
 pub struct Base64String(#[new(into)] String);
 
 /// <https://docs.github.com/graphql/reference/scalars#gitobjectid>
-#[derive(cynic::Scalar, PartialEq, Eq, Clone, new)]
+#[derive(cynic::Scalar, PartialEq, Eq, Clone, Deref, new)]
 #[cynic(graphql_type = "GitObjectID")]
 pub struct GitObjectId(#[new(into)] String);
 
@@ -1,4 +1,8 @@
+pub mod app;
+pub mod commit_signing;
 pub mod github_client;
 pub mod graphql;
+pub mod rate_limit;
 mod rest;
 pub mod utils;
+pub mod validation;
@@ -0,0 +1,346 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use reqwest::{
+    StatusCode,
+    header::{HeaderMap, RETRY_AFTER},
+};
+
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+const RATE_LIMIT_RESOURCE: &str = "x-ratelimit-resource";
+
+/// The number of times a request is retried after a transient error or a rate limit
+pub const MAX_RETRIES: u32 = 5;
+
+/// The longest Komac will wait for a rate limit to reset before giving up
+pub const MAX_WAIT: Duration = Duration::from_secs(15 * 60);
+
+/// The first delay when retrying transient errors, doubling with each attempt
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// GitHub asks for at least a minute between retries of secondary rate limits that do not specify
+/// when to retry
+///
+/// <https://docs.github.com/rest/using-the-rest-api/rate-limits-for-the-rest-api#exceeding-the-rate-limit>
+const SECONDARY_RATE_LIMIT_DELAY: Duration = Duration::from_secs(60);
+
+/// GitHub has an undocumented limit of 20 pull requests per minute
+///
+/// <https://github.com/cli/cli/issues/4801#issuecomment-1430651377>
+const MAX_PULL_REQUESTS_PER_MINUTE: usize = 20;
+
+/// GitHub has an undocumented limit of 150 pull requests per hour
+///
+/// <https://github.com/cli/cli/issues/4801#issuecomment-1430651377>
+pub const MAX_PULL_REQUESTS_PER_HOUR: usize = 150;
+
+const MINUTE: Duration = Duration::from_secs(60);
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+/// GitHub recommends waiting at least a second between requests that create content
+const MIN_PULL_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The primary rate limit of a GitHub API resource, such as `core` or `graphql`, as reported by
+/// the headers of the last response. For GraphQL, this counts the points that queries cost.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    pub remaining: u32,
+    pub reset: DateTime<Utc>,
+}
+
+impl RateLimit {
+    pub fn from_headers(headers: &HeaderMap) -> Option<(String, Self)> {
+        let header = |name| headers.get(name)?.to_str().ok();
+        let resource = header(RATE_LIMIT_RESOURCE).unwrap_or("core");
+        let remaining = header(RATE_LIMIT_REMAINING)?.parse().ok()?;
+        let reset = DateTime::from_timestamp(header(RATE_LIMIT_RESET)?.parse().ok()?, 0)?;
+        Some((resource.to_owned(), Self { remaining, reset }))
+    }
+
+    /// Returns how long to wait before making a request, if the limit has been used up
+    pub fn wait_time(&self, now: DateTime<Utc>) -> Option<Duration> {
+        (self.remaining == 0 && self.reset > now)
+            .then(|| (self.reset - now + TimeDelta::seconds(1)).to_std().ok())
+            .flatten()
+    }
+}
+
+/// Returns how long to wait before retrying a response, or [`None`] if it should not be retried.
+/// Transient server errors are retried with exponential backoff, and rate limited responses are
+/// retried when GitHub says the limit resets.
+pub fn retry_delay(
+    status: StatusCode,
+    headers: &HeaderMap,
+    attempt: u32,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    match status {
+        StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT => {
+            Some(backoff(attempt))
+        }
+        StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => {
+            if let Some(retry_after) = headers
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok()?.parse().ok())
+            {
+                Some(Duration::from_secs(retry_after))
+            } else if let Some((_, rate_limit)) = RateLimit::from_headers(headers)
+                && rate_limit.remaining == 0
+            {
+                rate_limit.wait_time(now).or(Some(INITIAL_BACKOFF))
+            } else if status == StatusCode::TOO_MANY_REQUESTS {
+                Some(SECONDARY_RATE_LIMIT_DELAY)
+            } else {
+                // Other forbidden responses, such as missing permissions, will not succeed later
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The exponential backoff before retrying a transient error
+pub fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF * 2_u32.saturating_pow(attempt)
+}
+
+/// The delay before retrying a GraphQL request whose errors say it was rate limited, which GitHub
+/// reports with a successful status code
+pub fn graphql_retry_delay<'a>(
+    mut messages: impl Iterator<Item = &'a str>,
+    rate_limit: Option<RateLimit>,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    messages
+        .any(|message| message.to_ascii_lowercase().contains("rate limit"))
+        .then(|| {
+            rate_limit
+                .and_then(|rate_limit| rate_limit.wait_time(now))
+                .unwrap_or(SECONDARY_RATE_LIMIT_DELAY)
+        })
+}
+
+/// Keeps track of the rate limits of each API resource, shared by every clone of the client
+#[derive(Debug, Default)]
+pub struct RateLimits(Mutex<HashMap<String, RateLimit>>);
+
+impl RateLimits {
+    pub fn update(&self, headers: &HeaderMap) {
+        if let Some((resource, rate_limit)) = RateLimit::from_headers(headers)
+            && let Ok(mut rate_limits) = self.0.lock()
+        {
+            rate_limits.insert(resource, rate_limit);
+        }
+    }
+
+    pub fn get(&self, resource: &str) -> Option<RateLimit> {
+        self.0.lock().ok()?.get(resource).copied()
+    }
+}
+
+/// Spaces out pull request creation so that bulk operations stay within GitHub's limits on pull
+/// requests per minute and per hour, which are enforced separately from the API rate limits
+#[derive(Debug)]
+pub struct PullRequestGovernor {
+    min_interval: Mutex<Duration>,
+    created: Mutex<VecDeque<Instant>>,
+}
+
+impl Default for PullRequestGovernor {
+    fn default() -> Self {
+        Self {
+            min_interval: Mutex::new(MIN_PULL_REQUEST_INTERVAL),
+            created: Mutex::new(VecDeque::with_capacity(MAX_PULL_REQUESTS_PER_HOUR)),
+        }
+    }
+}
+
+impl PullRequestGovernor {
+    /// Sets the minimum time between pull requests. This is in addition to the per-minute and
+    /// hourly limits.
+    pub fn set_min_interval(&self, interval: Duration) {
+        if let Ok(mut min_interval) = self.min_interval.lock() {
+            *min_interval = interval.max(MIN_PULL_REQUEST_INTERVAL);
+        }
+    }
+
+    /// Returns how long to wait before another pull request can be created
+    pub fn delay(&self) -> Duration {
+        let min_interval = self
+            .min_interval
+            .lock()
+            .map_or(MIN_PULL_REQUEST_INTERVAL, |interval| *interval);
+        self.created.lock().map_or(Duration::ZERO, |created| {
+            Self::delay_at(&created, min_interval, Instant::now())
+        })
+    }
+
+    fn delay_at(created: &VecDeque<Instant>, min_interval: Duration, now: Instant) -> Duration {
+        let since = |instant: &Instant| now.saturating_duration_since(*instant);
+        let last_interval = created.back().map_or(Duration::ZERO, |last| {
+            min_interval.saturating_sub(since(last))
+        });
+        let window_delay = |window: Duration, max: usize| {
+            let in_window = created
+                .iter()
+                .filter(|instant| since(instant) < window)
+                .collect::<Vec<_>>();
+            in_window
+                .len()
+                .checked_sub(max)
+                .and_then(|excess| in_window.get(excess))
+                .map_or(Duration::ZERO, |oldest| {
+                    window.saturating_sub(since(oldest))
+                })
+        };
+        last_interval
+            .max(window_delay(MINUTE, MAX_PULL_REQUESTS_PER_MINUTE))
+            .max(window_delay(HOUR, MAX_PULL_REQUESTS_PER_HOUR))
+    }
+
+    /// Waits until a pull request can be created and records that one is being created
+    pub async fn acquire(&self) {
+        loop {
+            let delay = {
+                let Ok(mut created) = self.created.lock() else {
+                    return;
+                };
+                let min_interval = self
+                    .min_interval
+                    .lock()
+                    .map_or(MIN_PULL_REQUEST_INTERVAL, |interval| *interval);
+                let now = Instant::now();
+                let delay = Self::delay_at(&created, min_interval, now);
+                if delay.is_zero() {
+                    while created
+                        .front()
+                        .is_some_and(|instant| now.saturating_duration_since(*instant) >= HOUR)
+                    {
+                        created.pop_front();
+                    }
+                    created.push_back(now);
+                    return;
+                }
+                delay
+            };
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        time::{Duration, Instant},
+    };
+
+    use chrono::DateTime;
+    use reqwest::{
+        StatusCode,
+        header::{HeaderMap, HeaderValue, RETRY_AFTER},
+    };
+    use rstest::rstest;
+
+    use super::{
+        HOUR, MAX_PULL_REQUESTS_PER_MINUTE, MINUTE, PullRequestGovernor, RateLimit,
+        graphql_retry_delay, retry_delay,
+    };
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|&(name, value)| (name.parse().unwrap(), HeaderValue::from_static(value)))
+            .collect()
+    }
+
+    #[test]
+    fn parse_rate_limit() {
+        let (resource, rate_limit) = RateLimit::from_headers(&headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1700000060"),
+            ("x-ratelimit-resource", "graphql"),
+        ]))
+        .unwrap();
+        assert_eq!(resource, "graphql");
+        assert_eq!(
+            rate_limit.wait_time(DateTime::from_timestamp(1_700_000_000, 0).unwrap()),
+            Some(Duration::from_secs(61))
+        );
+    }
+
+    #[rstest]
+    #[case(StatusCode::BAD_GATEWAY, &[], 2, Some(Duration::from_secs(4)))]
+    #[case(StatusCode::FORBIDDEN, &[(RETRY_AFTER.as_str(), "30")], 0, Some(Duration::from_secs(30)))]
+    #[case(
+        StatusCode::FORBIDDEN,
+        &[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "1700000009")],
+        0,
+        Some(Duration::from_secs(10))
+    )]
+    #[case(StatusCode::TOO_MANY_REQUESTS, &[], 0, Some(Duration::from_secs(60)))]
+    #[case(StatusCode::FORBIDDEN, &[("x-ratelimit-remaining", "4999")], 0, None)]
+    #[case(StatusCode::NOT_FOUND, &[], 0, None)]
+    fn retry(
+        #[case] status: StatusCode,
+        #[case] response_headers: &[(&'static str, &'static str)],
+        #[case] attempt: u32,
+        #[case] expected: Option<Duration>,
+    ) {
+        assert_eq!(
+            retry_delay(
+                status,
+                &headers(response_headers),
+                attempt,
+                DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn graphql_rate_limited() {
+        let now = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        assert_eq!(
+            graphql_retry_delay(["API rate limit exceeded"].into_iter(), None, now),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            graphql_retry_delay(["Could not resolve to a Repository"].into_iter(), None, now),
+            None
+        );
+    }
+
+    #[test]
+    fn governor_limits() {
+        let now = Instant::now() + HOUR;
+        let min_interval = Duration::from_secs(1);
+
+        assert_eq!(
+            PullRequestGovernor::delay_at(&VecDeque::new(), min_interval, now),
+            Duration::ZERO
+        );
+
+        // The minimum interval since the last pull request
+        let created = VecDeque::from([now - Duration::from_millis(200)]);
+        assert_eq!(
+            PullRequestGovernor::delay_at(&created, min_interval, now),
+            Duration::from_millis(800)
+        );
+
+        // The per-minute limit, which frees up when the oldest pull request leaves the window
+        let created = (0..MAX_PULL_REQUESTS_PER_MINUTE as u32)
+            .rev()
+            .map(|seconds| now - Duration::from_secs(2 * u64::from(seconds) + 10))
+            .collect::<VecDeque<_>>();
+        assert_eq!(
+            PullRequestGovernor::delay_at(&created, min_interval, now),
+            MINUTE - Duration::from_secs(2 * (MAX_PULL_REQUESTS_PER_MINUTE as u64 - 1) + 10)
+        );
+    }
+}
@@ -1,4 +1,10 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    str::FromStr,
+};
+
 use serde::{Deserialize, Serialize};
+use winget_types::{PackageIdentifier, PackageVersion};
 
 #[derive(Serialize, Deserialize)]
 pub struct GitTree {
@@ -17,3 +23,88 @@ pub struct TreeObject {
     pub size: Option<i32>,
     pub url: String,
 }
+
+const BLOB: &str = "blob";
+const YAML_EXTENSION: &str = ".yaml";
+
+impl GitTree {
+    /// Finds every version of every package in a recursive tree from their version manifests,
+    /// which are named after the package identifier and sit in a directory named after the version
+    pub fn packages(&self) -> BTreeMap<PackageIdentifier, BTreeSet<PackageVersion>> {
+        let mut packages = BTreeMap::<_, BTreeSet<_>>::new();
+        for object in self.tree.iter().filter(|object| object.r#type == BLOB) {
+            let mut parts = object.path.rsplit('/');
+            let (Some(file_name), Some(version)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let Some(identifier) = file_name
+                .strip_suffix(YAML_EXTENSION)
+                .filter(|stem| !stem.contains(".installer") && !stem.contains(".locale."))
+            else {
+                continue;
+            };
+            if let (Ok(identifier), Ok(version)) = (
+                PackageIdentifier::from_str(identifier),
+                PackageVersion::from_str(version),
+            ) {
+                packages.entry(identifier).or_default().insert(version);
+            }
+        }
+        packages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GitTree, TreeObject};
+
+    fn blob(path: &str) -> TreeObject {
+        TreeObject {
+            path: path.to_owned(),
+            mode: String::from("100644"),
+            r#type: String::from("blob"),
+            sha: String::new(),
+            size: None,
+            url: String::new(),
+        }
+    }
+
+    #[test]
+    fn packages() {
+        let tree = GitTree {
+            sha: String::new(),
+            url: String::new(),
+            truncated: false,
+            tree: vec![
+                blob("App/1.0.0/Contoso.App.installer.yaml"),
+                blob("App/1.0.0/Contoso.App.locale.en-US.yaml"),
+                blob("App/1.0.0/Contoso.App.yaml"),
+                blob("App/2.0.0/Contoso.App.yaml"),
+                blob("App/Cli/1.0.0/Contoso.App.Cli.yaml"),
+                blob("App/Cli/1.0.0/Contoso.App.Cli.installer.yaml"),
+                blob(".validation"),
+            ],
+        };
+
+        let packages = tree
+            .packages()
+            .into_iter()
+            .map(|(identifier, versions)| {
+                (
+                    identifier.to_string(),
+                    versions.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            packages,
+            [
+                (
+                    "Contoso.App".to_owned(),
+                    vec!["1.0.0".to_owned(), "2.0.0".to_owned()]
+                ),
+                ("Contoso.App.Cli".to_owned(), vec!["1.0.0".to_owned()]),
+            ]
+        );
+    }
+}
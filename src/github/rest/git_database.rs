@@ -0,0 +1,99 @@
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+const BLOB_MODE: &str = "100644";
+const BLOB: &str = "blob";
+
+/// <https://docs.github.com/rest/git/trees#create-a-tree>
+#[derive(Serialize)]
+pub struct CreateTree<'a> {
+    pub base_tree: &'a str,
+    pub tree: Vec<TreeEntry<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct TreeEntry<'a> {
+    path: &'a str,
+    mode: &'static str,
+    r#type: &'static str,
+    #[serde(flatten)]
+    change: TreeEntryChange<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum TreeEntryChange<'a> {
+    Content(Cow<'a, str>),
+    /// A null SHA deletes the file
+    Sha(Option<&'a str>),
+}
+
+impl<'a> TreeEntry<'a> {
+    pub fn addition(path: &'a str, content: impl Into<Cow<'a, str>>) -> Self {
+        Self {
+            path,
+            mode: BLOB_MODE,
+            r#type: BLOB,
+            change: TreeEntryChange::Content(content.into()),
+        }
+    }
+
+    pub const fn deletion(path: &'a str) -> Self {
+        Self {
+            path,
+            mode: BLOB_MODE,
+            r#type: BLOB,
+            change: TreeEntryChange::Sha(None),
+        }
+    }
+}
+
+/// <https://docs.github.com/rest/git/commits#create-a-commit>
+#[derive(Serialize)]
+pub struct CreateCommit<'a> {
+    pub message: &'a str,
+    pub tree: &'a str,
+    pub parents: [&'a str; 1],
+    pub author: CommitIdentity<'a>,
+    pub committer: CommitIdentity<'a>,
+    pub signature: &'a str,
+}
+
+#[derive(Clone, Copy, Serialize)]
+pub struct CommitIdentity<'a> {
+    pub name: &'a str,
+    pub email: &'a str,
+    pub date: &'a str,
+}
+
+#[derive(Deserialize)]
+pub struct CreatedTree {
+    pub sha: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreatedCommit {
+    pub sha: String,
+    pub html_url: Url,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::TreeEntry;
+
+    #[test]
+    fn tree_entries() {
+        assert_eq!(
+            serde_json::to_value(TreeEntry::addition("a.yaml", "content")).unwrap(),
+            json!({ "path": "a.yaml", "mode": "100644", "type": "blob", "content": "content" })
+        );
+        assert_eq!(
+            serde_json::to_value(TreeEntry::deletion("b.yaml")).unwrap(),
+            json!({ "path": "b.yaml", "mode": "100644", "type": "blob", "sha": null })
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use reqwest::header::HeaderValue;
 
 pub mod get_tree;
+pub mod git_database;
 
 pub const GITHUB_JSON_MIME: HeaderValue = HeaderValue::from_static("application/vnd.github+json");
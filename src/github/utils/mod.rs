@@ -13,11 +13,15 @@ use crate::update_state::UpdateState;
 
 mod package_path;
 pub mod pull_request;
+pub mod template;
 
 const YAML_EXTENSION: &str = ".yaml";
 const LOCALE_PART: &str = ".locale.";
 const INSTALLER_PART: &str = ".installer";
 
+/// GitHub rejects branch names longer than 255 bytes. Considering `refs/heads/`, 244 bytes are left for the name.
+const MAX_BRANCH_NAME_LEN: usize = u8::MAX as usize - "refs/heads/".len();
+
 pub fn is_manifest_file<M: Manifest>(
     file_name: &str,
     package_identifier: &PackageIdentifier,
@@ -68,6 +72,7 @@ pub fn is_manifest_file<M: Manifest>(
 #[builder(finish_fn = get)]
 pub fn pull_request_body(
     issue_resolves: Option<Vec<NonZeroU32>>,
+    package_titles: Option<Vec<String>>,
     alternative_text: Option<String>,
     created_with: Option<String>,
     created_with_url: Option<DecodedUrl>,
@@ -119,6 +124,12 @@ pub fn pull_request_body(
 
         let _ = writeln!(body, " :{emoji}:");
     }
+    if let Some(package_titles) = package_titles.filter(|titles| !titles.is_empty()) {
+        let _ = writeln!(body);
+        for title in package_titles {
+            let _ = writeln!(body, "- {title}");
+        }
+    }
     if let Some(issue_resolves) = issue_resolves.filter(|resolves| !resolves.is_empty()) {
         let _ = writeln!(body);
         for resolve in BTreeSet::from_iter(issue_resolves) {
@@ -132,18 +143,49 @@ pub fn get_branch_name(
     package_identifier: &PackageIdentifier,
     package_version: &PackageVersion,
 ) -> String {
-    /// GitHub rejects branch names longer than 255 bytes. Considering `refs/heads/`, 244 bytes are left for the name.
-    const MAX_BRANCH_NAME_LEN: usize = u8::MAX as usize - "refs/heads/".len();
+    unique_branch_name(&format!("{package_identifier}-{package_version}-"))
+}
+
+/// Appends a UUID to a branch name prefix so that every branch is unique, truncating the prefix so
+/// that the branch name fits within GitHub's length limit
+fn unique_branch_name(prefix: &str) -> String {
     let mut uuid_buffer = Uuid::encode_buffer();
     let uuid = Uuid::new_v4().simple().encode_upper(&mut uuid_buffer);
-    let mut branch_name = format!("{package_identifier}-{package_version}-{uuid}");
-    if branch_name.len() > MAX_BRANCH_NAME_LEN {
-        branch_name.truncate(MAX_BRANCH_NAME_LEN - uuid.len());
-        branch_name.push_str(uuid);
+    let mut branch_name = sanitise_branch_name(prefix);
+    if branch_name.len() > MAX_BRANCH_NAME_LEN - uuid.len() {
+        let mut len = MAX_BRANCH_NAME_LEN - uuid.len();
+        while !branch_name.is_char_boundary(len) {
+            len -= 1;
+        }
+        branch_name.truncate(len);
     }
+    branch_name.push_str(uuid);
     branch_name
 }
 
+/// Replaces the characters that are not allowed in a Git ref name, as described by
+/// [`git check-ref-format`](https://git-scm.com/docs/git-check-ref-format), with `-`.
+///
+/// The result is meant to be followed by a UUID, so it may still end with `/` or `.`.
+fn sanitise_branch_name(branch_name: &str) -> String {
+    let mut sanitised = String::with_capacity(branch_name.len());
+    for char in branch_name.chars() {
+        let char = match (sanitised.chars().next_back(), char) {
+            (_, char) if char.is_control() || char.is_whitespace() => '-',
+            (_, '~' | '^' | ':' | '?' | '*' | '[' | '\\') | (Some('@'), '{') => '-',
+            // Components cannot be empty or start with a dot, and `..` is not allowed anywhere
+            (None | Some('/'), '/' | '.') | (Some('.'), '.') => continue,
+            (_, char) => char,
+        };
+        if char == '/' && sanitised.ends_with(".lock") {
+            sanitised.truncate(sanitised.len() - ".lock".len());
+            sanitised.push_str("-lock");
+        }
+        sanitised.push(char);
+    }
+    sanitised
+}
+
 pub fn get_commit_title(
     identifier: &PackageIdentifier,
     version: &PackageVersion,
@@ -152,8 +194,46 @@ pub fn get_commit_title(
     format!("{update_state}: {identifier} version {version}")
 }
 
+/// Reads the package identifier and version from the path of a manifest file created by
+/// [`PackagePath::new`], such as `manifests/p/Package/Identifier/1.2.3/Package.Identifier.yaml`
+pub fn parse_manifest_path(path: &str) -> Option<(PackageIdentifier, PackageVersion)> {
+    let (_first_character, path) = path.strip_prefix("manifests/")?.split_once('/')?;
+    let mut parts = path.split('/');
+    let file_name = parts.next_back()?;
+    let version = parts.next_back()?;
+    let identifier = parts.collect::<Vec<_>>().join(".");
+    if !file_name.starts_with(&identifier) || !file_name.ends_with(YAML_EXTENSION) {
+        return None;
+    }
+    Some((identifier.parse().ok()?, version.parse().ok()?))
+}
+
+/// Combines the commit titles of several packages into one. The update state is only kept if it is
+/// the same for every package.
+pub fn get_combined_commit_title<'a>(
+    packages: impl IntoIterator<Item = (&'a PackageIdentifier, &'a PackageVersion, UpdateState)>,
+) -> String {
+    let packages = packages.into_iter().collect::<Vec<_>>();
+    let prefix = match packages.as_slice() {
+        [(identifier, version, update_state)] => {
+            return get_commit_title(identifier, version, *update_state);
+        }
+        [(_, _, first), rest @ ..] if rest.iter().all(|(_, _, state)| state == first) => {
+            first.to_string()
+        }
+        _ => String::from("Multiple packages"),
+    };
+    let packages = packages
+        .iter()
+        .map(|(identifier, version, _)| format!("{identifier} version {version}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{prefix}: {packages}")
+}
+
 #[cfg(test)]
 mod tests {
+    use rstest::rstest;
     use winget_types::{
         LanguageTag, PackageIdentifier,
         icu_locid::langid,
@@ -162,7 +242,11 @@ mod tests {
         version::VersionManifest,
     };
 
-    use super::is_manifest_file;
+    use super::{
+        MAX_BRANCH_NAME_LEN, get_combined_commit_title, is_manifest_file, parse_manifest_path,
+        sanitise_branch_name, unique_branch_name,
+    };
+    use crate::update_state::UpdateState;
 
     #[test]
     fn valid_installer_manifest_file() {
@@ -235,4 +319,61 @@ mod tests {
             None,
         ))
     }
+
+    #[test]
+    fn combined_commit_title() {
+        let first = "Package.First".parse::<PackageIdentifier>().unwrap();
+        let second = "Package.Second".parse::<PackageIdentifier>().unwrap();
+        let version = "1.2.3".parse().unwrap();
+
+        assert_eq!(
+            get_combined_commit_title([
+                (&first, &version, UpdateState::NewVersion),
+                (&second, &version, UpdateState::NewVersion),
+            ]),
+            "New version: Package.First version 1.2.3, Package.Second version 1.2.3"
+        );
+        assert_eq!(
+            get_combined_commit_title([
+                (&first, &version, UpdateState::NewVersion),
+                (&second, &version, UpdateState::NewPackage),
+            ]),
+            "Multiple packages: Package.First version 1.2.3, Package.Second version 1.2.3"
+        );
+    }
+
+    #[rstest]
+    #[case("Package.Identifier-1.2.3-", "Package.Identifier-1.2.3-")]
+    #[case("feature/new version: 1.2.3~", "feature/new-version--1.2.3-")]
+    #[case(
+        "/.hidden//path..name.lock/@{upstream}",
+        "hidden/path.name-lock/@-upstream}"
+    )]
+    fn sanitise_branch_names(#[case] branch_name: &str, #[case] expected: &str) {
+        assert_eq!(sanitise_branch_name(branch_name), expected);
+    }
+
+    #[test]
+    fn branch_name_keeps_uuid() {
+        let branch_name = unique_branch_name(&"a".repeat(300));
+        assert_eq!(branch_name.len(), MAX_BRANCH_NAME_LEN);
+        assert!(
+            branch_name[MAX_BRANCH_NAME_LEN - 32..]
+                .chars()
+                .all(|char| char.is_ascii_hexdigit())
+        );
+    }
+
+    #[test]
+    fn parse_path() {
+        let (identifier, version) = parse_manifest_path(
+            "manifests/p/Package/Identifier/1.2.3/Package.Identifier.locale.en-US.yaml",
+        )
+        .unwrap();
+        assert_eq!(identifier.as_str(), "Package.Identifier");
+        assert_eq!(version.as_str(), "1.2.3");
+
+        assert!(parse_manifest_path("manifests/p/Package/Identifier/1.2.3/README.md").is_none());
+        assert!(parse_manifest_path(".github/CODEOWNERS").is_none());
+    }
 }
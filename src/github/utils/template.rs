@@ -0,0 +1,404 @@
+use std::{collections::BTreeSet, fs, io, num::NonZeroU32, str::FromStr};
+
+use camino::Utf8Path;
+use clap::{crate_name, crate_version};
+use derive_more::Display;
+use itertools::Itertools;
+use serde::Deserialize;
+use serde_with::DeserializeFromStr;
+use thiserror::Error;
+use uuid::Uuid;
+use winget_types::{
+    PackageIdentifier, PackageVersion, installer::InstallerManifest, url::DecodedUrl,
+};
+
+use super::{
+    get_branch_name, get_combined_commit_title, get_commit_title, pull_request_body,
+    unique_branch_name,
+};
+use crate::update_state::UpdateState;
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("Unknown template variable `{{{0}}}`. Expected one of {variables}", variables = Variable::ALL.iter().map(|variable| format!("{{{variable}}}")).join(", "))]
+    UnknownVariable(String),
+    #[error("Unclosed `{{` in template. Use `{{{{` to insert a literal brace")]
+    Unclosed,
+    #[error("Unmatched `}}` in template. Use `}}}}` to insert a literal brace")]
+    Unmatched,
+    #[error("Failed to read templates file {path}: {source}")]
+    ReadFile { path: String, source: io::Error },
+    #[error("Failed to parse templates file {path}: {source}")]
+    ParseFile {
+        path: String,
+        source: serde_yaml::Error,
+    },
+}
+
+#[derive(Copy, Clone, Debug, Display, Eq, PartialEq)]
+enum Variable {
+    #[display("identifier")]
+    Identifier,
+    #[display("version")]
+    Version,
+    #[display("update_state")]
+    UpdateState,
+    #[display("resolves")]
+    Resolves,
+    #[display("installers")]
+    Installers,
+    #[display("created_with")]
+    CreatedWith,
+    #[display("uuid")]
+    Uuid,
+}
+
+impl Variable {
+    const ALL: [Self; 7] = [
+        Self::Identifier,
+        Self::Version,
+        Self::UpdateState,
+        Self::Resolves,
+        Self::Installers,
+        Self::CreatedWith,
+        Self::Uuid,
+    ];
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Segment {
+    Literal(String),
+    Variable(Variable),
+}
+
+/// A string containing `{variable}` placeholders, with `{{` and `}}` for literal braces
+#[derive(Clone, Debug, DeserializeFromStr)]
+pub struct Template(Vec<Segment>);
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(char) = chars.next() {
+            match char {
+                '{' if chars.next_if_eq(&'{').is_some() => literal.push('{'),
+                '}' if chars.next_if_eq(&'}').is_some() => literal.push('}'),
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('{') | None => return Err(TemplateError::Unclosed),
+                            Some(char) => name.push(char),
+                        }
+                    }
+                    let variable = Variable::ALL
+                        .into_iter()
+                        .find(|variable| variable.to_string() == name.trim())
+                        .ok_or(TemplateError::UnknownVariable(name))?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Variable(variable));
+                }
+                '}' => return Err(TemplateError::Unmatched),
+                _ => literal.push(char),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Self(segments))
+    }
+}
+
+/// The values that can be inserted into a pull request template
+pub struct TemplateValues<'a> {
+    pub identifier: &'a PackageIdentifier,
+    pub version: &'a PackageVersion,
+    pub update_state: UpdateState,
+    pub resolves: Option<&'a [NonZeroU32]>,
+    pub installers: Option<&'a str>,
+    pub created_with: Option<&'a str>,
+    pub created_with_url: Option<&'a DecodedUrl>,
+}
+
+impl Template {
+    pub fn render(&self, values: &TemplateValues) -> String {
+        self.0
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.clone(),
+                Segment::Variable(Variable::Identifier) => values.identifier.to_string(),
+                Segment::Variable(Variable::Version) => values.version.to_string(),
+                Segment::Variable(Variable::UpdateState) => values.update_state.to_string(),
+                Segment::Variable(Variable::Resolves) => values
+                    .resolves
+                    .into_iter()
+                    .flatten()
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .map(|resolve| format!("#{resolve}"))
+                    .join(", "),
+                Segment::Variable(Variable::Installers) => {
+                    values.installers.unwrap_or_default().to_owned()
+                }
+                Segment::Variable(Variable::CreatedWith) => {
+                    match (values.created_with, values.created_with_url) {
+                        (Some(tool_name), Some(tool_url)) => format!("[{tool_name}]({tool_url})"),
+                        (Some(tool_name), None) => tool_name.to_owned(),
+                        _ => format!(
+                            "[{}]({}) v{}",
+                            crate_name!(),
+                            env!("CARGO_PKG_REPOSITORY"),
+                            crate_version!()
+                        ),
+                    }
+                }
+                Segment::Variable(Variable::Uuid) => {
+                    let mut uuid_buffer = Uuid::encode_buffer();
+                    Uuid::new_v4()
+                        .simple()
+                        .encode_upper(&mut uuid_buffer)
+                        .to_owned()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Templates for the title, body and branch name of pull requests. Any that are not set use
+/// Komac's default text.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PullRequestTemplates {
+    pub title: Option<Template>,
+    pub body: Option<Template>,
+    pub branch: Option<Template>,
+}
+
+impl PullRequestTemplates {
+    /// Reads templates from a YAML file with `title`, `body` and `branch` keys
+    pub fn from_file(path: &Utf8Path) -> Result<Self, TemplateError> {
+        let contents = fs::read_to_string(path).map_err(|source| TemplateError::ReadFile {
+            path: path.to_string(),
+            source,
+        })?;
+        serde_yaml::from_str(&contents).map_err(|source| TemplateError::ParseFile {
+            path: path.to_string(),
+            source,
+        })
+    }
+
+    /// Returns the commit and pull request title
+    pub fn title(&self, values: &TemplateValues) -> String {
+        self.title.as_ref().map_or_else(
+            || get_commit_title(values.identifier, values.version, values.update_state),
+            |template| template.render(values),
+        )
+    }
+
+    pub fn body(&self, values: &TemplateValues) -> String {
+        self.body.as_ref().map_or_else(
+            || {
+                pull_request_body()
+                    .maybe_issue_resolves(values.resolves.map(<[NonZeroU32]>::to_vec))
+                    .maybe_created_with(values.created_with.map(str::to_owned))
+                    .maybe_created_with_url(values.created_with_url.cloned())
+                    .get()
+            },
+            |template| template.render(values),
+        )
+    }
+
+    /// Returns the commit and pull request title for several packages. A title template is
+    /// rendered for each package and the titles are joined.
+    pub fn combined_title(&self, packages: &[TemplateValues]) -> String {
+        match (&self.title, packages) {
+            (_, [values]) => self.title(values),
+            (Some(template), packages) => packages
+                .iter()
+                .map(|values| template.render(values))
+                .join(", "),
+            (None, packages) => get_combined_commit_title(
+                packages
+                    .iter()
+                    .map(|values| (values.identifier, values.version, values.update_state)),
+            ),
+        }
+    }
+
+    /// Returns the pull request body for several packages. A body template is rendered for each
+    /// package and the bodies are joined.
+    pub fn combined_body(&self, packages: &[TemplateValues]) -> String {
+        match (&self.body, packages) {
+            (_, [values]) => self.body(values),
+            (Some(template), packages) => packages
+                .iter()
+                .map(|values| template.render(values))
+                .join("\n"),
+            (None, packages) => {
+                let first = packages.first();
+                pull_request_body()
+                    .maybe_issue_resolves(
+                        first.and_then(|values| values.resolves.map(<[NonZeroU32]>::to_vec)),
+                    )
+                    .maybe_created_with(
+                        first.and_then(|values| values.created_with.map(str::to_owned)),
+                    )
+                    .maybe_created_with_url(
+                        first.and_then(|values| values.created_with_url.cloned()),
+                    )
+                    .package_titles(
+                        packages
+                            .iter()
+                            .map(|values| {
+                                get_commit_title(
+                                    values.identifier,
+                                    values.version,
+                                    values.update_state,
+                                )
+                            })
+                            .collect(),
+                    )
+                    .get()
+            }
+        }
+    }
+
+    /// Returns a branch name that always ends with a UUID so that it is unique, even if the
+    /// template does not end with `{uuid}`. Characters that are not allowed in branch names are
+    /// replaced with `-`.
+    pub fn branch_name(&self, values: &TemplateValues) -> String {
+        self.branch.as_ref().map_or_else(
+            || get_branch_name(values.identifier, values.version),
+            |template| {
+                let prefix = match template.0.split_last() {
+                    Some((Segment::Variable(Variable::Uuid), prefix)) => {
+                        Template(prefix.to_vec()).render(values)
+                    }
+                    _ => format!("{}-", template.render(values)),
+                };
+                unique_branch_name(&prefix)
+            },
+        )
+    }
+}
+
+/// Summarises the architecture and type of each installer, such as `x64 msi, arm64 msix`
+pub fn installer_summary(manifest: &InstallerManifest) -> String {
+    manifest
+        .installers
+        .iter()
+        .map(|installer| {
+            installer.r#type.or(manifest.r#type).map_or_else(
+                || installer.architecture.to_string(),
+                |installer_type| format!("{} {installer_type}", installer.architecture),
+            )
+        })
+        .unique()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+
+    use indoc::indoc;
+    use rstest::rstest;
+    use winget_types::{PackageIdentifier, PackageVersion};
+
+    use super::{PullRequestTemplates, Template, TemplateError, TemplateValues};
+    use crate::update_state::UpdateState;
+
+    fn render(template: &str) -> String {
+        let identifier = "Package.Identifier".parse::<PackageIdentifier>().unwrap();
+        let version = "1.2.3".parse::<PackageVersion>().unwrap();
+        let resolves = [NonZeroU32::new(456).unwrap(), NonZeroU32::new(123).unwrap()];
+        template
+            .parse::<Template>()
+            .unwrap()
+            .render(&TemplateValues {
+                identifier: &identifier,
+                version: &version,
+                update_state: UpdateState::NewVersion,
+                resolves: Some(&resolves),
+                installers: Some("x64 msi, arm64 msix"),
+                created_with: Some("Tool"),
+                created_with_url: None,
+            })
+    }
+
+    #[rstest]
+    #[case(
+        "[ABC-1] {update_state}: {identifier} {version}",
+        "[ABC-1] New version: Package.Identifier 1.2.3"
+    )]
+    #[case("Resolves {resolves}", "Resolves #123, #456")]
+    #[case("{installers} via {created_with}", "x64 msi, arm64 msix via Tool")]
+    #[case("{{literal}} {{{version}}}", "{literal} {1.2.3}")]
+    fn render_template(#[case] template: &str, #[case] expected: &str) {
+        assert_eq!(render(template), expected);
+    }
+
+    #[test]
+    fn render_uuid() {
+        let branch = render("{identifier}-{uuid}");
+        assert_eq!(branch.len(), "Package.Identifier-".len() + 32);
+    }
+
+    #[rstest]
+    #[case("{identifier}/{version}-{uuid}", "Package.Identifier/1.2.3-")]
+    #[case("release {version}", "release-1.2.3-")]
+    fn branch_name_ends_with_uuid(#[case] template: &str, #[case] prefix: &str) {
+        let templates = PullRequestTemplates {
+            branch: Some(template.parse().unwrap()),
+            ..PullRequestTemplates::default()
+        };
+        let identifier = "Package.Identifier".parse::<PackageIdentifier>().unwrap();
+        let version = "1.2.3".parse::<PackageVersion>().unwrap();
+
+        let branch_name = templates.branch_name(&TemplateValues {
+            identifier: &identifier,
+            version: &version,
+            update_state: UpdateState::NewVersion,
+            resolves: None,
+            installers: None,
+            created_with: None,
+            created_with_url: None,
+        });
+
+        assert_eq!(branch_name.len(), prefix.len() + 32, "{branch_name}");
+        assert!(branch_name.starts_with(prefix), "{branch_name}");
+    }
+
+    #[rstest]
+    #[case("{unknown}")]
+    #[case("{version")]
+    #[case("version}")]
+    fn invalid_template(#[case] template: &str) {
+        assert!(matches!(
+            template.parse::<Template>(),
+            Err(TemplateError::UnknownVariable(_)
+                | TemplateError::Unclosed
+                | TemplateError::Unmatched)
+        ));
+    }
+
+    #[test]
+    fn templates_file() {
+        const TEMPLATES: &str = indoc! {"
+            title: 'JIRA-42: {identifier} {version}'
+            body: |
+              - [ ] Tested {installers}
+        "};
+
+        let templates = serde_yaml::from_str::<PullRequestTemplates>(TEMPLATES).unwrap();
+        assert!(templates.title.is_some());
+        assert!(templates.body.is_some());
+        assert!(templates.branch.is_none());
+    }
+}
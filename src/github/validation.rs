@@ -0,0 +1,220 @@
+use std::collections::BTreeSet;
+
+use derive_more::Display;
+use winget_types::{PackageIdentifier, PackageVersion};
+
+/// Accounts that post the results of the winget-pkgs validation pipeline
+pub const VALIDATION_BOTS: [&str; 3] = [
+    "wingetbot",
+    "microsoft-github-policy-service",
+    "azure-pipelines",
+];
+
+/// A category of failure reported by the winget-pkgs validation pipeline
+#[derive(Copy, Clone, Debug, Display, Eq, PartialEq, Ord, PartialOrd)]
+pub enum ValidationIssue {
+    #[display("An installer URL could not be reached")]
+    UrlUnreachable,
+    #[display("An installer URL is not allowed")]
+    UrlNotAllowed,
+    #[display("An installer's SHA256 does not match the manifest")]
+    HashMismatch,
+    #[display("An installer failed to install silently")]
+    SilentInstallFailed,
+    #[display("The installed Apps and Features entries do not match the manifest")]
+    AppsAndFeaturesMismatch,
+    #[display("No executables were found after installing")]
+    NoExecutables,
+    #[display("A dependency could not be found")]
+    MissingDependency,
+    #[display("An installer was flagged by the security scan")]
+    SecurityScan,
+    #[display("The manifests failed validation")]
+    ManifestError,
+    #[display("The pull request has a merge conflict")]
+    MergeConflict,
+    #[display("The validation pipeline encountered an internal error")]
+    InternalError,
+}
+
+struct Rule {
+    issue: ValidationIssue,
+    labels: &'static [&'static str],
+    /// Lowercase phrases that appear in the validation bot's comments
+    phrases: &'static [&'static str],
+}
+
+const RULES: [Rule; 11] = [
+    Rule {
+        issue: ValidationIssue::UrlUnreachable,
+        labels: &["URL-Validation-Error", "Error-Installer-Availability"],
+        phrases: &[
+            "installer url is not reachable",
+            "failed to download",
+            "404 (not found)",
+        ],
+    },
+    Rule {
+        issue: ValidationIssue::UrlNotAllowed,
+        labels: &[
+            "Validation-Forbidden-URL-Error",
+            "Validation-Unapproved-URL",
+            "Validation-Indirect-URL",
+            "Validation-Domain",
+        ],
+        phrases: &["url is not allowed", "unapproved url"],
+    },
+    Rule {
+        issue: ValidationIssue::HashMismatch,
+        labels: &["Error-Hash-Mismatch", "Validation-Hash-Verification-Failed"],
+        phrases: &["hash mismatch", "sha256 does not match"],
+    },
+    Rule {
+        issue: ValidationIssue::SilentInstallFailed,
+        labels: &[
+            "Validation-Installation-Error",
+            "Validation-Unattended-Failed",
+            "Validation-Shell-Execute",
+        ],
+        phrases: &["failed to install silently", "unattended installation"],
+    },
+    Rule {
+        issue: ValidationIssue::AppsAndFeaturesMismatch,
+        labels: &[
+            "Manifest-AppsAndFeaturesVersion-Error",
+            "Validation-Apps-And-Features-Mismatch",
+        ],
+        phrases: &["appsandfeaturesentries", "arp entry", "arp entries"],
+    },
+    Rule {
+        issue: ValidationIssue::NoExecutables,
+        labels: &["Validation-No-Executables"],
+        phrases: &["no executables"],
+    },
+    Rule {
+        issue: ValidationIssue::MissingDependency,
+        labels: &["Validation-Missing-Dependency"],
+        phrases: &["missing dependency", "dependency not found"],
+    },
+    Rule {
+        issue: ValidationIssue::SecurityScan,
+        labels: &["Validation-Defender-Error", "Binary-Validation-Error"],
+        phrases: &["defender", "malware"],
+    },
+    Rule {
+        issue: ValidationIssue::ManifestError,
+        labels: &[
+            "Manifest-Validation-Error",
+            "Manifest-Installer-Validation-Error",
+        ],
+        phrases: &["manifest validation error", "manifest error"],
+    },
+    Rule {
+        issue: ValidationIssue::MergeConflict,
+        labels: &["Validation-Merge-Conflict"],
+        phrases: &["merge conflict"],
+    },
+    Rule {
+        issue: ValidationIssue::InternalError,
+        labels: &["Internal-Error", "Internal-Error-Dynamic-Scan"],
+        phrases: &["internal error"],
+    },
+];
+
+impl ValidationIssue {
+    /// Finds the issues indicated by the pull request's labels and the validation bot's comments
+    pub fn find<'a>(
+        labels: impl IntoIterator<Item = &'a str>,
+        bot_comments: impl IntoIterator<Item = &'a str>,
+    ) -> BTreeSet<Self> {
+        let labels = labels.into_iter().collect::<Vec<_>>();
+        let bot_comments = bot_comments
+            .into_iter()
+            .map(str::to_ascii_lowercase)
+            .collect::<Vec<_>>();
+
+        RULES
+            .iter()
+            .filter(|rule| {
+                labels.iter().any(|label| {
+                    rule.labels
+                        .iter()
+                        .any(|rule_label| rule_label.eq_ignore_ascii_case(label))
+                }) || bot_comments
+                    .iter()
+                    .any(|comment| rule.phrases.iter().any(|phrase| comment.contains(phrase)))
+            })
+            .map(|rule| rule.issue)
+            .collect()
+    }
+
+    /// Suggests how to fix the issue, including the Komac command to run where there is one
+    pub fn suggestion(
+        self,
+        package: Option<(&PackageIdentifier, &PackageVersion)>,
+    ) -> Option<String> {
+        let update = package.map_or_else(
+            || String::from("komac update <IDENTIFIER> --version <VERSION>"),
+            |(identifier, version)| format!("komac update {identifier} --version {version}"),
+        );
+        match self {
+            Self::UrlUnreachable => Some(format!(
+                "Replace the unreachable URL: {update} --urls <URLS> --amend-pr"
+            )),
+            Self::UrlNotAllowed => Some(format!(
+                "Use a direct URL from the publisher's domain: {update} --urls <URLS> --amend-pr"
+            )),
+            Self::HashMismatch => Some(format!(
+                "Download the installers again to recalculate their hashes: {update} --urls <URLS> --amend-pr"
+            )),
+            Self::SilentInstallFailed => Some(String::from(
+                "Check the installer switches detected by `komac analyse <INSTALLER>` and correct InstallerSwitches",
+            )),
+            Self::AppsAndFeaturesMismatch => Some(format!(
+                "Correct AppsAndFeaturesEntries to match the installed entries: {update} --urls <URLS> --diff --amend-pr"
+            )),
+            Self::MergeConflict => Some(format!(
+                "Close the pull request and create it again from the updated master branch: {update} --urls <URLS>, or replace the conflicting version with --replace <VERSION>"
+            )),
+            Self::InternalError => Some(String::from(
+                "Comment `@wingetbot run` on the pull request to run the validation pipeline again",
+            )),
+            Self::NoExecutables
+            | Self::MissingDependency
+            | Self::SecurityScan
+            | Self::ManifestError => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use indoc::indoc;
+
+    use super::ValidationIssue;
+
+    #[test]
+    fn find_issues() {
+        const COMMENT: &str = indoc! {"
+            Validation Pipeline Run WinGetSvc-Validation-123-456
+            Error: the SHA256 does not match the installer downloaded from the URL.
+        "};
+
+        assert_eq!(
+            ValidationIssue::find(["Needs-Author-Feedback", "url-validation-error"], [COMMENT]),
+            BTreeSet::from([
+                ValidationIssue::UrlUnreachable,
+                ValidationIssue::HashMismatch
+            ])
+        );
+    }
+
+    #[test]
+    fn no_issues() {
+        assert!(
+            ValidationIssue::find(["Azure-Pipeline-Passed"], ["Validation Completed"]).is_empty()
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+pub const INTEGER: u8 = 0x02;
+pub const OCTET_STRING: u8 = 0x04;
+pub const OBJECT_IDENTIFIER: u8 = 0x06;
+pub const UTF8_STRING: u8 = 0x0C;
+pub const PRINTABLE_STRING: u8 = 0x13;
+pub const T61_STRING: u8 = 0x14;
+pub const IA5_STRING: u8 = 0x16;
+pub const UTC_TIME: u8 = 0x17;
+pub const GENERALIZED_TIME: u8 = 0x18;
+pub const BMP_STRING: u8 = 0x1E;
+pub const SEQUENCE: u8 = 0x30;
+pub const SET: u8 = 0x31;
+pub const CONTEXT_0: u8 = 0xA0;
+pub const CONTEXT_1: u8 = 0xA1;
+
+/// A single DER encoded tag-length-value.
+///
+/// Only single-byte tags and definite lengths are supported, which covers everything used by
+/// Authenticode signatures.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Tlv<'a> {
+    pub tag: u8,
+    pub content: &'a [u8],
+}
+
+impl<'a> Tlv<'a> {
+    /// Reads a TLV from the start of the data, returning it along with the remaining data.
+    pub fn read(data: &'a [u8]) -> Option<(Self, &'a [u8])> {
+        let (&tag, data) = data.split_first()?;
+        let (&first_length, data) = data.split_first()?;
+
+        let (length, data) = if first_length & 0x80 == 0 {
+            (usize::from(first_length), data)
+        } else {
+            let length_bytes = usize::from(first_length & 0x7F);
+            if length_bytes == 0 || length_bytes > size_of::<u32>() {
+                return None;
+            }
+            let (length, data) = data.split_at_checked(length_bytes)?;
+            let length = length
+                .iter()
+                .fold(0, |length, &byte| (length << 8) | usize::from(byte));
+            (length, data)
+        };
+
+        let (content, rest) = data.split_at_checked(length)?;
+        Some((Self { tag, content }, rest))
+    }
+
+    /// Returns an iterator over the TLVs within this TLV's content
+    pub const fn children(&self) -> Children<'a> {
+        Children(self.content)
+    }
+
+    /// Returns the first child with the given tag
+    pub fn child(&self, tag: u8) -> Option<Self> {
+        self.children().find(|child| child.tag == tag)
+    }
+
+    /// Parses a string of any of the types that can be used in an X.509 name
+    pub fn string(&self) -> Option<String> {
+        match self.tag {
+            UTF8_STRING | PRINTABLE_STRING | IA5_STRING | T61_STRING => {
+                Some(String::from_utf8_lossy(self.content).into_owned())
+            }
+            BMP_STRING => Some(String::from_utf16_lossy(
+                &self
+                    .content
+                    .chunks_exact(2)
+                    .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+                    .collect::<Vec<_>>(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Parses a UTCTime or GeneralizedTime
+    pub fn time(&self) -> Option<DateTime<Utc>> {
+        let text = str::from_utf8(self.content).ok()?.trim_end_matches('Z');
+        // Fractional seconds are allowed in GeneralizedTime but are not needed
+        let text = text.split_once('.').map_or(text, |(time, _fraction)| time);
+        let format = match self.tag {
+            UTC_TIME => "%y%m%d%H%M%S",
+            GENERALIZED_TIME => "%Y%m%d%H%M%S",
+            _ => return None,
+        };
+        NaiveDateTime::parse_from_str(text, format)
+            .ok()
+            .map(|date_time| date_time.and_utc())
+    }
+
+    /// Formats an object identifier in its dotted form, such as `2.5.4.3`
+    pub fn oid(&self) -> Option<String> {
+        if self.tag != OBJECT_IDENTIFIER {
+            return None;
+        }
+        let (&first, rest) = self.content.split_first()?;
+        let mut oid = format!("{}.{}", first / 40, first % 40);
+        let mut value = 0u64;
+        for &byte in rest {
+            value = (value << 7) | u64::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                let _ = write!(oid, ".{value}");
+                value = 0;
+            }
+        }
+        Some(oid)
+    }
+}
+
+pub struct Children<'a>(&'a [u8]);
+
+impl<'a> Iterator for Children<'a> {
+    type Item = Tlv<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (tlv, rest) = Tlv::read(self.0)?;
+        self.0 = rest;
+        Some(tlv)
+    }
+}
+
+/// Formats an X.509 name in the same `/C=US/O=Example/CN=Example` form that yara-x uses for PE
+/// signatures, so that signers of different installer types can be compared.
+pub fn format_name(name: Tlv) -> String {
+    let mut formatted = String::new();
+    for attribute in name
+        .children()
+        .filter(|rdn| rdn.tag == SET)
+        .flat_map(|rdn| rdn.children())
+    {
+        let mut parts = attribute.children();
+        let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        let Some(key) = key.oid() else {
+            continue;
+        };
+        let key = match key.as_str() {
+            "2.5.4.3" => "CN",
+            "2.5.4.5" => "serialNumber",
+            "2.5.4.6" => "C",
+            "2.5.4.7" => "L",
+            "2.5.4.8" => "ST",
+            "2.5.4.9" => "street",
+            "2.5.4.10" => "O",
+            "2.5.4.11" => "OU",
+            "2.5.4.15" => "businessCategory",
+            "2.5.4.17" => "postalCode",
+            "1.3.6.1.4.1.311.60.2.1.1" => "jurisdictionL",
+            "1.3.6.1.4.1.311.60.2.1.2" => "jurisdictionST",
+            "1.3.6.1.4.1.311.60.2.1.3" => "jurisdictionC",
+            other => other,
+        };
+        let _ = write!(formatted, "/{key}={}", value.string().unwrap_or_default());
+    }
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{GENERALIZED_TIME, OBJECT_IDENTIFIER, SEQUENCE, Tlv, UTC_TIME, format_name};
+
+    #[test]
+    fn read_short_and_long_lengths() {
+        let mut data = vec![0x04, 0x81, 0x80];
+        data.extend([0xAB; 0x80]);
+        data.extend([0x05, 0x00]);
+
+        let (tlv, rest) = Tlv::read(&data).unwrap();
+        assert_eq!(tlv.tag, 0x04);
+        assert_eq!(tlv.content.len(), 0x80);
+
+        let (null, rest) = Tlv::read(rest).unwrap();
+        assert_eq!(null.tag, 0x05);
+        assert!(null.content.is_empty());
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn read_truncated() {
+        assert_eq!(Tlv::read(&[0x30, 0x05, 0x01]), None);
+        assert_eq!(Tlv::read(&[0x30, 0x80]), None);
+    }
+
+    #[test]
+    fn oid() {
+        let tlv = Tlv {
+            tag: OBJECT_IDENTIFIER,
+            content: &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x01, 0x04],
+        };
+        assert_eq!(tlv.oid().as_deref(), Some("1.3.6.1.4.1.311.2.1.4"));
+    }
+
+    #[test]
+    fn times() {
+        let utc_time = Tlv {
+            tag: UTC_TIME,
+            content: b"240315120000Z",
+        };
+        let generalized_time = Tlv {
+            tag: GENERALIZED_TIME,
+            content: b"20240315120000.123Z",
+        };
+        let expected = Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap();
+        assert_eq!(utc_time.time(), Some(expected));
+        assert_eq!(generalized_time.time(), Some(expected));
+    }
+
+    #[test]
+    fn name() {
+        #[rustfmt::skip]
+        const NAME: &[u8] = &[
+            0x31, 0x0B, 0x30, 0x09, 0x06, 0x03, 0x55, 0x04, 0x06, 0x13, 0x02, b'U', b'S',
+            0x31, 0x10, 0x30, 0x0E, 0x06, 0x03, 0x55, 0x04, 0x03, 0x0C, 0x07,
+            b'E', b'x', b'a', b'm', b'p', b'l', b'e',
+        ];
+        let name = Tlv {
+            tag: SEQUENCE,
+            content: NAME,
+        };
+        assert_eq!(format_name(name), "/C=US/CN=Example");
+    }
+}
@@ -0,0 +1,149 @@
+mod der;
+mod msi;
+
+use std::{
+    fmt::{Display, Formatter},
+    io,
+    io::Cursor,
+};
+
+use camino::Utf8Path;
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+use yara_x::mods::PE;
+
+use crate::file_analyser::{EXE, MSI};
+
+#[derive(Debug, Error)]
+pub enum AuthenticodeError {
+    #[error("The Authenticode signature is malformed")]
+    InvalidSignature,
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// The signer of an Authenticode signed EXE or MSI
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Signer {
+    pub subject: String,
+    pub issuer: String,
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Whether the digest in the signature matches the file's own digest, or `None` if it could
+    /// not be checked
+    pub digest_valid: Option<bool>,
+}
+
+impl Signer {
+    pub fn from_pe(pe: &PE) -> Option<Self> {
+        let signature = pe.signatures.first()?;
+        Some(Self {
+            subject: signature.subject.clone()?,
+            issuer: signature.issuer.clone().unwrap_or_default(),
+            timestamp: signature
+                .countersignatures
+                .iter()
+                .find_map(|countersignature| countersignature.sign_time)
+                .and_then(|sign_time| DateTime::from_timestamp(sign_time, 0)),
+            digest_valid: signature
+                .digest
+                .as_ref()
+                .zip(signature.file_digest.as_ref())
+                .map(|(digest, file_digest)| digest.eq_ignore_ascii_case(file_digest)),
+        })
+    }
+
+    pub fn from_msi(data: &[u8]) -> Result<Option<Self>, AuthenticodeError> {
+        msi::read_signer(Cursor::new(data))
+    }
+
+    /// Reads the signer of a file based on its extension, returning `None` for unsigned files and
+    /// file types that do not use Authenticode
+    pub fn from_file(data: &[u8], file_name: &str) -> Result<Option<Self>, AuthenticodeError> {
+        let extension = Utf8Path::new(file_name)
+            .extension()
+            .unwrap_or_default()
+            .to_lowercase();
+        match extension.as_str() {
+            EXE => Ok(yara_x::mods::invoke::<PE>(data)
+                .as_deref()
+                .and_then(Self::from_pe)),
+            MSI => Self::from_msi(data),
+            _ => Ok(None),
+        }
+    }
+
+    /// Gets the value of an attribute from the subject, such as `CN` or `O`
+    fn subject_attribute(&self, key: &str) -> Option<&str> {
+        self.subject.split('/').find_map(|attribute| {
+            attribute
+                .split_once('=')
+                .filter(|(attribute_key, _)| *attribute_key == key)
+                .map(|(_, value)| value)
+        })
+    }
+
+    pub fn common_name(&self) -> &str {
+        self.subject_attribute("CN").unwrap_or(&self.subject)
+    }
+
+    /// Whether two signers represent the same publisher. Certificates are renewed regularly so this
+    /// compares the subject's common name and organisation rather than the certificate itself.
+    pub fn is_same_publisher(&self, other: &Self) -> bool {
+        self.common_name() == other.common_name()
+            && self.subject_attribute("O") == other.subject_attribute("O")
+    }
+}
+
+impl Display for Signer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.common_name())?;
+        if let Some(issuer) = self
+            .issuer
+            .split('/')
+            .find_map(|attribute| attribute.strip_prefix("CN="))
+        {
+            write!(f, " (issued by {issuer})")?;
+        }
+        if let Some(timestamp) = self.timestamp {
+            write!(f, ", signed on {}", timestamp.date_naive())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Signer;
+
+    fn signer(subject: &str) -> Signer {
+        Signer {
+            subject: subject.to_owned(),
+            issuer: "/C=US/O=DigiCert, Inc./CN=DigiCert Code Signing CA".to_owned(),
+            timestamp: None,
+            digest_valid: Some(true),
+        }
+    }
+
+    #[test]
+    fn same_publisher_across_renewals() {
+        let old = signer("/C=US/ST=Washington/O=Contoso Ltd/CN=Contoso Ltd");
+        let renewed = signer("/C=US/ST=Oregon/O=Contoso Ltd/CN=Contoso Ltd");
+        assert!(old.is_same_publisher(&renewed));
+    }
+
+    #[test]
+    fn different_publisher() {
+        let old = signer("/C=US/O=Contoso Ltd/CN=Contoso Ltd");
+        let hijacked = signer("/C=US/O=Fabrikam/CN=Contoso Ltd");
+        assert!(!old.is_same_publisher(&hijacked));
+    }
+
+    #[test]
+    fn display() {
+        let signer = signer("/C=US/O=Contoso Ltd/CN=Contoso Ltd");
+        assert_eq!(
+            signer.to_string(),
+            "Contoso Ltd (issued by DigiCert Code Signing CA)"
+        );
+    }
+}
@@ -0,0 +1,500 @@
+use std::{
+    cmp::Ordering,
+    io::{self, Read, Seek},
+    path::Path,
+};
+
+use cfb::CompoundFile;
+use chrono::{DateTime, Utc};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use super::{
+    AuthenticodeError, Signer,
+    der::{
+        CONTEXT_0, CONTEXT_1, GENERALIZED_TIME, INTEGER, OBJECT_IDENTIFIER, OCTET_STRING, SEQUENCE,
+        SET, Tlv, UTC_TIME, format_name,
+    },
+};
+
+const DIGITAL_SIGNATURE: &str = "\u{5}DigitalSignature";
+const MSI_DIGITAL_SIGNATURE_EX: &str = "\u{5}MsiDigitalSignatureEx";
+
+const SHA1: &str = "1.3.14.3.2.26";
+const SHA256: &str = "2.16.840.1.101.3.4.2.1";
+const SHA384: &str = "2.16.840.1.101.3.4.2.2";
+const SHA512: &str = "2.16.840.1.101.3.4.2.3";
+
+const COUNTER_SIGNATURE: &str = "1.2.840.113549.1.9.6";
+const SIGNING_TIME: &str = "1.2.840.113549.1.9.5";
+const MS_COUNTER_SIGNATURE: &str = "1.3.6.1.4.1.311.3.3.1";
+
+/// Reads the Authenticode signature from an MSI's `\x05DigitalSignature` stream.
+///
+/// Returns `None` if the MSI is not signed.
+pub fn read_signer<R: Read + Seek>(reader: R) -> Result<Option<Signer>, AuthenticodeError> {
+    let mut compound_file = CompoundFile::open(reader)?;
+
+    let root = Path::new("/");
+    let signature_path = root.join(DIGITAL_SIGNATURE);
+    if !compound_file.is_stream(&signature_path) {
+        return Ok(None);
+    }
+
+    let mut signature = Vec::new();
+    compound_file
+        .open_stream(&signature_path)?
+        .read_to_end(&mut signature)?;
+
+    let signed_data = SignedData::parse(&signature).ok_or(AuthenticodeError::InvalidSignature)?;
+
+    // MsiDigitalSignatureEx adds a pre-hash of the metadata of every stream, including timestamps,
+    // which isn't worth reproducing just to report digest validity
+    let digest_valid = if compound_file.is_stream(root.join(MSI_DIGITAL_SIGNATURE_EX)) {
+        None
+    } else {
+        let digest = match signed_data.digest_algorithm.as_str() {
+            SHA1 => Some(hash_storage::<_, Sha1>(&mut compound_file)?),
+            SHA256 => Some(hash_storage::<_, Sha256>(&mut compound_file)?),
+            SHA384 => Some(hash_storage::<_, Sha384>(&mut compound_file)?),
+            SHA512 => Some(hash_storage::<_, Sha512>(&mut compound_file)?),
+            _ => None,
+        };
+        digest.map(|digest| digest == signed_data.digest)
+    };
+
+    Ok(Some(Signer {
+        subject: signed_data.subject,
+        issuer: signed_data.issuer,
+        timestamp: signed_data.timestamp,
+        digest_valid,
+    }))
+}
+
+struct SignedData {
+    subject: String,
+    issuer: String,
+    timestamp: Option<DateTime<Utc>>,
+    digest_algorithm: String,
+    digest: Vec<u8>,
+}
+
+impl SignedData {
+    fn parse(data: &[u8]) -> Option<Self> {
+        // ContentInfo ::= SEQUENCE { contentType, [0] EXPLICIT SignedData }
+        let (content_info, _) = Tlv::read(data)?;
+        let signed_data = content_info.child(CONTEXT_0)?.child(SEQUENCE)?;
+        let mut children = signed_data.children();
+
+        // SignedData ::= SEQUENCE { version, digestAlgorithms, encapContentInfo, [0] certificates,
+        // [1] crls, signerInfos }
+        let _version = children.next().filter(|tlv| tlv.tag == INTEGER)?;
+        let _digest_algorithms = children.next().filter(|tlv| tlv.tag == SET)?;
+        let encap_content_info = children.next().filter(|tlv| tlv.tag == SEQUENCE)?;
+        let remaining = children.collect::<Vec<_>>();
+        let certificates = remaining.iter().find(|tlv| tlv.tag == CONTEXT_0);
+        let signer_info = remaining
+            .iter()
+            .rfind(|tlv| tlv.tag == SET)?
+            .child(SEQUENCE)?;
+
+        // SpcIndirectDataContent ::= SEQUENCE { data, messageDigest DigestInfo }
+        let indirect_data = encap_content_info.child(CONTEXT_0)?.child(SEQUENCE)?;
+        let digest_info = indirect_data.children().nth(1)?;
+        let mut digest_info = digest_info.children();
+        let digest_algorithm = digest_info.next()?.child(OBJECT_IDENTIFIER)?.oid()?;
+        let digest = digest_info
+            .next()
+            .filter(|tlv| tlv.tag == OCTET_STRING)?
+            .content
+            .to_vec();
+
+        // SignerInfo ::= SEQUENCE { version, sid IssuerAndSerialNumber, ... }
+        let mut signer_children = signer_info.children();
+        let _version = signer_children.next()?;
+        let issuer_and_serial = signer_children.next()?;
+        let serial = issuer_and_serial.child(INTEGER)?;
+
+        let certificate = certificates
+            .into_iter()
+            .flat_map(|certificates| certificates.children())
+            .filter_map(|certificate| certificate.child(SEQUENCE))
+            .find(|tbs_certificate| tbs_certificate.child(INTEGER) == Some(serial))?;
+
+        // TBSCertificate ::= SEQUENCE { [0] version, serialNumber, signature, issuer, validity,
+        // subject, ... }
+        let mut names = certificate
+            .children()
+            .filter(|tlv| tlv.tag == SEQUENCE)
+            .skip(1);
+        let issuer = format_name(names.next()?);
+        let _validity = names.next()?;
+        let subject = format_name(names.next()?);
+
+        let timestamp = signer_children
+            .find(|tlv| tlv.tag == CONTEXT_1)
+            .and_then(Self::timestamp);
+
+        Some(Self {
+            subject,
+            issuer,
+            timestamp,
+            digest_algorithm,
+            digest,
+        })
+    }
+
+    /// Gets the signing time from either a PKCS #9 or an RFC 3161 countersignature in the
+    /// unsigned attributes
+    fn timestamp(unsigned_attributes: Tlv) -> Option<DateTime<Utc>> {
+        unsigned_attributes.children().find_map(|attribute| {
+            let mut parts = attribute.children();
+            let oid = parts.next()?.oid()?;
+            let value = parts.next()?.child(SEQUENCE)?;
+            match oid.as_str() {
+                COUNTER_SIGNATURE => {
+                    value
+                        .child(CONTEXT_0)?
+                        .children()
+                        .find_map(|signed_attribute| {
+                            let mut parts = signed_attribute.children();
+                            (parts.next()?.oid()? == SIGNING_TIME)
+                                .then(|| parts.next()?.children().next()?.time())?
+                        })
+                }
+                MS_COUNTER_SIGNATURE => {
+                    // ContentInfo -> SignedData -> encapContentInfo -> TSTInfo
+                    let tst_info = value
+                        .child(CONTEXT_0)?
+                        .child(SEQUENCE)?
+                        .children()
+                        .find(|tlv| tlv.tag == SEQUENCE)?
+                        .child(CONTEXT_0)?
+                        .child(OCTET_STRING)?;
+                    Tlv::read(tst_info.content)?
+                        .0
+                        .children()
+                        .find(|tlv| matches!(tlv.tag, GENERALIZED_TIME | UTC_TIME))?
+                        .time()
+                }
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Hashes the contents of every stream in the same way as `signtool`, recursively in the order of
+/// [`compare_names`] and followed by the CLSID of each storage.
+fn hash_storage<R: Read + Seek, D: Digest>(
+    compound_file: &mut CompoundFile<R>,
+) -> io::Result<Vec<u8>> {
+    let mut hasher = D::new();
+    hash_storage_into(compound_file, Path::new("/"), &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
+
+fn hash_storage_into<R: Read + Seek, D: Digest>(
+    compound_file: &mut CompoundFile<R>,
+    path: &Path,
+    hasher: &mut D,
+) -> io::Result<()> {
+    let is_root = path == Path::new("/");
+
+    let mut entries = compound_file
+        .read_storage(path)?
+        .map(|entry| {
+            (
+                entry.name().to_owned(),
+                entry.path().to_path_buf(),
+                entry.is_stream(),
+            )
+        })
+        .collect::<Vec<_>>();
+    entries.sort_by(|(a, ..), (b, ..)| compare_names(a, b));
+
+    let mut buffer = vec![0; 1 << 16];
+    for (name, path, is_stream) in entries {
+        if is_root && matches!(name.as_str(), DIGITAL_SIGNATURE | MSI_DIGITAL_SIGNATURE_EX) {
+            continue;
+        }
+        if is_stream {
+            let mut stream = compound_file.open_stream(&path)?;
+            loop {
+                let read = stream.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+            }
+        } else {
+            hash_storage_into(compound_file, &path, hasher)?;
+        }
+    }
+
+    hasher.update(compound_file.entry(path)?.clsid().to_bytes_le());
+
+    Ok(())
+}
+
+/// Compares directory entry names as their raw null-terminated UTF-16LE bytes, which is the order
+/// that Windows hashes MSI streams in.
+fn compare_names(a: &str, b: &str) -> Ordering {
+    fn utf16_le_bytes(name: &str) -> Vec<u8> {
+        name.encode_utf16()
+            .chain([0])
+            .flat_map(u16::to_le_bytes)
+            .collect()
+    }
+
+    let (a, b) = (utf16_le_bytes(a), utf16_le_bytes(b));
+    let length = a.len().min(b.len());
+    a[..length]
+        .cmp(&b[..length])
+        .then_with(|| a.len().cmp(&b.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cmp::Ordering,
+        io::{Cursor, Write},
+    };
+
+    use cfb::CompoundFile;
+    use chrono::{TimeZone, Utc};
+    use rstest::rstest;
+    use sha2::Sha256;
+
+    use super::{DIGITAL_SIGNATURE, compare_names, hash_storage, read_signer};
+    use crate::installers::authenticode::{
+        AuthenticodeError,
+        der::{
+            CONTEXT_0, CONTEXT_1, INTEGER, OBJECT_IDENTIFIER, OCTET_STRING, SEQUENCE, SET,
+            UTC_TIME, UTF8_STRING,
+        },
+    };
+
+    const SIGNED_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02];
+    const SPC_INDIRECT_DATA: &[u8] = &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x01, 0x04];
+    const SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+    const COUNTER_SIGNATURE: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x06];
+    const SIGNING_TIME: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x05];
+    const COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+    const ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0A];
+    const SERIAL: &[u8] = &[0x01, 0x23];
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut tlv = vec![tag];
+        match u8::try_from(content.len()) {
+            Ok(length) if length < 0x80 => tlv.push(length),
+            Ok(length) => tlv.extend([0x81, length]),
+            Err(_) => {
+                tlv.push(0x82);
+                tlv.extend(u16::try_from(content.len()).unwrap().to_be_bytes());
+            }
+        }
+        tlv.extend(content);
+        tlv
+    }
+
+    fn sequence(tag: u8, children: &[Vec<u8>]) -> Vec<u8> {
+        tlv(tag, &children.concat())
+    }
+
+    fn name(organization: &str, common_name: &str) -> Vec<u8> {
+        let attribute = |oid, value: &str| {
+            sequence(
+                SET,
+                &[sequence(
+                    SEQUENCE,
+                    &[
+                        tlv(OBJECT_IDENTIFIER, oid),
+                        tlv(UTF8_STRING, value.as_bytes()),
+                    ],
+                )],
+            )
+        };
+        sequence(
+            SEQUENCE,
+            &[
+                attribute(ORGANIZATION, organization),
+                attribute(COMMON_NAME, common_name),
+            ],
+        )
+    }
+
+    /// Builds a PKCS #7 `SignedData` with the digest of an MSI, a certificate for Contoso Ltd and a
+    /// countersignature with the signing time
+    fn signed_data(digest: &[u8]) -> Vec<u8> {
+        let algorithm = sequence(SEQUENCE, &[tlv(OBJECT_IDENTIFIER, SHA256)]);
+        let issuer = name("Fabrikam", "Fabrikam Code Signing CA");
+        let certificate = sequence(
+            SEQUENCE,
+            &[sequence(
+                SEQUENCE,
+                &[
+                    sequence(CONTEXT_0, &[tlv(INTEGER, &[2])]),
+                    tlv(INTEGER, SERIAL),
+                    algorithm.clone(),
+                    issuer.clone(),
+                    sequence(
+                        SEQUENCE,
+                        &[
+                            tlv(UTC_TIME, b"240101000000Z"),
+                            tlv(UTC_TIME, b"270101000000Z"),
+                        ],
+                    ),
+                    name("Contoso Ltd", "Contoso Ltd"),
+                ],
+            )],
+        );
+        let countersignature = sequence(
+            SEQUENCE,
+            &[
+                tlv(OBJECT_IDENTIFIER, COUNTER_SIGNATURE),
+                sequence(
+                    SET,
+                    &[sequence(
+                        SEQUENCE,
+                        &[
+                            tlv(INTEGER, &[1]),
+                            sequence(SEQUENCE, &[issuer.clone(), tlv(INTEGER, SERIAL)]),
+                            algorithm.clone(),
+                            sequence(
+                                CONTEXT_0,
+                                &[sequence(
+                                    SEQUENCE,
+                                    &[
+                                        tlv(OBJECT_IDENTIFIER, SIGNING_TIME),
+                                        sequence(SET, &[tlv(UTC_TIME, b"240315120000Z")]),
+                                    ],
+                                )],
+                            ),
+                        ],
+                    )],
+                ),
+            ],
+        );
+        let signer_info = sequence(
+            SEQUENCE,
+            &[
+                tlv(INTEGER, &[1]),
+                sequence(SEQUENCE, &[issuer, tlv(INTEGER, SERIAL)]),
+                algorithm.clone(),
+                sequence(CONTEXT_1, &[countersignature]),
+            ],
+        );
+        let indirect_data = sequence(
+            SEQUENCE,
+            &[
+                sequence(SEQUENCE, &[tlv(OBJECT_IDENTIFIER, SPC_INDIRECT_DATA)]),
+                sequence(SEQUENCE, &[algorithm.clone(), tlv(OCTET_STRING, digest)]),
+            ],
+        );
+        sequence(
+            SEQUENCE,
+            &[
+                tlv(OBJECT_IDENTIFIER, SIGNED_DATA),
+                sequence(
+                    CONTEXT_0,
+                    &[sequence(
+                        SEQUENCE,
+                        &[
+                            tlv(INTEGER, &[1]),
+                            sequence(SET, &[algorithm]),
+                            sequence(
+                                SEQUENCE,
+                                &[
+                                    tlv(OBJECT_IDENTIFIER, SPC_INDIRECT_DATA),
+                                    sequence(CONTEXT_0, &[indirect_data]),
+                                ],
+                            ),
+                            sequence(CONTEXT_0, &[certificate]),
+                            sequence(SET, &[signer_info]),
+                        ],
+                    )],
+                ),
+            ],
+        )
+    }
+
+    /// Creates an MSI with a single stream, signed with the given signature or with a valid
+    /// signature if none is given
+    fn msi(signature: Option<&[u8]>) -> Vec<u8> {
+        let mut compound_file = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        compound_file
+            .create_stream("/Property")
+            .unwrap()
+            .write_all(b"ProductName=Contoso App")
+            .unwrap();
+        let signature = signature.map_or_else(
+            || signed_data(&hash_storage::<_, Sha256>(&mut compound_file).unwrap()),
+            <[u8]>::to_vec,
+        );
+        compound_file
+            .create_stream(format!("/{DIGITAL_SIGNATURE}"))
+            .unwrap()
+            .write_all(&signature)
+            .unwrap();
+        compound_file.flush().unwrap();
+        compound_file.into_inner().into_inner()
+    }
+
+    #[test]
+    fn read_msi_signer() {
+        let signer = read_signer(Cursor::new(msi(None))).unwrap().unwrap();
+        assert_eq!(signer.subject, "/O=Contoso Ltd/CN=Contoso Ltd");
+        assert_eq!(signer.issuer, "/O=Fabrikam/CN=Fabrikam Code Signing CA");
+        assert_eq!(
+            signer.timestamp,
+            Some(Utc.with_ymd_and_hms(2024, 3, 15, 12, 0, 0).unwrap())
+        );
+        assert_eq!(signer.digest_valid, Some(true));
+    }
+
+    #[test]
+    fn modified_msi_has_invalid_digest() {
+        let mut compound_file = CompoundFile::open(Cursor::new(msi(None))).unwrap();
+        compound_file
+            .create_stream("/Property")
+            .unwrap()
+            .write_all(b"ProductName=Fabrikam App")
+            .unwrap();
+        compound_file.flush().unwrap();
+
+        let signer = read_signer(Cursor::new(compound_file.into_inner().into_inner()))
+            .unwrap()
+            .unwrap();
+        assert_eq!(signer.digest_valid, Some(false));
+    }
+
+    #[test]
+    fn unsigned_msi() {
+        let mut compound_file = CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+        compound_file.create_stream("/Property").unwrap();
+        compound_file.flush().unwrap();
+
+        assert_eq!(
+            read_signer(Cursor::new(compound_file.into_inner().into_inner())).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn malformed_signature() {
+        assert!(matches!(
+            read_signer(Cursor::new(msi(Some(&[0x30, 0x03, 0x02, 0x01, 0x01])))),
+            Err(AuthenticodeError::InvalidSignature)
+        ));
+    }
+
+    #[rstest]
+    #[case("A", "B", Ordering::Less)]
+    #[case("AB", "A", Ordering::Greater)]
+    #[case("A", "A", Ordering::Equal)]
+    // Comparison is on little-endian bytes so the low byte of each code unit is compared first
+    #[case("\u{0141}", "\u{0240}", Ordering::Greater)]
+    fn name_order(#[case] a: &str, #[case] b: &str, #[case] expected: Ordering) {
+        assert_eq!(compare_names(a, b), expected);
+    }
+}
@@ -1,8 +1,12 @@
+pub mod authenticode;
 pub mod burn;
 pub mod inno;
 pub mod msi;
 pub mod msix_family;
 pub mod nsis;
+pub mod portable;
 pub mod possible_installers;
+pub mod qt_ifw;
+pub mod switch_rules;
 pub mod utils;
 pub mod zip;
@@ -0,0 +1,301 @@
+use std::collections::BTreeSet;
+
+use camino::Utf8Path;
+use quick_xml::{Reader, events::Event};
+use tracing::debug;
+use winget_types::{
+    PackageIdentifier,
+    installer::{
+        Architecture, Command, ElevationRequirement, Installer, InstallerType, PackageDependencies,
+    },
+};
+use yara_x::mods::{
+    PE,
+    pe::{ResourceType::RESOURCE_TYPE_MANIFEST, Subsystem},
+};
+
+use crate::file_analyser::{ORIGINAL_FILENAME, version_info};
+
+/// The metadata of a portable that can be inferred from its PE headers and resources. It is read
+/// from every EXE, as an EXE can become a portable after it has been analysed, such as when the
+/// user says that it is one.
+#[derive(Clone, Debug, Default)]
+pub struct PortableMetadata {
+    elevation_requirement: Option<ElevationRequirement>,
+    command: Option<Command>,
+    vc_redist_dependencies: BTreeSet<PackageDependencies>,
+}
+
+impl PortableMetadata {
+    pub fn new(data: &[u8], pe: &PE, file_name: &str, architecture: Architecture) -> Self {
+        Self {
+            elevation_requirement: requested_execution_level(data, pe)
+                .as_deref()
+                .and_then(to_elevation_requirement),
+            // Only console applications are likely to be run from the command line
+            command: (pe.subsystem() == Subsystem::SUBSYSTEM_WINDOWS_CUI)
+                .then(|| command_alias(pe, file_name))
+                .flatten(),
+            vc_redist_dependencies: vc_redist_dependencies(pe, architecture),
+        }
+    }
+
+    /// Fills in the values of a portable installer that it does not already have
+    pub fn enrich(&self, installer: &mut Installer) {
+        if installer.r#type != Some(InstallerType::Portable) {
+            return;
+        }
+
+        installer.elevation_requirement = installer
+            .elevation_requirement
+            .or(self.elevation_requirement);
+        if installer.commands.is_empty() {
+            installer.commands = self.command.iter().cloned().collect();
+        }
+        if installer.dependencies.package.is_empty() {
+            installer
+                .dependencies
+                .package
+                .clone_from(&self.vc_redist_dependencies);
+        }
+    }
+}
+
+/// Reads the `level` of the `requestedExecutionLevel` element in the embedded application manifest
+fn requested_execution_level(data: &[u8], pe: &PE) -> Option<String> {
+    const REQUESTED_EXECUTION_LEVEL: &[u8] = b"requestedExecutionLevel";
+    const LEVEL: &[u8] = b"level";
+
+    let manifest = pe
+        .resources
+        .iter()
+        .find(|resource| resource.type_() == RESOURCE_TYPE_MANIFEST)
+        .and_then(|manifest| {
+            let offset = manifest.offset() as usize;
+            data.get(offset..offset + manifest.length() as usize)
+        })
+        .and_then(|manifest_bytes| str::from_utf8(manifest_bytes).ok())?;
+
+    let mut reader = Reader::from_str(manifest);
+    loop {
+        match reader.read_event().ok()? {
+            Event::Start(element) | Event::Empty(element)
+                if element.local_name().as_ref() == REQUESTED_EXECUTION_LEVEL =>
+            {
+                let level = element
+                    .attributes()
+                    .flatten()
+                    .find(|attribute| attribute.key.local_name().as_ref() == LEVEL)?
+                    .unescape_value()
+                    .ok()?
+                    .into_owned();
+                debug!(requested_execution_level = level);
+                return Some(level);
+            }
+            Event::Eof => return None,
+            _ => {}
+        }
+    }
+}
+
+fn to_elevation_requirement(level: &str) -> Option<ElevationRequirement> {
+    match level {
+        // Windows prompts for elevation when the executable is launched
+        "requireAdministrator" => Some(ElevationRequirement::ElevatesSelf),
+        _ => None,
+    }
+}
+
+/// Suggests a command alias from the original file name, falling back to the downloaded file name,
+/// which is more likely to include a version or architecture
+fn command_alias(pe: &PE, file_name: &str) -> Option<Command> {
+    version_info(pe, ORIGINAL_FILENAME)
+        .into_iter()
+        .chain([file_name])
+        .filter_map(|file_name| Utf8Path::new(file_name.trim()).file_stem())
+        .find_map(|stem| Command::new(stem.to_ascii_lowercase()).ok())
+}
+
+/// Maps imported Visual C++ runtime libraries to their redistributable packages in winget-pkgs
+fn vc_redist_dependencies(pe: &PE, architecture: Architecture) -> BTreeSet<PackageDependencies> {
+    let architecture = match architecture {
+        Architecture::X86 => "x86",
+        Architecture::X64 => "x64",
+        Architecture::Arm64 => "arm64",
+        _ => return BTreeSet::new(),
+    };
+
+    pe.import_details
+        .iter()
+        .filter_map(|import| import.library_name.as_deref())
+        .filter_map(|library| vc_redist_version(&library.to_ascii_lowercase()))
+        // Only the 2015+ redistributable is available for ARM64
+        .filter(|&version| architecture != "arm64" || version == VC_REDIST_2015)
+        .filter_map(|version| {
+            PackageIdentifier::new(format!("Microsoft.VCRedist.{version}.{architecture}")).ok()
+        })
+        .map(PackageDependencies::new)
+        .collect()
+}
+
+const VC_REDIST_2015: &str = "2015+";
+
+fn vc_redist_version(library: &str) -> Option<&'static str> {
+    let name = library.strip_suffix(".dll")?;
+    let runtime_version = name
+        .strip_prefix("vcruntime")
+        .or_else(|| name.strip_prefix("msvcp"))
+        .or_else(|| name.strip_prefix("msvcr"))
+        .or_else(|| name.strip_prefix("concrt"))
+        .or_else(|| name.strip_prefix("vccorlib"))?;
+
+    // Strips suffixes such as the _1 in vcruntime140_1.dll
+    let runtime_version = runtime_version
+        .split_once('_')
+        .map_or(runtime_version, |(version, _)| version);
+
+    match runtime_version {
+        "140" => Some(VC_REDIST_2015),
+        "120" => Some("2013"),
+        "110" => Some("2012"),
+        "100" => Some("2010"),
+        "90" => Some("2008"),
+        "80" => Some("2005"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use indoc::indoc;
+    use rstest::rstest;
+    use winget_types::installer::{Architecture, ElevationRequirement, Installer, InstallerType};
+    use yara_x::mods::{
+        PE,
+        pe::{Import, Resource, ResourceType::RESOURCE_TYPE_MANIFEST, Subsystem},
+    };
+
+    use super::{ORIGINAL_FILENAME, PortableMetadata, vc_redist_version};
+
+    #[rstest]
+    #[case("vcruntime140.dll", Some("2015+"))]
+    #[case("vcruntime140_1.dll", Some("2015+"))]
+    #[case("msvcp140_2.dll", Some("2015+"))]
+    #[case("msvcr120.dll", Some("2013"))]
+    #[case("msvcp100.dll", Some("2010"))]
+    #[case("vcruntime140d.dll", None)]
+    #[case("msvcrt.dll", None)]
+    #[case("kernel32.dll", None)]
+    fn vc_redist(#[case] library: &str, #[case] expected: Option<&str>) {
+        assert_eq!(vc_redist_version(library), expected);
+    }
+
+    #[test]
+    fn enrich_console_portable() {
+        const MANIFEST: &str = indoc! {r#"
+            <?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+            <assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">
+              <trustInfo xmlns="urn:schemas-microsoft-com:asm.v3">
+                <security>
+                  <requestedPrivileges>
+                    <requestedExecutionLevel level="requireAdministrator" uiAccess="false"/>
+                  </requestedPrivileges>
+                </security>
+              </trustInfo>
+            </assembly>
+        "#};
+
+        let mut pe = PE::default();
+        pe.set_subsystem(Subsystem::SUBSYSTEM_WINDOWS_CUI);
+        pe.version_info
+            .insert(ORIGINAL_FILENAME.to_owned(), "Tool.exe".to_owned());
+        pe.resources.push({
+            let mut resource = Resource::default();
+            resource.set_type(RESOURCE_TYPE_MANIFEST);
+            resource.set_offset(0);
+            resource.set_length(MANIFEST.len() as u32);
+            resource
+        });
+        pe.import_details
+            .extend(
+                ["KERNEL32.dll", "VCRUNTIME140.dll", "MSVCP140.dll"].map(|library| Import {
+                    library_name: Some(library.to_owned()),
+                    ..Import::default()
+                }),
+            );
+
+        let mut installer = Installer {
+            architecture: Architecture::X64,
+            r#type: Some(InstallerType::Portable),
+            ..Installer::default()
+        };
+        PortableMetadata::new(
+            MANIFEST.as_bytes(),
+            &pe,
+            "tool-1.2.3-win64.exe",
+            Architecture::X64,
+        )
+        .enrich(&mut installer);
+
+        assert_eq!(
+            installer.elevation_requirement,
+            Some(ElevationRequirement::ElevatesSelf)
+        );
+        assert_eq!(
+            installer
+                .commands
+                .iter()
+                .map(AsRef::as_ref)
+                .collect::<Vec<&str>>(),
+            ["tool"]
+        );
+        assert_eq!(
+            installer
+                .dependencies
+                .package
+                .iter()
+                .map(|dependency| dependency.package_identifier.as_str())
+                .collect::<Vec<_>>(),
+            ["Microsoft.VCRedist.2015+.x64"]
+        );
+    }
+
+    #[test]
+    fn gui_portable_has_no_command() {
+        let mut pe = PE::default();
+        pe.set_subsystem(Subsystem::SUBSYSTEM_WINDOWS_GUI);
+
+        let mut installer = Installer {
+            r#type: Some(InstallerType::Portable),
+            ..Installer::default()
+        };
+        PortableMetadata::new(&[], &pe, "app.exe", Architecture::X64).enrich(&mut installer);
+
+        assert!(installer.commands.is_empty());
+        assert_eq!(installer.elevation_requirement, None);
+    }
+
+    #[test]
+    fn keeps_existing_values() {
+        let mut pe = PE::default();
+        pe.set_subsystem(Subsystem::SUBSYSTEM_WINDOWS_CUI);
+
+        let mut installer = Installer {
+            r#type: Some(InstallerType::Portable),
+            commands: BTreeSet::from(["previous".parse().unwrap()]),
+            ..Installer::default()
+        };
+        PortableMetadata::new(&[], &pe, "tool.exe", Architecture::X64).enrich(&mut installer);
+
+        assert_eq!(
+            installer
+                .commands
+                .iter()
+                .map(AsRef::as_ref)
+                .collect::<Vec<&str>>(),
+            ["previous"]
+        );
+    }
+}
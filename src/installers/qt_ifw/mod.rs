@@ -0,0 +1,348 @@
+mod rcc;
+
+use camino::Utf8PathBuf;
+use compact_str::CompactString;
+use const_format::formatcp;
+use memchr::memmem;
+use quick_xml::de::from_str;
+use rcc::{RCC_MAGIC, ResourceCollection};
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::{debug, warn};
+use winget_types::{
+    Version,
+    installer::{
+        AppsAndFeaturesEntry, Architecture, InstallationMetadata, Installer, InstallerType, Scope,
+        switches::{InstallLocationSwitch, InstallerSwitches},
+    },
+};
+use yara_x::mods::PE;
+
+use super::utils::{
+    RELATIVE_LOCAL_APP_DATA, RELATIVE_PROGRAM_FILES_32, RELATIVE_PROGRAM_FILES_64,
+    RELATIVE_SYSTEM_DRIVE,
+};
+use crate::traits::FromMachine;
+
+/// The cookie at the end of the binary content that is appended to an IFW installer
+const MAGIC_COOKIE: u64 = 0xC263_0A1C_99D6_68F8;
+const MAGIC_INSTALLER_MARKER: u64 = 0x1202_3233;
+
+const CONFIG_XML: &str = "config.xml";
+const PACKAGE_XML: &str = "package.xml";
+const UPDATES_XML: &str = "Updates.xml";
+
+const DEFAULT_MAINTENANCE_TOOL_NAME: &str = "maintenancetool";
+const SILENT_SWITCH: &str = "--accept-licenses --default-answer --confirm-command install";
+
+#[derive(Error, Debug)]
+pub enum QtIfwError {
+    #[error("File is not a Qt Installer Framework installer")]
+    NotQtIfwFile,
+}
+
+/// The installer's `config/config.xml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct Config {
+    name: Option<String>,
+    version: Option<String>,
+    publisher: Option<String>,
+    target_dir: Option<String>,
+    admin_target_dir: Option<String>,
+    maintenance_tool_name: Option<String>,
+    #[serde(rename = "ProductUUID")]
+    product_uuid: Option<String>,
+}
+
+/// A component's `meta/package.xml`, or a `PackageUpdate` element of the generated `Updates.xml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct Package {
+    name: Option<String>,
+    display_name: Option<String>,
+    version: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+struct Updates {
+    application_name: Option<String>,
+    application_version: Option<String>,
+    package_update: Vec<Package>,
+}
+
+pub struct QtIfw {
+    pub installer: Installer,
+}
+
+impl QtIfw {
+    pub fn new(data: &[u8], pe: &PE) -> Result<Self, QtIfwError> {
+        let binary_content = Self::binary_content(data, pe).ok_or(QtIfwError::NotQtIfwFile)?;
+
+        let mut config = None;
+        let mut packages = Vec::new();
+        let mut updates = Updates::default();
+
+        // The metadata resources are binary Qt resource collections within the binary content.
+        // Rather than relying on the exact layout of the index, which has changed between
+        // versions of the framework, every resource collection is read.
+        for offset in memmem::find_iter(binary_content, RCC_MAGIC) {
+            let Some(collection) = ResourceCollection::new(&binary_content[offset..]) else {
+                continue;
+            };
+            for file in collection.files() {
+                let Ok(contents) = str::from_utf8(&file.data) else {
+                    continue;
+                };
+                debug!(file.path);
+                // A resource that cannot be parsed is skipped rather than failing the analysis, as
+                // the installer is still known to be an IFW installer from its cookie
+                let parsed = match file.path.rsplit('/').next() {
+                    Some(CONFIG_XML) if config.is_none() => {
+                        from_str(contents).map(|parsed| config = Some(parsed))
+                    }
+                    Some(PACKAGE_XML) => from_str(contents).map(|parsed| packages.push(parsed)),
+                    Some(UPDATES_XML) => from_str(contents).map(|parsed| updates = parsed),
+                    _ => Ok(()),
+                };
+                if let Err(error) = parsed {
+                    warn!(%error, "Failed to parse {}", file.path);
+                }
+            }
+        }
+
+        // The switches are still known even if the metadata could not be found
+        let config: Config = config.unwrap_or_default();
+        packages.extend(updates.package_update);
+        debug!(?config, ?packages);
+
+        let target_dir = config
+            .admin_target_dir
+            .as_deref()
+            .or(config.target_dir.as_deref())
+            .map(to_relative_install_dir);
+
+        let display_name = config.name.or(updates.application_name);
+        let display_version = config
+            .version
+            .or(updates.application_version)
+            .or_else(|| packages.iter().find_map(|package| package.version.clone()));
+
+        // winget-types does not expose the type of installed files, so the maintenance tool, which
+        // is also the uninstaller, can only be logged
+        debug!(
+            maintenance_tool = config
+                .maintenance_tool_name
+                .as_deref()
+                .unwrap_or(DEFAULT_MAINTENANCE_TOOL_NAME)
+        );
+
+        Ok(Self {
+            installer: Installer {
+                architecture: Architecture::from_machine(pe.machine()),
+                r#type: Some(InstallerType::Exe),
+                scope: target_dir.as_deref().and_then(|target_dir| {
+                    Scope::from_install_directory(target_dir).or_else(|| {
+                        target_dir
+                            .starts_with(RELATIVE_USER_PROFILE)
+                            .then_some(Scope::User)
+                    })
+                }),
+                switches: InstallerSwitches {
+                    silent: SILENT_SWITCH.parse().ok(),
+                    silent_with_progress: SILENT_SWITCH.parse().ok(),
+                    install_location:
+                        r#"--root "<INSTALLPATH>""#.parse::<InstallLocationSwitch>().ok(),
+                    ..InstallerSwitches::default()
+                },
+                product_code: config.product_uuid.clone(),
+                apps_and_features_entries: if display_name.is_some()
+                    || config.publisher.is_some()
+                    || display_version.is_some()
+                {
+                    vec![AppsAndFeaturesEntry {
+                        display_name: display_name.map(CompactString::from),
+                        publisher: config.publisher.map(CompactString::from),
+                        display_version: display_version.as_deref().map(Version::new),
+                        product_code: config.product_uuid,
+                        ..AppsAndFeaturesEntry::default()
+                    }]
+                } else {
+                    vec![]
+                },
+                installation_metadata: InstallationMetadata {
+                    default_install_location: target_dir.map(Utf8PathBuf::from),
+                    ..InstallationMetadata::default()
+                },
+                ..Installer::default()
+            },
+        })
+    }
+
+    /// Returns the binary content appended to the installer base, which ends with the size of the
+    /// binary content, a marker and the magic cookie. A signature may follow the cookie.
+    fn binary_content<'data>(data: &'data [u8], pe: &PE) -> Option<&'data [u8]> {
+        let overlay_offset = usize::try_from(pe.overlay.offset?).ok()?;
+        let overlay = data.get(overlay_offset..)?;
+
+        let cookie_offset = memmem::rfind(overlay, &MAGIC_COOKIE.to_le_bytes())? + overlay_offset;
+        let end_of_binary_content = cookie_offset + size_of::<u64>();
+
+        let read_u64 = |offset: usize| {
+            data.get(offset..offset + size_of::<u64>())
+                .and_then(|bytes| bytes.try_into().ok())
+                .map(u64::from_le_bytes)
+        };
+        let marker = read_u64(cookie_offset.checked_sub(size_of::<u64>())?)?;
+        if marker != MAGIC_INSTALLER_MARKER {
+            return None;
+        }
+        let binary_content_size =
+            usize::try_from(read_u64(cookie_offset.checked_sub(2 * size_of::<u64>())?)?).ok()?;
+
+        data.get(end_of_binary_content.checked_sub(binary_content_size)?..cookie_offset)
+    }
+}
+
+const RELATIVE_USER_PROFILE: &str = "%UserProfile%";
+
+/// Converts an IFW target directory such as `@ApplicationsDir@/Example` into a relative Windows
+/// directory such as `%ProgramFiles%\Example`
+fn to_relative_install_dir(target_dir: &str) -> String {
+    const DIRECTORIES: [(&str, &str); 6] = [
+        (
+            "@ApplicationsDirUser@",
+            formatcp!(r"{RELATIVE_LOCAL_APP_DATA}\Programs"),
+        ),
+        ("@ApplicationsDirX86@", RELATIVE_PROGRAM_FILES_32),
+        ("@ApplicationsDirX64@", RELATIVE_PROGRAM_FILES_64),
+        ("@ApplicationsDir@", RELATIVE_PROGRAM_FILES_64),
+        ("@HomeDir@", RELATIVE_USER_PROFILE),
+        ("@RootDir@", RELATIVE_SYSTEM_DRIVE),
+    ];
+
+    let mut install_dir = target_dir.replace('/', r"\");
+    for (ifw_directory, relative_directory) in DIRECTORIES {
+        if let Some(index) = install_dir.find(ifw_directory) {
+            install_dir.replace_range(index..index + ifw_directory.len(), relative_directory);
+            break;
+        }
+    }
+    install_dir
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rstest::rstest;
+    use winget_types::installer::Scope;
+    use yara_x::mods::PE;
+
+    use super::{
+        MAGIC_COOKIE, MAGIC_INSTALLER_MARKER, QtIfw, QtIfwError, rcc::tests::build_collection,
+        to_relative_install_dir,
+    };
+
+    const CONFIG: &str = indoc! {r#"
+        <?xml version="1.0" encoding="UTF-8"?>
+        <Installer>
+            <Name>Example App</Name>
+            <Version>1.2.3</Version>
+            <Title>Example App Installer</Title>
+            <Publisher>Example Corp</Publisher>
+            <TargetDir>@ApplicationsDirUser@/Example App</TargetDir>
+            <MaintenanceToolName>ExampleMaintenanceTool</MaintenanceToolName>
+        </Installer>
+    "#};
+
+    fn build_installer(marker: u64) -> (Vec<u8>, PE) {
+        build_installer_with(marker, &[("config.xml", CONFIG.as_bytes(), true)])
+    }
+
+    fn build_installer_with(marker: u64, files: &[(&str, &[u8], bool)]) -> (Vec<u8>, PE) {
+        let executable = b"MZ installer base".to_vec();
+        let collection = build_collection("installer-config", files);
+
+        let mut binary_content = collection;
+        // Operations and resource collection index, which aren't read
+        binary_content.extend([0; 32]);
+        let binary_content_size = binary_content.len() + 3 * size_of::<u64>();
+        binary_content.extend((binary_content_size as u64).to_le_bytes());
+        binary_content.extend(marker.to_le_bytes());
+        binary_content.extend(MAGIC_COOKIE.to_le_bytes());
+
+        let mut pe = PE::default();
+        pe.overlay.mut_or_insert_default().offset = Some(executable.len() as u64);
+
+        let mut data = executable;
+        data.extend(binary_content);
+        // Trailing Authenticode signature
+        data.extend([0xAB; 16]);
+        (data, pe)
+    }
+
+    #[test]
+    fn installer() {
+        let (data, pe) = build_installer(MAGIC_INSTALLER_MARKER);
+
+        let installer = QtIfw::new(&data, &pe).unwrap().installer;
+
+        assert_eq!(installer.scope, Some(Scope::User));
+        assert_eq!(
+            installer
+                .installation_metadata
+                .default_install_location
+                .as_deref()
+                .map(|path| path.as_str()),
+            Some(r"%LocalAppData%\Programs\Example App")
+        );
+        let entry = &installer.apps_and_features_entries[0];
+        assert_eq!(entry.display_name.as_deref(), Some("Example App"));
+        assert_eq!(entry.publisher.as_deref(), Some("Example Corp"));
+        assert_eq!(
+            entry.display_version.as_ref().map(ToString::to_string),
+            Some("1.2.3".to_owned())
+        );
+        assert!(installer.switches.silent.is_some());
+    }
+
+    #[test]
+    fn skips_unparsable_resources() {
+        let (data, pe) = build_installer_with(
+            MAGIC_INSTALLER_MARKER,
+            &[
+                ("package.xml", b"<Package><Name>".as_slice(), true),
+                ("config.xml", CONFIG.as_bytes(), true),
+            ],
+        );
+
+        let installer = QtIfw::new(&data, &pe).unwrap().installer;
+
+        assert_eq!(
+            installer.apps_and_features_entries[0]
+                .display_name
+                .as_deref(),
+            Some("Example App")
+        );
+    }
+
+    #[test]
+    fn not_an_installer() {
+        let (data, pe) = build_installer(MAGIC_INSTALLER_MARKER + 1);
+
+        assert!(matches!(
+            QtIfw::new(&data, &pe),
+            Err(QtIfwError::NotQtIfwFile)
+        ));
+    }
+
+    #[rstest]
+    #[case("@ApplicationsDir@/App", r"%ProgramFiles%\App")]
+    #[case("@ApplicationsDirX86@/App", r"%ProgramFiles(x86)%\App")]
+    #[case("@HomeDir@/App", r"%UserProfile%\App")]
+    #[case("C:/App", r"C:\App")]
+    fn relative_install_dir(#[case] target_dir: &str, #[case] expected: &str) {
+        assert_eq!(to_relative_install_dir(target_dir), expected);
+    }
+}
@@ -0,0 +1,228 @@
+use std::{borrow::Cow, io::Read};
+
+use flate2::read::ZlibDecoder;
+use tracing::debug;
+
+pub const RCC_MAGIC: &[u8; 4] = b"qres";
+
+const COMPRESSED: u16 = 1 << 0;
+const DIRECTORY: u16 = 1 << 1;
+const COMPRESSED_ZSTD: u16 = 1 << 2;
+
+/// Reads files from a binary Qt resource collection, as created by `rcc --binary`.
+///
+/// All integers are big-endian and all offsets are relative to the start of the collection.
+pub struct ResourceCollection<'data> {
+    data: &'data [u8],
+    version: u32,
+    tree_offset: usize,
+    data_offset: usize,
+    names_offset: usize,
+}
+
+pub struct ResourceFile<'data> {
+    pub path: String,
+    pub data: Cow<'data, [u8]>,
+}
+
+impl<'data> ResourceCollection<'data> {
+    pub fn new(data: &'data [u8]) -> Option<Self> {
+        if !data.starts_with(RCC_MAGIC) {
+            return None;
+        }
+
+        let collection = Self {
+            data,
+            version: read_u32(data, 4)?,
+            tree_offset: read_u32(data, 8)? as usize,
+            data_offset: read_u32(data, 12)? as usize,
+            names_offset: read_u32(data, 16)? as usize,
+        };
+
+        (1..=3).contains(&collection.version).then_some(collection)
+    }
+
+    const fn node_size(&self) -> usize {
+        // Version 2 added the last modified time to each node
+        if self.version >= 2 { 22 } else { 14 }
+    }
+
+    fn name(&self, node: usize) -> Option<String> {
+        let node_offset = self.tree_offset + node * self.node_size();
+        let name_offset = self.names_offset + read_u32(self.data, node_offset)? as usize;
+        let length = usize::from(read_u16(self.data, name_offset)?);
+        // Skip the length and the name's hash
+        let start = name_offset + size_of::<u16>() + size_of::<u32>();
+        let name = self.data.get(start..start + length * size_of::<u16>())?;
+        Some(String::from_utf16_lossy(
+            &name
+                .chunks_exact(2)
+                .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    /// Returns every file in the collection with its path from the root
+    pub fn files(&self) -> Vec<ResourceFile<'data>> {
+        let mut files = Vec::new();
+        self.collect_files(0, "", &mut files, 0);
+        files
+    }
+
+    fn collect_files(
+        &self,
+        node: usize,
+        parent: &str,
+        files: &mut Vec<ResourceFile<'data>>,
+        depth: usize,
+    ) -> Option<()> {
+        const MAX_DEPTH: usize = 32;
+
+        let node_offset = self.tree_offset + node * self.node_size();
+        let flags = read_u16(self.data, node_offset + 4)?;
+        let path = if node == 0 {
+            String::new()
+        } else {
+            format!("{parent}/{}", self.name(node)?)
+        };
+
+        if flags & DIRECTORY != 0 {
+            if depth >= MAX_DEPTH {
+                return None;
+            }
+            let child_count = read_u32(self.data, node_offset + 6)? as usize;
+            let first_child = read_u32(self.data, node_offset + 10)? as usize;
+            for child in first_child..first_child.saturating_add(child_count) {
+                self.collect_files(child, &path, files, depth + 1);
+            }
+        } else {
+            let offset = self.data_offset + read_u32(self.data, node_offset + 10)? as usize;
+            let length = read_u32(self.data, offset)? as usize;
+            let start = offset + size_of::<u32>();
+            let data = self.data.get(start..start + length)?;
+            let data = if flags & COMPRESSED_ZSTD != 0 {
+                debug!(path, "Skipping zstd compressed Qt resource");
+                return None;
+            } else if flags & COMPRESSED != 0 {
+                // qCompress prefixes the zlib stream with the uncompressed length
+                let mut decompressed = Vec::new();
+                ZlibDecoder::new(data.get(size_of::<u32>()..)?)
+                    .read_to_end(&mut decompressed)
+                    .ok()?;
+                Cow::Owned(decompressed)
+            } else {
+                Cow::Borrowed(data)
+            };
+            files.push(ResourceFile { path, data });
+        }
+
+        Some(())
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + size_of::<u16>())
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u16::from_be_bytes)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + size_of::<u32>())
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_be_bytes)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::Write;
+
+    use flate2::{Compression, write::ZlibEncoder};
+
+    use super::{COMPRESSED, DIRECTORY, RCC_MAGIC, ResourceCollection};
+
+    /// Builds a version 1 resource collection with the given files in a single directory
+    pub fn build_collection(directory: &str, files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut names = Vec::new();
+        let mut add_name = |name: &str| {
+            let offset = names.len() as u32;
+            let utf16 = name.encode_utf16().collect::<Vec<_>>();
+            names.extend((utf16.len() as u16).to_be_bytes());
+            names.extend(0u32.to_be_bytes());
+            names.extend(utf16.iter().flat_map(|unit| unit.to_be_bytes()));
+            offset
+        };
+
+        let mut payloads = Vec::new();
+        let mut tree = Vec::new();
+
+        // Root directory containing one directory
+        tree.extend(0u32.to_be_bytes());
+        tree.extend(DIRECTORY.to_be_bytes());
+        tree.extend(1u32.to_be_bytes());
+        tree.extend(1u32.to_be_bytes());
+
+        tree.extend(add_name(directory).to_be_bytes());
+        tree.extend(DIRECTORY.to_be_bytes());
+        tree.extend((files.len() as u32).to_be_bytes());
+        tree.extend(2u32.to_be_bytes());
+
+        for &(name, contents, compress) in files {
+            let payload = if compress {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(contents).unwrap();
+                let mut payload = (contents.len() as u32).to_be_bytes().to_vec();
+                payload.extend(encoder.finish().unwrap());
+                payload
+            } else {
+                contents.to_vec()
+            };
+
+            tree.extend(add_name(name).to_be_bytes());
+            tree.extend(if compress { COMPRESSED } else { 0 }.to_be_bytes());
+            tree.extend(0u32.to_be_bytes());
+            tree.extend((payloads.len() as u32).to_be_bytes());
+
+            payloads.extend((payload.len() as u32).to_be_bytes());
+            payloads.extend(payload);
+        }
+
+        const HEADER_SIZE: u32 = 20;
+        let tree_offset = HEADER_SIZE;
+        let data_offset = tree_offset + tree.len() as u32;
+        let names_offset = data_offset + payloads.len() as u32;
+
+        let mut collection = RCC_MAGIC.to_vec();
+        collection.extend(1u32.to_be_bytes());
+        collection.extend(tree_offset.to_be_bytes());
+        collection.extend(data_offset.to_be_bytes());
+        collection.extend(names_offset.to_be_bytes());
+        collection.extend(tree);
+        collection.extend(payloads);
+        collection.extend(names);
+        collection
+    }
+
+    #[test]
+    fn read_files() {
+        let collection = build_collection(
+            "installer-config",
+            &[
+                ("config.xml", b"<Installer/>", false),
+                ("license.txt", b"License text", true),
+            ],
+        );
+
+        let files = ResourceCollection::new(&collection).unwrap().files();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, "/installer-config/config.xml");
+        assert_eq!(&*files[0].data, b"<Installer/>");
+        assert_eq!(files[1].path, "/installer-config/license.txt");
+        assert_eq!(&*files[1].data, b"License text");
+    }
+
+    #[test]
+    fn invalid_magic() {
+        assert!(ResourceCollection::new(b"qrez\0\0\0\x01").is_none());
+    }
+}
@@ -0,0 +1,238 @@
+use std::{env, fs, io, sync::LazyLock};
+
+use indexmap::IndexMap;
+use memchr::memmem;
+use serde::Deserialize;
+use thiserror::Error;
+use tracing::{debug, warn};
+use winget_types::installer::{
+    InstallerType,
+    switches::{InstallerSwitches, SilentSwitch, SilentWithProgressSwitch},
+};
+use yara_x::mods::{PE, pe::ResourceType::RESOURCE_TYPE_MANIFEST};
+
+const BUILT_IN_RULES: &str = include_str!("rules.yaml");
+const KOMAC_SWITCH_RULES: &str = "KOMAC_SWITCH_RULES";
+
+static SWITCH_RULES: LazyLock<SwitchRules> = LazyLock::new(|| {
+    let mut rules = env::var(KOMAC_SWITCH_RULES)
+        .ok()
+        .and_then(|path| {
+            SwitchRules::from_file(&path)
+                .inspect_err(|error| warn!("{error}"))
+                .ok()
+        })
+        .unwrap_or_default();
+    rules
+        .0
+        .extend(SwitchRules::from_yaml(BUILT_IN_RULES).unwrap().0);
+    rules
+});
+
+#[derive(Debug, Error)]
+pub enum SwitchRuleError {
+    #[error("Failed to read switch rules from {path}: {source}")]
+    Read { path: String, source: io::Error },
+    #[error("Failed to parse switch rules: {0}")]
+    Parse(#[from] serde_yaml::Error),
+}
+
+/// A rule that identifies an EXE installer framework and the switches it accepts
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SwitchRule {
+    pub name: String,
+    #[serde(default)]
+    strings: Vec<String>,
+    #[serde(default)]
+    version_info: IndexMap<String, String>,
+    #[serde(default)]
+    manifest: Vec<String>,
+    #[serde(default)]
+    resources: Vec<String>,
+    #[serde(rename = "type", default = "default_installer_type")]
+    pub r#type: InstallerType,
+    pub silent: Option<SilentSwitch>,
+    pub silent_with_progress: Option<SilentWithProgressSwitch>,
+}
+
+const fn default_installer_type() -> InstallerType {
+    InstallerType::Exe
+}
+
+impl SwitchRule {
+    fn matches(&self, data: &[u8], pe: &PE, manifest: Option<&str>) -> bool {
+        if self.strings.is_empty()
+            && self.version_info.is_empty()
+            && self.manifest.is_empty()
+            && self.resources.is_empty()
+        {
+            return false;
+        }
+
+        (self.strings.is_empty() || self.strings.iter().any(|string| contains(data, string)))
+            && (self.version_info.is_empty()
+                || self.version_info.iter().any(|(key, value)| {
+                    pe.version_info.get(key).is_some_and(|version_info_value| {
+                        version_info_value
+                            .to_lowercase()
+                            .contains(&value.to_lowercase())
+                    })
+                }))
+            && (self.manifest.is_empty()
+                || manifest.is_some_and(|manifest| {
+                    self.manifest.iter().any(|text| manifest.contains(text))
+                }))
+            && (self.resources.is_empty()
+                || pe
+                    .resources
+                    .iter()
+                    .filter_map(|resource| resource.name_string.as_deref())
+                    .map(utf16_le_to_string)
+                    .any(|name| {
+                        self.resources
+                            .iter()
+                            .any(|resource| resource.eq_ignore_ascii_case(&name))
+                    }))
+    }
+
+    pub fn switches(&self) -> InstallerSwitches {
+        InstallerSwitches {
+            silent: self.silent.clone(),
+            silent_with_progress: self.silent_with_progress.clone(),
+            ..InstallerSwitches::default()
+        }
+    }
+}
+
+/// An ordered set of [`SwitchRule`]s where the first matching rule wins
+#[derive(Debug, Default)]
+pub struct SwitchRules(Vec<SwitchRule>);
+
+impl SwitchRules {
+    pub fn from_file(path: &str) -> Result<Self, SwitchRuleError> {
+        let contents = fs::read_to_string(path).map_err(|source| SwitchRuleError::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+        Self::from_yaml(&contents)
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<Self, SwitchRuleError> {
+        serde_yaml::from_str(yaml).map(Self).map_err(Into::into)
+    }
+
+    /// Finds the first rule that matches a PE, using the built-in rules and any rules from the
+    /// file at `KOMAC_SWITCH_RULES`
+    pub fn find(data: &[u8], pe: &PE) -> Option<&'static SwitchRule> {
+        SWITCH_RULES.find_match(data, pe)
+    }
+
+    fn find_match(&self, data: &[u8], pe: &PE) -> Option<&SwitchRule> {
+        let manifest = pe
+            .resources
+            .iter()
+            .find(|resource| resource.type_() == RESOURCE_TYPE_MANIFEST)
+            .and_then(|manifest| {
+                let offset = manifest.offset() as usize;
+                data.get(offset..offset + manifest.length() as usize)
+            })
+            .and_then(|manifest_bytes| str::from_utf8(manifest_bytes).ok());
+
+        self.0
+            .iter()
+            .find(|rule| rule.matches(data, pe, manifest))
+            .inspect(|rule| debug!(switch_rule = rule.name))
+    }
+}
+
+/// Checks whether the data contains a string as either ASCII or UTF-16LE
+fn contains(data: &[u8], string: &str) -> bool {
+    let utf16_le = string
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect::<Vec<_>>();
+    memmem::find(data, string.as_bytes()).is_some() || memmem::find(data, &utf16_le).is_some()
+}
+
+fn utf16_le_to_string(bytes: &[u8]) -> String {
+    String::from_utf16_lossy(
+        &bytes
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use winget_types::installer::InstallerType;
+    use yara_x::mods::PE;
+
+    use super::{BUILT_IN_RULES, SwitchRules, contains};
+
+    #[test]
+    fn built_in_rules_parse() {
+        let rules = SwitchRules::from_yaml(BUILT_IN_RULES).unwrap();
+        assert!(
+            rules
+                .0
+                .iter()
+                .all(|rule| rule.silent.is_some() && rule.silent_with_progress.is_some())
+        );
+    }
+
+    #[test]
+    fn contains_ascii_and_utf16() {
+        assert!(contains(b"xxSetup Factoryxx", "Setup Factory"));
+        assert!(contains(b"\0W\0i\0s\0e\0", "Wise"));
+        assert!(!contains(b"Wis e", "Wise"));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        const RULES: &str = indoc! {"
+            - name: Custom
+              type: inno
+              strings: [Custom Setup]
+              silent: /VERYSILENT
+            - name: Generic
+              strings: [Setup]
+              silent: /S
+            - name: Empty
+              silent: /quiet
+        "};
+
+        let rules = SwitchRules::from_yaml(RULES).unwrap();
+        let pe = PE::default();
+
+        let custom = rules.find_match(b"My Custom Setup", &pe).unwrap();
+        assert_eq!(custom.name, "Custom");
+        assert_eq!(custom.r#type, InstallerType::Inno);
+        assert_eq!(custom.switches().silent.unwrap().to_string(), "/VERYSILENT");
+
+        let generic = rules.find_match(b"Setup", &pe).unwrap();
+        assert_eq!(generic.r#type, InstallerType::Exe);
+
+        assert!(rules.find_match(b"Nothing", &pe).is_none());
+    }
+
+    #[test]
+    fn version_info_condition() {
+        const RULES: &str = indoc! {"
+            - name: Vendor
+              version-info:
+                CompanyName: example corp
+              silent: /S
+        "};
+
+        let rules = SwitchRules::from_yaml(RULES).unwrap();
+        let mut pe = PE::default();
+        assert!(rules.find_match(&[], &pe).is_none());
+
+        pe.version_info
+            .insert("CompanyName".to_owned(), "Example Corp Ltd".to_owned());
+        assert!(rules.find_match(&[], &pe).is_some());
+    }
+}
@@ -132,41 +132,70 @@ impl<R: Read + Seek> Zip<R> {
             .with_validator(min_length!(1))
             .prompt()
             .map_err(handle_inquire_error)?;
-            let first_choice = chosen.first().unwrap();
-            let mut temp_file = tempfile::tempfile()?;
-            io::copy(
-                &mut self.archive.by_name(first_choice.as_str())?,
-                &mut temp_file,
+            self.select(
+                chosen
+                    .into_iter()
+                    .map(|relative_file_path| NestedInstallerFiles {
+                        relative_file_path,
+                        portable_command_alias: None,
+                    })
+                    .collect::<Vec<_>>(),
             )?;
-            let map = unsafe { Mmap::map(&temp_file) }?;
-            let file_analyser = FileAnalyser::new(&map, first_choice.file_name().unwrap())?;
-            let nested_installer_files = chosen
-                .into_iter()
-                .map(|path| {
-                    Ok(NestedInstallerFiles {
-                        portable_command_alias: if file_analyser.installers[0].r#type
-                            == Some(InstallerType::Portable)
-                        {
-                            Some(required_prompt(None)?)
-                        } else {
-                            None
-                        },
-                        relative_file_path: path,
+            if self.installers.first().is_some_and(|installer| {
+                installer.nested_installer_type == InstallerType::Portable.try_into().ok()
+            }) {
+                let nested_installer_files = self.installers[0]
+                    .nested_installer_files
+                    .iter()
+                    .map(|nested_installer_file| {
+                        Ok(NestedInstallerFiles {
+                            portable_command_alias: Some(required_prompt(None)?),
+                            ..nested_installer_file.clone()
+                        })
                     })
-                })
-                .collect::<Result<BTreeSet<_>>>()?;
-            self.installers = file_analyser
-                .installers
-                .into_iter()
-                .map(|installer| Installer {
-                    nested_installer_type: installer
-                        .r#type
-                        .and_then(|installer_type| installer_type.try_into().ok()),
-                    nested_installer_files: nested_installer_files.clone(),
-                    ..installer
-                })
-                .collect();
+                    .collect::<Result<BTreeSet<_>>>()?;
+                for installer in &mut self.installers {
+                    installer.nested_installer_files = nested_installer_files.clone();
+                }
+            }
         }
         Ok(())
     }
+
+    /// Uses the given nested files for the installers, analysing the first of them to determine
+    /// the nested installer type
+    pub fn select(&mut self, nested_installer_files: Vec<NestedInstallerFiles>) -> Result<()> {
+        let Some(first_choice) = nested_installer_files.first() else {
+            return Ok(());
+        };
+        self.possible_installer_files.clear();
+        let mut temp_file = tempfile::tempfile()?;
+        io::copy(
+            &mut self
+                .archive
+                .by_name(first_choice.relative_file_path.as_str())?,
+            &mut temp_file,
+        )?;
+        let map = unsafe { Mmap::map(&temp_file) }?;
+        let file_analyser = FileAnalyser::new(
+            &map,
+            first_choice
+                .relative_file_path
+                .file_name()
+                .unwrap_or(first_choice.relative_file_path.as_str()),
+        )?;
+        let nested_installer_files = nested_installer_files.into_iter().collect::<BTreeSet<_>>();
+        self.installers = file_analyser
+            .installers
+            .into_iter()
+            .map(|installer| Installer {
+                nested_installer_type: installer
+                    .r#type
+                    .and_then(|installer_type| installer_type.try_into().ok()),
+                nested_installer_files: nested_installer_files.clone(),
+                ..installer
+            })
+            .collect();
+        Ok(())
+    }
 }
@@ -2,26 +2,37 @@ extern crate core;
 
 use clap::{Parser, Subcommand, crate_name};
 use color_eyre::eyre::Result;
-use tracing::{Level, metadata::LevelFilter};
+use tracing::{Level, metadata::LevelFilter, warn};
 use tracing_indicatif::IndicatifLayer;
 use tracing_subscriber::{filter, layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::commands::{
-    analyse::Analyse,
-    cleanup::Cleanup,
-    complete::Complete,
-    list_versions::ListVersions,
-    new_version::NewVersion,
-    remove_dead_versions::RemoveDeadVersions,
-    remove_version::RemoveVersion,
-    show_version::ShowVersion,
-    submit::Submit,
-    sync_fork::SyncFork,
-    token::commands::{TokenArgs, TokenCommands},
-    update_version::UpdateVersion,
+use crate::{
+    commands::{
+        analyse::Analyse,
+        cleanup::Cleanup,
+        complete::Complete,
+        config::commands::{ConfigArgs, ConfigCommands},
+        diff::Diff,
+        fmt::Fmt,
+        list_versions::ListVersions,
+        migrate::Migrate,
+        new_version::NewVersion,
+        pull_requests::PullRequests,
+        rehash::Rehash,
+        remove_dead_versions::RemoveDeadVersions,
+        remove_version::RemoveVersion,
+        scan::Scan,
+        show_version::ShowVersion,
+        submit::Submit,
+        sync_fork::SyncFork,
+        token::commands::{TokenArgs, TokenCommands},
+        update_version::UpdateVersion,
+    },
+    config::{Config, ConfigError},
 };
 
 mod commands;
+mod config;
 mod credential;
 mod download;
 mod download_file;
@@ -31,13 +42,16 @@ mod github;
 mod installers;
 mod manifests;
 mod match_installers;
+#[cfg(test)]
+mod mock_server;
 mod prompts;
 mod terminal;
 mod traits;
+mod translation;
 mod update_state;
+mod url_check;
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
     color_eyre::config::HookBuilder::default()
         .display_env_section(false)
         .install()?;
@@ -59,7 +73,31 @@ async fn main() -> Result<()> {
         )
         .init();
 
-    match Cli::parse().command {
+    // The selected profile provides defaults for arguments, so it is applied before parsing them
+    let config = Config::load().and_then(|config| {
+        config
+            .apply(config::profile_arg().as_deref())
+            .map(|()| config)
+    });
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(Cli::parse(), config))
+}
+
+async fn run(cli: Cli, config: Result<Config, ConfigError>) -> Result<()> {
+    // An invalid config only produces a warning, so that it can still be fixed with `komac config`,
+    // which reports the error itself. Profiles are created by `komac config set`, so they only have
+    // to exist for other commands.
+    if !matches!(cli.command, Commands::Config(_)) {
+        match config {
+            Ok(config) => config.check_selected(cli.profile.as_deref())?,
+            Err(error) => warn!("{error}. Continuing without the config file's settings"),
+        }
+    }
+
+    match cli.command {
         Commands::New(new_version) => new_version.run().await,
         Commands::Update(update_version) => update_version.run().await,
         Commands::Cleanup(cleanup) => cleanup.run().await,
@@ -75,6 +113,22 @@ async fn main() -> Result<()> {
         Commands::Analyse(analyse) => analyse.run(),
         Commands::RemoveDeadVersions(remove_dead_versions) => remove_dead_versions.run().await,
         Commands::Submit(submit) => submit.run().await,
+        Commands::Diff(diff) => diff.run().await,
+        Commands::Prs(pull_requests) => pull_requests.run().await,
+        Commands::Scan(scan) => scan.run().await,
+        Commands::Rehash(rehash) => rehash.run().await,
+        Commands::Fmt(fmt) => fmt.run(),
+        Commands::Migrate(migrate) => migrate.run().await,
+        Commands::Config(config_args) => {
+            let profile = cli.profile.as_deref();
+            match config_args.command {
+                ConfigCommands::Get(get_config) => get_config.run(profile),
+                ConfigCommands::Set(set_config) => set_config.run(profile),
+                ConfigCommands::Unset(unset_config) => unset_config.run(profile),
+                ConfigCommands::List(list_config) => list_config.run(profile),
+                ConfigCommands::Use(use_profile) => use_profile.run(),
+            }
+        }
     }
 }
 
@@ -83,6 +137,9 @@ async fn main() -> Result<()> {
 struct Cli {
     #[arg(short = 'v', short_alias = 'V', long, action = clap::builder::ArgAction::Version)]
     version: (),
+    /// The config profile to use for default settings
+    #[arg(long, global = true, env = "KOMAC_PROFILE")]
+    profile: Option<String>,
     #[command(subcommand)]
     command: Commands,
 }
@@ -101,4 +158,11 @@ enum Commands {
     Analyse(Analyse),
     RemoveDeadVersions(RemoveDeadVersions),
     Submit(Submit),
+    Diff(Diff),
+    Prs(PullRequests),
+    Scan(Scan),
+    Rehash(Rehash),
+    Fmt(Fmt),
+    Migrate(Migrate),
+    Config(ConfigArgs),
 }
@@ -0,0 +1,542 @@
+use std::{
+    collections::BTreeSet,
+    fmt::{Display, Formatter},
+};
+
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::manifests::Manifests;
+
+const INSTALLERS: &str = "Installers";
+const ARCHITECTURE: &str = "Architecture";
+const INSTALLER_TYPE: &str = "InstallerType";
+const NESTED_INSTALLER_TYPE: &str = "NestedInstallerType";
+const SCOPE: &str = "Scope";
+const INSTALLER_LOCALE: &str = "InstallerLocale";
+const INSTALLER_URL: &str = "InstallerUrl";
+const UPGRADE_CODE: &str = "UpgradeCode";
+const PACKAGE_FAMILY_NAME: &str = "PackageFamilyName";
+const PUBLISHER: &str = "Publisher";
+const PACKAGE_LOCALE: &str = "PackageLocale";
+
+/// Keys that are expected to differ between every version or that only describe the manifest itself
+const IGNORED_KEYS: [&str; 4] = [
+    "PackageVersion",
+    "ManifestType",
+    "ManifestVersion",
+    "$schema",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum ChangeKind {
+    Added(Value),
+    Removed(Value),
+    Modified { old: Value, new: Value },
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Change {
+    pub path: String,
+    pub kind: ChangeKind,
+    /// Why the change may be unintended or malicious and needs a closer look
+    pub warning: Option<&'static str>,
+}
+
+/// The serialized manifests of a package version, which can be kept while the manifests are
+/// updated in place
+pub struct ManifestSnapshot {
+    installers: Vec<Value>,
+    default_locale: Value,
+    locales: Vec<Value>,
+}
+
+impl ManifestSnapshot {
+    pub fn new(manifests: &Manifests) -> Self {
+        Self {
+            installers: effective_installers(to_value(&manifests.installer)),
+            default_locale: to_value(&manifests.default_locale),
+            locales: manifests.locales.iter().map(to_value).collect(),
+        }
+    }
+}
+
+/// A field by field comparison of two versions of a package
+#[derive(Debug, Default)]
+pub struct ManifestDiff {
+    pub changes: Vec<Change>,
+}
+
+impl ManifestDiff {
+    pub fn new(old: &ManifestSnapshot, new: &ManifestSnapshot) -> Self {
+        let mut diff = Self::default();
+        diff.installers(old.installers.clone(), new.installers.clone());
+
+        let locale = new
+            .default_locale
+            .get(PACKAGE_LOCALE)
+            .map(format_value)
+            .unwrap_or_default();
+        diff.compare(
+            &format!("DefaultLocale ({locale})"),
+            &old.default_locale,
+            &new.default_locale,
+        );
+
+        diff.keyed("Locale", &old.locales, &new.locales, |locale| {
+            key_string(locale, &[PACKAGE_LOCALE])
+        });
+
+        diff
+    }
+
+    fn installers(&mut self, old_installers: Vec<Value>, new_installers: Vec<Value>) {
+        let new_architectures = new_installers
+            .iter()
+            .filter_map(|installer| installer.get(ARCHITECTURE))
+            .map(format_value)
+            .collect::<BTreeSet<_>>();
+
+        // Installers are matched on progressively fewer keys so that a change in scope or type is
+        // shown as a change rather than as a removal and an addition
+        let mut unmatched_old = old_installers;
+        let mut unmatched_new = new_installers;
+        for keys in [
+            &[ARCHITECTURE, INSTALLER_TYPE, SCOPE, INSTALLER_LOCALE][..],
+            &[ARCHITECTURE, INSTALLER_TYPE, INSTALLER_LOCALE],
+            &[ARCHITECTURE, INSTALLER_TYPE],
+            &[ARCHITECTURE],
+        ] {
+            let mut remaining_old = Vec::new();
+            for old_installer in unmatched_old {
+                let key = key_string(&old_installer, keys);
+                match unmatched_new
+                    .iter()
+                    .position(|new_installer| key_string(new_installer, keys) == key)
+                {
+                    Some(index) => {
+                        let new_installer = unmatched_new.remove(index);
+                        self.compare(
+                            &format!("{INSTALLERS}[{}]", installer_name(&new_installer)),
+                            &old_installer,
+                            &new_installer,
+                        );
+                    }
+                    None => remaining_old.push(old_installer),
+                }
+            }
+            unmatched_old = remaining_old;
+        }
+
+        for old_installer in unmatched_old {
+            let lost_architecture = old_installer.get(ARCHITECTURE).is_some_and(|architecture| {
+                !new_architectures.contains(&format_value(architecture))
+            });
+            self.changes.push(Change {
+                path: format!("{INSTALLERS}[{}]", installer_name(&old_installer)),
+                kind: ChangeKind::Removed(old_installer),
+                warning: lost_architecture.then_some("architecture is no longer supported"),
+            });
+        }
+
+        for new_installer in unmatched_new {
+            self.changes.push(Change {
+                path: format!("{INSTALLERS}[{}]", installer_name(&new_installer)),
+                kind: ChangeKind::Added(new_installer),
+                warning: None,
+            });
+        }
+    }
+
+    /// Matches arrays of objects by a key and compares the matching pairs
+    fn keyed(&mut self, name: &str, old: &[Value], new: &[Value], key: impl Fn(&Value) -> String) {
+        for old_value in old {
+            let old_key = key(old_value);
+            let path = format!("{name}[{old_key}]");
+            match new.iter().find(|new_value| key(new_value) == old_key) {
+                Some(new_value) => self.compare(&path, old_value, new_value),
+                None => self.changes.push(Change {
+                    path,
+                    kind: ChangeKind::Removed(old_value.clone()),
+                    warning: None,
+                }),
+            }
+        }
+        for new_value in new {
+            let new_key = key(new_value);
+            if !old.iter().any(|old_value| key(old_value) == new_key) {
+                self.changes.push(Change {
+                    path: format!("{name}[{new_key}]"),
+                    kind: ChangeKind::Added(new_value.clone()),
+                    warning: None,
+                });
+            }
+        }
+    }
+
+    fn compare(&mut self, path: &str, old: &Value, new: &Value) {
+        if old == new {
+            return;
+        }
+
+        match (old, new) {
+            (Value::Object(old_map), Value::Object(new_map)) => {
+                let keys = old_map
+                    .keys()
+                    .chain(new_map.keys())
+                    .filter(|key| !IGNORED_KEYS.contains(&key.as_str()))
+                    .collect::<BTreeSet<_>>();
+                for key in keys {
+                    let child_path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    match (old_map.get(key), new_map.get(key)) {
+                        (Some(old_value), Some(new_value)) => {
+                            self.compare(&child_path, old_value, new_value);
+                        }
+                        (Some(old_value), None) => {
+                            let kind = ChangeKind::Removed(old_value.clone());
+                            self.changes.push(Change {
+                                warning: warning(key, &kind),
+                                path: child_path,
+                                kind,
+                            });
+                        }
+                        (None, Some(new_value)) => self.changes.push(Change {
+                            warning: None,
+                            path: child_path,
+                            kind: ChangeKind::Added(new_value.clone()),
+                        }),
+                        (None, None) => {}
+                    }
+                }
+            }
+            (Value::Array(old_array), Value::Array(new_array))
+                if old_array.iter().chain(new_array).all(is_scalar) =>
+            {
+                // Lists of scalars such as commands or tags are compared as sets
+                for removed in old_array.iter().filter(|value| !new_array.contains(value)) {
+                    self.changes.push(Change {
+                        path: path.to_owned(),
+                        kind: ChangeKind::Removed(removed.clone()),
+                        warning: None,
+                    });
+                }
+                for added in new_array.iter().filter(|value| !old_array.contains(value)) {
+                    self.changes.push(Change {
+                        path: path.to_owned(),
+                        kind: ChangeKind::Added(added.clone()),
+                        warning: None,
+                    });
+                }
+            }
+            (Value::Array(old_array), Value::Array(new_array)) => {
+                for index in 0..old_array.len().max(new_array.len()) {
+                    let child_path = format!("{path}[{index}]");
+                    match (old_array.get(index), new_array.get(index)) {
+                        (Some(old_value), Some(new_value)) => {
+                            self.compare(&child_path, old_value, new_value);
+                        }
+                        (Some(old_value), None) => self.changes.push(Change {
+                            path: child_path,
+                            kind: ChangeKind::Removed(old_value.clone()),
+                            warning: None,
+                        }),
+                        (None, Some(new_value)) => self.changes.push(Change {
+                            path: child_path,
+                            kind: ChangeKind::Added(new_value.clone()),
+                            warning: None,
+                        }),
+                        (None, None) => {}
+                    }
+                }
+            }
+            _ => {
+                let kind = ChangeKind::Modified {
+                    old: old.clone(),
+                    new: new.clone(),
+                };
+                let key = path.rsplit('.').next().unwrap_or(path);
+                self.changes.push(Change {
+                    warning: warning(key, &kind),
+                    path: path.to_owned(),
+                    kind,
+                });
+            }
+        }
+    }
+}
+
+/// Returns why a change to a key is suspicious, if it is
+fn warning(key: &str, kind: &ChangeKind) -> Option<&'static str> {
+    match (key, kind) {
+        (SCOPE, ChangeKind::Modified { .. }) => Some("scope changed"),
+        (INSTALLER_TYPE | NESTED_INSTALLER_TYPE, ChangeKind::Modified { .. }) => {
+            Some("installer type changed")
+        }
+        (UPGRADE_CODE, _) => Some("upgrade code changed"),
+        (PACKAGE_FAMILY_NAME, _) => Some("package family name changed"),
+        (PUBLISHER, ChangeKind::Modified { .. }) => Some("publisher changed"),
+        (INSTALLER_URL, ChangeKind::Modified { old, new }) if host(old) != host(new) => {
+            Some("download host changed")
+        }
+        _ => None,
+    }
+}
+
+fn host(url: &Value) -> Option<String> {
+    url::Url::parse(url.as_str()?)
+        .ok()?
+        .host_str()
+        .map(str::to_ascii_lowercase)
+}
+
+fn to_value(value: &impl Serialize) -> Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+const fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Array(_) | Value::Object(_))
+}
+
+/// Returns the installers of an installer manifest with the root keys applied to each installer
+/// that does not override them. This is how WinGet itself reads the manifest and means that moving
+/// a key between the root and the installers is not shown as a change.
+fn effective_installers(manifest: Value) -> Vec<Value> {
+    let Value::Object(mut root) = manifest else {
+        return Vec::new();
+    };
+    let installers = match root.remove(INSTALLERS) {
+        Some(Value::Array(installers)) => installers,
+        _ => Vec::new(),
+    };
+
+    installers
+        .into_iter()
+        .map(|mut installer| {
+            if let Value::Object(installer) = &mut installer {
+                for (key, root_value) in &root {
+                    if matches!(key.as_str(), "PackageIdentifier" | "PackageVersion")
+                        || IGNORED_KEYS.contains(&key.as_str())
+                    {
+                        continue;
+                    }
+                    match (installer.get_mut(key), root_value) {
+                        (None, _) => {
+                            installer.insert(key.clone(), root_value.clone());
+                        }
+                        // Nested objects such as switches are merged key by key
+                        (Some(Value::Object(installer_value)), Value::Object(root_value)) => {
+                            for (nested_key, nested_value) in root_value {
+                                installer_value
+                                    .entry(nested_key.clone())
+                                    .or_insert_with(|| nested_value.clone());
+                            }
+                        }
+                        (Some(_), _) => {}
+                    }
+                }
+            }
+            installer
+        })
+        .collect()
+}
+
+fn key_string(value: &Value, keys: &[&str]) -> String {
+    keys.iter()
+        .filter_map(|key| value.get(key))
+        .map(format_value)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn installer_name(installer: &Value) -> String {
+    key_string(
+        installer,
+        &[ARCHITECTURE, INSTALLER_TYPE, SCOPE, INSTALLER_LOCALE],
+    )
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+impl Display for ManifestDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.changes.is_empty() {
+            return writeln!(f, "No changes");
+        }
+
+        for change in &self.changes {
+            match &change.kind {
+                ChangeKind::Added(value) => {
+                    writeln!(
+                        f,
+                        "{}",
+                        format!("+ {}: {}", change.path, format_value(value)).green()
+                    )?;
+                }
+                ChangeKind::Removed(value) => {
+                    writeln!(
+                        f,
+                        "{}",
+                        format!("- {}: {}", change.path, format_value(value)).red()
+                    )?;
+                }
+                ChangeKind::Modified { old, new } => {
+                    writeln!(
+                        f,
+                        "{} {}: {} {} {}",
+                        "~".yellow(),
+                        change.path,
+                        format_value(old).red(),
+                        "→".yellow(),
+                        format_value(new).green()
+                    )?;
+                }
+            }
+            if let Some(warning) = change.warning {
+                writeln!(f, "  {} {warning}", "Warning:".yellow().bold())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use serde_json::json;
+
+    use super::{ChangeKind, ManifestDiff, ManifestSnapshot};
+    use crate::manifests::{Manifests, manifest::Manifest};
+
+    fn manifests(installer: &str) -> Manifests {
+        const DEFAULT_LOCALE: &str = indoc! {"
+            PackageIdentifier: Example.App
+            PackageVersion: 1.0.0
+            PackageLocale: en-US
+            Publisher: Example
+            PackageName: App
+            License: MIT
+            ShortDescription: An example app
+            ManifestType: defaultLocale
+            ManifestVersion: 1.10.0
+        "};
+        const VERSION: &str = indoc! {"
+            PackageIdentifier: Example.App
+            PackageVersion: 1.0.0
+            DefaultLocale: en-US
+            ManifestType: version
+            ManifestVersion: 1.10.0
+        "};
+
+        Manifests::from_manifests(
+            [installer, DEFAULT_LOCALE, VERSION]
+                .into_iter()
+                .map(|yaml| Manifest::from_yaml(yaml).unwrap()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn root_keys_are_applied_to_installers() {
+        let old = manifests(indoc! {"
+            PackageIdentifier: Example.App
+            PackageVersion: 1.0.0
+            InstallerType: nullsoft
+            Scope: machine
+            Installers:
+            - Architecture: x64
+              InstallerUrl: https://example.com/1.0.0/setup.exe
+              InstallerSha256: AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
+            ManifestType: installer
+            ManifestVersion: 1.10.0
+        "});
+        let new = manifests(indoc! {"
+            PackageIdentifier: Example.App
+            PackageVersion: 1.1.0
+            Installers:
+            - Architecture: x64
+              InstallerType: nullsoft
+              Scope: machine
+              InstallerUrl: https://example.com/1.1.0/setup.exe
+              InstallerSha256: AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
+            ManifestType: installer
+            ManifestVersion: 1.10.0
+        "});
+
+        let diff = ManifestDiff::new(&ManifestSnapshot::new(&old), &ManifestSnapshot::new(&new));
+
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(
+            diff.changes[0].path,
+            "Installers[x64 nullsoft machine].InstallerUrl"
+        );
+        assert!(diff.changes.iter().all(|change| change.warning.is_none()));
+    }
+
+    #[test]
+    fn suspicious_changes() {
+        let old = manifests(indoc! {"
+            PackageIdentifier: Example.App
+            PackageVersion: 1.0.0
+            InstallerType: inno
+            Installers:
+            - Architecture: x64
+              Scope: machine
+              InstallerUrl: https://example.com/setup-x64.exe
+              InstallerSha256: AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
+            - Architecture: arm64
+              Scope: machine
+              InstallerUrl: https://example.com/setup-arm64.exe
+              InstallerSha256: BBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBBB
+            ManifestType: installer
+            ManifestVersion: 1.10.0
+        "});
+        let new = manifests(indoc! {"
+            PackageIdentifier: Example.App
+            PackageVersion: 1.1.0
+            InstallerType: inno
+            Installers:
+            - Architecture: x64
+              Scope: user
+              InstallerUrl: https://mirror.example.net/setup-x64.exe
+              InstallerSha256: AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
+            ManifestType: installer
+            ManifestVersion: 1.10.0
+        "});
+
+        let diff = ManifestDiff::new(&ManifestSnapshot::new(&old), &ManifestSnapshot::new(&new));
+
+        let warnings = diff
+            .changes
+            .iter()
+            .filter_map(|change| change.warning)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            warnings,
+            [
+                "download host changed",
+                "scope changed",
+                "architecture is no longer supported"
+            ]
+        );
+        assert!(
+            diff.changes
+                .iter()
+                .any(|change| change.path == "Installers[x64 inno user].Scope"
+                    && change.kind
+                        == ChangeKind::Modified {
+                            old: json!("machine"),
+                            new: json!("user")
+                        })
+        );
+    }
+}
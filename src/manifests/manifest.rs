@@ -1,10 +1,16 @@
 use winget_types::{
-    PackageIdentifier, PackageVersion,
+    GenericManifest, ManifestType, ManifestVersion, PackageIdentifier, PackageVersion,
     installer::InstallerManifest,
     locale::{DefaultLocaleManifest, LocaleManifest},
     version::VersionManifest,
 };
 
+use crate::manifests::{
+    format_manifest,
+    migrate::{Migration, migrate},
+    schema_url,
+};
+
 pub enum Manifest {
     Installer(InstallerManifest),
     DefaultLocale(DefaultLocaleManifest),
@@ -13,6 +19,34 @@ pub enum Manifest {
 }
 
 impl Manifest {
+    /// Deserializes a manifest of any type by first reading its `ManifestType`
+    pub fn from_yaml(yaml: &str) -> serde_yaml::Result<Self> {
+        Ok(
+            match serde_yaml::from_str::<GenericManifest>(yaml)?.r#type {
+                ManifestType::Installer => Self::Installer(serde_yaml::from_str(yaml)?),
+                ManifestType::DefaultLocale => Self::DefaultLocale(serde_yaml::from_str(yaml)?),
+                ManifestType::Locale => Self::Locale(serde_yaml::from_str(yaml)?),
+                ManifestType::Version => Self::Version(serde_yaml::from_str(yaml)?),
+            },
+        )
+    }
+
+    /// Deserializes a manifest of any type and manifest version as the latest manifest version
+    pub fn migrate(yaml: &str) -> serde_yaml::Result<(Self, Migration)> {
+        Ok(
+            match serde_yaml::from_str::<GenericManifest>(yaml)?.r#type {
+                ManifestType::Installer => migrate(yaml)
+                    .map(|(manifest, migration)| (Self::Installer(manifest), migration))?,
+                ManifestType::DefaultLocale => migrate(yaml)
+                    .map(|(manifest, migration)| (Self::DefaultLocale(manifest), migration))?,
+                ManifestType::Locale => migrate(yaml)
+                    .map(|(manifest, migration)| (Self::Locale(manifest), migration))?,
+                ManifestType::Version => migrate(yaml)
+                    .map(|(manifest, migration)| (Self::Version(manifest), migration))?,
+            },
+        )
+    }
+
     pub const fn package_identifier(&self) -> &PackageIdentifier {
         match self {
             Self::Installer(installer) => &installer.package_identifier,
@@ -30,4 +64,52 @@ impl Manifest {
             Self::Version(version) => &version.package_version,
         }
     }
+
+    pub const fn manifest_version(&self) -> ManifestVersion {
+        match self {
+            Self::Installer(installer) => installer.manifest_version,
+            Self::DefaultLocale(default_locale) => default_locale.manifest_version,
+            Self::Locale(locale) => locale.manifest_version,
+            Self::Version(version) => version.manifest_version,
+        }
+    }
+
+    pub const fn set_manifest_version(&mut self, manifest_version: ManifestVersion) {
+        match self {
+            Self::Installer(installer) => installer.manifest_version = manifest_version,
+            Self::DefaultLocale(default_locale) => {
+                default_locale.manifest_version = manifest_version
+            }
+            Self::Locale(locale) => locale.manifest_version = manifest_version,
+            Self::Version(version) => version.manifest_version = manifest_version,
+        }
+    }
+
+    /// Serializes the manifest below a header comment, referencing the schema of its manifest
+    /// version
+    pub fn to_yaml(&self, header: &str) -> serde_yaml::Result<String> {
+        let manifest_version = self.manifest_version();
+        match self {
+            Self::Installer(installer) => format_manifest(
+                installer,
+                header,
+                &schema_url::<InstallerManifest>(manifest_version),
+            ),
+            Self::DefaultLocale(default_locale) => format_manifest(
+                default_locale,
+                header,
+                &schema_url::<DefaultLocaleManifest>(manifest_version),
+            ),
+            Self::Locale(locale) => format_manifest(
+                locale,
+                header,
+                &schema_url::<LocaleManifest>(manifest_version),
+            ),
+            Self::Version(version) => format_manifest(
+                version,
+                header,
+                &schema_url::<VersionManifest>(manifest_version),
+            ),
+        }
+    }
 }
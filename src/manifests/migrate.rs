@@ -0,0 +1,540 @@
+use std::collections::BTreeSet;
+
+use derive_more::Display;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_yaml::{Mapping, Value};
+use winget_types::ManifestVersion;
+
+const MANIFEST_VERSION: &str = "ManifestVersion";
+const INSTALLERS: &str = "Installers";
+const INSTALLER_TYPE: &str = "InstallerType";
+const NESTED_INSTALLER_TYPE: &str = "NestedInstallerType";
+const INSTALLER_SUCCESS_CODES: &str = "InstallerSuccessCodes";
+const MANIFEST_TYPE: &str = "ManifestType";
+const MONIKER: &str = "Moniker";
+const MSSTORE: &str = "msstore";
+
+/// The manifest version assumed for manifests that do not declare a valid one
+const INITIAL_VERSION: ManifestVersion = ManifestVersion::new(1, 0, 0);
+
+/// A transform for the fields that changed meaning in a manifest version, applied to manifests
+/// from before that version
+struct Step {
+    version: ManifestVersion,
+    transform: fn(&mut Mapping, ManifestVersion, &mut Vec<MigrationNote>),
+}
+
+const STEPS: [Step; 4] = [
+    Step {
+        version: ManifestVersion::new(1, 1, 0),
+        transform: remove_zero_success_codes,
+    },
+    Step {
+        version: ManifestVersion::new(1, 4, 0),
+        transform: check_nested_installer_type,
+    },
+    Step {
+        version: ManifestVersion::DEFAULT,
+        transform: remove_store_installers,
+    },
+    Step {
+        version: ManifestVersion::DEFAULT,
+        transform: remove_locale_moniker,
+    },
+];
+
+/// A change made while migrating a manifest, or a value that could not be carried over
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+#[display("{field} {message}")]
+pub struct MigrationNote {
+    pub version: ManifestVersion,
+    pub field: String,
+    pub message: String,
+    /// Whether a value was lost or needs to be filled in manually
+    pub lossy: bool,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Migration {
+    pub from: ManifestVersion,
+    pub notes: Vec<MigrationNote>,
+}
+
+impl Migration {
+    /// Whether the manifest was on an older manifest version or had to be changed
+    pub fn is_needed(&self) -> bool {
+        self.from < ManifestVersion::DEFAULT || !self.notes.is_empty()
+    }
+
+    pub fn is_lossy(&self) -> bool {
+        self.notes.iter().any(|note| note.lossy)
+    }
+}
+
+/// Deserializes a manifest of any manifest version as the latest manifest version.
+///
+/// The fields of older manifests that changed meaning are transformed one manifest version at a
+/// time. Any field that the latest manifest version does not have is reported as a lossy note
+/// rather than dropped silently.
+pub fn migrate<T>(yaml: &str) -> serde_yaml::Result<(T, Migration)>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let mut value = serde_yaml::from_str::<Value>(yaml)?;
+    let from = value
+        .get(MANIFEST_VERSION)
+        .and_then(Value::as_str)
+        .and_then(|version| version.parse().ok())
+        .unwrap_or(INITIAL_VERSION);
+
+    let mut notes = Vec::new();
+    if let Value::Mapping(mapping) = &mut value {
+        for step in STEPS.iter().filter(|step| from < step.version) {
+            (step.transform)(mapping, step.version, &mut notes);
+        }
+        mapping.insert(
+            Value::from(MANIFEST_VERSION),
+            Value::from(ManifestVersion::DEFAULT.to_string()),
+        );
+    }
+
+    // Some fields borrow from the input while deserializing, which a `Value` cannot provide
+    let manifest = serde_yaml::from_str::<T>(&serde_yaml::to_string(&value)?)?;
+    notes.extend(
+        dropped_fields(&value, &serde_yaml::to_value(&manifest)?)
+            .into_iter()
+            .map(|field| MigrationNote {
+                version: ManifestVersion::DEFAULT,
+                field,
+                message: format!(
+                    "is not part of manifest version {} and was dropped",
+                    ManifestVersion::DEFAULT
+                ),
+                lossy: true,
+            }),
+    );
+
+    Ok((manifest, Migration { from, notes }))
+}
+
+/// Returns the paths of the fields with a value in the original manifest that are missing from the
+/// new manifest, which happens when a field is unknown or misspelt. Paths are separated by `/`,
+/// such as `Installers/InstallerSwitches/Silent`, and leave out the index of sequence items.
+pub fn dropped_fields(original: &Value, new: &Value) -> Vec<String> {
+    fn fields(value: &Value, path: &str, found: &mut BTreeSet<String>) {
+        match value {
+            Value::Mapping(mapping) => {
+                for (key, value) in mapping {
+                    let Value::String(key) = key else {
+                        continue;
+                    };
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}/{key}")
+                    };
+                    fields(value, &path, found);
+                    if !is_empty(value) {
+                        found.insert(path);
+                    }
+                }
+            }
+            Value::Sequence(sequence) => {
+                for value in sequence {
+                    fields(value, path, found);
+                }
+            }
+            Value::Tagged(tagged) => fields(&tagged.value, path, found),
+            _ => {}
+        }
+    }
+
+    // Empty values and `false` are left out when serializing, so they are not lost
+    fn is_empty(value: &Value) -> bool {
+        match value {
+            Value::Null | Value::Bool(false) => true,
+            Value::String(string) => string.is_empty(),
+            Value::Sequence(sequence) => sequence.is_empty(),
+            Value::Mapping(mapping) => mapping.is_empty(),
+            _ => false,
+        }
+    }
+
+    let mut original_fields = BTreeSet::new();
+    fields(original, "", &mut original_fields);
+    let mut new_fields = BTreeSet::new();
+    fields(new, "", &mut new_fields);
+
+    // Optimising moves the values that every installer shares to the root, and values at the root
+    // apply to every installer, so a value may be at either level without being lost
+    if let Some(installers) = new.get(INSTALLERS).and_then(Value::as_sequence) {
+        let installer_prefix = format!("{INSTALLERS}/");
+        let root_fields = new_fields
+            .iter()
+            .filter(|field| *field != INSTALLERS && !field.starts_with(&installer_prefix))
+            .map(|field| format!("{installer_prefix}{field}"))
+            .collect::<Vec<_>>();
+        let shared_fields = installers
+            .iter()
+            .map(|installer| {
+                let mut found = BTreeSet::new();
+                fields(installer, "", &mut found);
+                found
+            })
+            .reduce(|shared, found| &shared & &found)
+            .unwrap_or_default();
+        new_fields.extend(root_fields);
+        new_fields.extend(shared_fields);
+    }
+
+    let dropped = original_fields
+        .difference(&new_fields)
+        .map(String::as_str)
+        .collect::<BTreeSet<_>>();
+
+    // Only the outermost field is reported when a whole mapping was dropped
+    dropped
+        .iter()
+        .filter(|field| {
+            !field
+                .rsplit_once('/')
+                .is_some_and(|(parent, _)| dropped.contains(parent))
+        })
+        .map(|field| (*field).to_owned())
+        .collect()
+}
+
+/// The installers of an installer manifest
+fn installer_levels(mapping: &mut Mapping) -> impl Iterator<Item = &mut Mapping> {
+    let installers = mapping
+        .get_mut(INSTALLERS)
+        .and_then(Value::as_sequence_mut)
+        .map(|installers| {
+            installers
+                .iter_mut()
+                .filter_map(Value::as_mapping_mut)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    installers.into_iter()
+}
+
+/// Exit code 0 always means success, so it may no longer be listed as a success code
+fn remove_zero_success_codes(
+    mapping: &mut Mapping,
+    version: ManifestVersion,
+    notes: &mut Vec<MigrationNote>,
+) {
+    let mut remove = |level: &mut Mapping| {
+        let Some(codes) = level
+            .get_mut(INSTALLER_SUCCESS_CODES)
+            .and_then(Value::as_sequence_mut)
+        else {
+            return;
+        };
+        let count = codes.len();
+        codes.retain(|code| code.as_i64() != Some(0));
+        if codes.len() != count {
+            if codes.is_empty() {
+                level.remove(INSTALLER_SUCCESS_CODES);
+            }
+            notes.push(MigrationNote {
+                version,
+                field: String::from(INSTALLER_SUCCESS_CODES),
+                message: String::from("no longer lists 0 as it always means success"),
+                lossy: false,
+            });
+        }
+    };
+
+    remove(mapping);
+    for installer in installer_levels(mapping) {
+        remove(installer);
+    }
+}
+
+/// Zip installers need to say which installer they contain
+fn check_nested_installer_type(
+    mapping: &mut Mapping,
+    version: ManifestVersion,
+    notes: &mut Vec<MigrationNote>,
+) {
+    let is_zip = |level: &Mapping| {
+        level
+            .get(INSTALLER_TYPE)
+            .and_then(Value::as_str)
+            .is_some_and(|installer_type| installer_type.eq_ignore_ascii_case("zip"))
+    };
+    let root_zip = is_zip(mapping);
+    let root_nested = mapping.contains_key(NESTED_INSTALLER_TYPE);
+
+    let missing = installer_levels(mapping).any(|installer| {
+        (root_zip || is_zip(installer))
+            && !(root_nested || installer.contains_key(NESTED_INSTALLER_TYPE))
+    });
+    if missing {
+        notes.push(MigrationNote {
+            version,
+            field: String::from(INSTALLER_TYPE),
+            message: format!(
+                "zip needs a NestedInstallerType and NestedInstallerFiles since manifest version {version}, which have to be added manually"
+            ),
+            lossy: true,
+        });
+    }
+}
+
+/// Microsoft Store packages are installed through the `msstore` source rather than an installer
+/// type, so installers of the `msstore` type are removed
+fn remove_store_installers(
+    mapping: &mut Mapping,
+    version: ManifestVersion,
+    notes: &mut Vec<MigrationNote>,
+) {
+    let is_store = |level: &Mapping| {
+        level
+            .get(INSTALLER_TYPE)
+            .and_then(Value::as_str)
+            .is_some_and(|installer_type| installer_type.eq_ignore_ascii_case(MSSTORE))
+    };
+    let root_store = is_store(mapping);
+    if root_store {
+        mapping.remove(INSTALLER_TYPE);
+    }
+
+    let Some(installers) = mapping.get_mut(INSTALLERS).and_then(Value::as_sequence_mut) else {
+        return;
+    };
+    let count = installers.len();
+    installers.retain(|installer| {
+        installer
+            .as_mapping()
+            .is_none_or(|installer| match installer.get(INSTALLER_TYPE) {
+                Some(_) => !is_store(installer),
+                None => !root_store,
+            })
+    });
+    let removed = count - installers.len();
+    if removed != 0 {
+        notes.push(MigrationNote {
+            version,
+            field: String::from(INSTALLERS),
+            message: format!(
+                "had {removed} {MSSTORE} installer(s) removed, as Microsoft Store packages are installed from the {MSSTORE} source"
+            ),
+            lossy: true,
+        });
+    }
+}
+
+/// The moniker of a package is only part of its default locale
+fn remove_locale_moniker(
+    mapping: &mut Mapping,
+    version: ManifestVersion,
+    notes: &mut Vec<MigrationNote>,
+) {
+    let is_locale = mapping
+        .get(MANIFEST_TYPE)
+        .and_then(Value::as_str)
+        .is_some_and(|manifest_type| manifest_type == "locale");
+    if is_locale
+        && let Some(moniker) = mapping.remove(MONIKER)
+        && !moniker.is_null()
+    {
+        notes.push(MigrationNote {
+            version,
+            field: String::from(MONIKER),
+            message: String::from("is only allowed in the default locale manifest and was removed"),
+            lossy: true,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use winget_types::{ManifestVersion, installer::InstallerManifest, locale::LocaleManifest};
+
+    use super::{INSTALLER_SUCCESS_CODES, INSTALLERS, MONIKER, dropped_fields, migrate};
+
+    #[test]
+    fn removes_zero_success_codes() {
+        let (manifest, migration) = migrate::<InstallerManifest>(indoc! {"
+            PackageIdentifier: Contoso.App
+            PackageVersion: 1.2.3
+            InstallerSuccessCodes:
+            - 0
+            - 3010
+            Installers:
+            - Architecture: x64
+              InstallerType: exe
+              InstallerUrl: https://example.com/setup.exe
+              InstallerSha256: AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
+              InstallerSuccessCodes:
+              - 0
+            ManifestType: installer
+            ManifestVersion: 1.0.0
+        "})
+        .unwrap();
+
+        assert_eq!(migration.from, ManifestVersion::new(1, 0, 0));
+        assert_eq!(manifest.manifest_version, ManifestVersion::DEFAULT);
+        assert_eq!(manifest.success_codes.len(), 1);
+        assert!(manifest.installers[0].success_codes.is_empty());
+        assert_eq!(migration.notes.len(), 2);
+        assert!(
+            migration
+                .notes
+                .iter()
+                .all(|note| note.field == INSTALLER_SUCCESS_CODES && !note.lossy)
+        );
+    }
+
+    #[test]
+    fn reports_lossy_changes() {
+        let (_, migration) = migrate::<InstallerManifest>(indoc! {"
+            PackageIdentifier: Contoso.App
+            PackageVersion: 1.2.3
+            InstallerType: zip
+            Installers:
+            - Architecture: x64
+              InstallerUrl: https://example.com/app.zip
+              InstallerSha256: AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
+              RetiredField: value
+            ManifestType: installer
+            ManifestVersion: 1.2.0
+        "})
+        .unwrap();
+
+        assert!(migration.is_lossy());
+        assert_eq!(
+            migration
+                .notes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            [
+                "InstallerType zip needs a NestedInstallerType and NestedInstallerFiles since manifest version 1.4.0, which have to be added manually",
+                "Installers/RetiredField is not part of manifest version 1.10.0 and was dropped"
+            ]
+        );
+    }
+
+    #[test]
+    fn removes_store_installers() {
+        let (manifest, migration) = migrate::<InstallerManifest>(indoc! {"
+            PackageIdentifier: Contoso.App
+            PackageVersion: 1.2.3
+            InstallerType: msstore
+            Installers:
+            - Architecture: neutral
+              MSStoreProductIdentifier: 9NBLGGH4NNS1
+            - Architecture: x64
+              InstallerType: msix
+              InstallerUrl: https://example.com/app.msix
+              InstallerSha256: AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
+            ManifestType: installer
+            ManifestVersion: 1.0.0
+        "})
+        .unwrap();
+
+        assert_eq!(manifest.installers.len(), 1);
+        assert!(manifest.r#type.is_none());
+        assert!(migration.is_lossy());
+        assert!(migration.notes.iter().any(|note| note.field == INSTALLERS));
+    }
+
+    #[test]
+    fn removes_locale_moniker() {
+        let (manifest, migration) = migrate::<LocaleManifest>(indoc! {"
+            PackageIdentifier: Contoso.App
+            PackageVersion: 1.2.3
+            PackageLocale: de-DE
+            Moniker: app
+            ShortDescription: Eine App
+            ManifestType: locale
+            ManifestVersion: 1.6.0
+        "})
+        .unwrap();
+
+        assert!(manifest.short_description.is_some());
+        assert_eq!(
+            migration
+                .notes
+                .iter()
+                .map(|note| note.field.as_str())
+                .collect::<Vec<_>>(),
+            [MONIKER]
+        );
+    }
+
+    #[test]
+    fn latest_manifest_is_unchanged() {
+        let (_, migration) = migrate::<InstallerManifest>(indoc! {"
+            PackageIdentifier: Contoso.App
+            PackageVersion: 1.2.3
+            Installers:
+            - Architecture: x64
+              InstallerType: exe
+              InstallerUrl: https://example.com/setup.exe
+              InstallerSha256: AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
+              InstallerAbortsTerminal: false
+            ManifestType: installer
+            ManifestVersion: 1.10.0
+        "})
+        .unwrap();
+
+        assert!(!migration.is_needed());
+    }
+
+    #[test]
+    fn dropped_field_paths() {
+        let original = serde_yaml::from_str(indoc! {"
+            Scope: machine
+            Installers:
+            - Architecture: x64
+              Scope: user
+              InstallerSwitches:
+                Silent: /S
+                Scope: user
+            - Architecture: x86
+        "})
+        .unwrap();
+        let new = serde_yaml::from_str(indoc! {"
+            Installers:
+            - Architecture: x64
+              Scope: user
+              InstallerSwitches:
+                Silent: /S
+            - Architecture: x86
+        "})
+        .unwrap();
+
+        assert_eq!(
+            dropped_fields(&original, &new),
+            ["Installers/InstallerSwitches/Scope", "Scope"]
+        );
+    }
+
+    #[test]
+    fn moving_fields_between_root_and_installers_is_not_dropping() {
+        let root = serde_yaml::from_str(indoc! {"
+            Scope: machine
+            Installers:
+            - Architecture: x64
+            - Architecture: x86
+        "})
+        .unwrap();
+        let installers = serde_yaml::from_str(indoc! {"
+            Installers:
+            - Architecture: x64
+              Scope: machine
+            - Architecture: x86
+              Scope: machine
+        "})
+        .unwrap();
+
+        assert!(dropped_fields(&root, &installers).is_empty());
+        assert!(dropped_fields(&installers, &root).is_empty());
+    }
+}
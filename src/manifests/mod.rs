@@ -14,13 +14,17 @@ use serde::Serialize;
 use tree_sitter_highlight::{Highlight, HighlightConfiguration, HighlightEvent, Highlighter};
 pub use url::Url;
 use winget_types::{
-    Manifest,
+    Manifest, ManifestVersion,
     installer::InstallerManifest,
     locale::{DefaultLocaleManifest, LocaleManifest},
     version::VersionManifest,
 };
 
+pub mod diff;
 pub mod manifest;
+pub mod migrate;
+pub mod schema;
+pub mod spec;
 mod url;
 
 pub struct Manifests {
@@ -30,6 +34,35 @@ pub struct Manifests {
     pub version: VersionManifest,
 }
 
+impl Manifests {
+    /// Rebuilds the manifests of a single package version. Returns `None` if the installer,
+    /// default locale or version manifest is missing.
+    pub fn from_manifests(manifests: impl IntoIterator<Item = manifest::Manifest>) -> Option<Self> {
+        let mut installer = None;
+        let mut default_locale = None;
+        let mut locales = Vec::new();
+        let mut version = None;
+        for manifest in manifests {
+            match manifest {
+                manifest::Manifest::Installer(installer_manifest) => {
+                    installer = Some(installer_manifest);
+                }
+                manifest::Manifest::DefaultLocale(default_locale_manifest) => {
+                    default_locale = Some(default_locale_manifest);
+                }
+                manifest::Manifest::Locale(locale) => locales.push(locale),
+                manifest::Manifest::Version(version_manifest) => version = Some(version_manifest),
+            }
+        }
+        Some(Self {
+            installer: installer?,
+            default_locale: default_locale?,
+            locales,
+            version: version?,
+        })
+    }
+}
+
 impl Display for Manifests {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -111,17 +144,51 @@ pub fn build_manifest_string<T>(
 where
     T: Manifest + Serialize,
 {
-    let mut result = String::from("# Created with ");
+    format_manifest(manifest, &created_with_header(created_with), T::SCHEMA)
+}
+
+/// The header comment that credits Komac, and the tool that invoked it if any
+pub fn created_with_header(created_with: Option<&str>) -> String {
+    let mut header = String::from("Created with ");
     if let Some(created_with_tool) = created_with {
-        let _ = write!(result, "{created_with_tool} using ");
+        let _ = write!(header, "{created_with_tool} using ");
     }
-    let _ = writeln!(result, "{} v{}", crate_name!(), crate_version!());
-    let _ = writeln!(result, "# yaml-language-server: $schema={}", T::SCHEMA);
+    let _ = write!(header, "{} v{}", crate_name!(), crate_version!());
+    header
+}
+
+/// Returns the comment on the first line of a manifest if it is not the `$schema` comment
+pub fn header_comment(content: &str) -> Option<&str> {
+    content
+        .trim_start_matches('\u{FEFF}')
+        .lines()
+        .next()?
+        .strip_prefix('#')
+        .map(str::trim)
+        .filter(|header| !header.is_empty() && !header.contains("$schema"))
+}
+
+/// Serializes a manifest below a header comment and a `$schema` comment, with CRLF line endings
+pub fn format_manifest<T>(manifest: &T, header: &str, schema: &str) -> serde_yaml::Result<String>
+where
+    T: Serialize,
+{
+    let mut result = String::new();
+    let _ = writeln!(result, "# {header}");
+    let _ = writeln!(result, "# yaml-language-server: $schema={schema}");
     let _ = writeln!(result);
     let _ = write!(result, "{}", serde_yaml::to_string(manifest)?);
     Ok(convert_to_crlf(&result).into_owned())
 }
 
+/// Returns the URL of a manifest type's schema for a given manifest version
+pub fn schema_url<T: Manifest>(manifest_version: ManifestVersion) -> String {
+    T::SCHEMA.replace(
+        &ManifestVersion::DEFAULT.to_string(),
+        &manifest_version.to_string(),
+    )
+}
+
 fn convert_to_crlf(input: &str) -> Cow<str> {
     const CR: char = '\r';
     const LF: char = '\n';
@@ -172,7 +239,7 @@ fn convert_to_crlf(input: &str) -> Cow<str> {
 mod tests {
     use std::borrow::Cow;
 
-    use crate::manifests::convert_to_crlf;
+    use crate::manifests::{convert_to_crlf, header_comment};
 
     #[test]
     fn preserves_valid_crlf() {
@@ -223,4 +290,17 @@ mod tests {
         assert_eq!(convert_to_crlf("\r\n"), "\r\n");
         assert_eq!(convert_to_crlf("a\rb\nc\r\nd"), "a\r\nb\r\nc\r\nd");
     }
+
+    #[test]
+    fn reads_header_comment() {
+        assert_eq!(
+            header_comment("# Created with komac v2.0.0\r\nPackageIdentifier: Contoso.App"),
+            Some("Created with komac v2.0.0")
+        );
+        assert_eq!(
+            header_comment("# yaml-language-server: $schema=https://aka.ms/schema.json"),
+            None
+        );
+        assert_eq!(header_comment("PackageIdentifier: Contoso.App"), None);
+    }
 }
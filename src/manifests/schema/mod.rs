@@ -0,0 +1,244 @@
+use std::sync::LazyLock;
+
+use derive_more::Display;
+use jsonschema::Validator;
+use serde_json::Value;
+use thiserror::Error;
+use winget_types::{ManifestType, ManifestVersion};
+
+const MANIFEST_TYPE: &str = "ManifestType";
+const MANIFEST_VERSION: &str = "ManifestVersion";
+
+/// The published winget manifest schemas. Only the manifest version Komac generates is bundled
+const BUNDLED_SCHEMAS: [(ManifestType, ManifestVersion, &str); 4] = [
+    (
+        ManifestType::Installer,
+        ManifestVersion::new(1, 10, 0),
+        include_str!("manifest.installer.1.10.0.json"),
+    ),
+    (
+        ManifestType::DefaultLocale,
+        ManifestVersion::new(1, 10, 0),
+        include_str!("manifest.defaultLocale.1.10.0.json"),
+    ),
+    (
+        ManifestType::Locale,
+        ManifestVersion::new(1, 10, 0),
+        include_str!("manifest.locale.1.10.0.json"),
+    ),
+    (
+        ManifestType::Version,
+        ManifestVersion::new(1, 10, 0),
+        include_str!("manifest.version.1.10.0.json"),
+    ),
+];
+
+static VALIDATORS: LazyLock<Vec<(ManifestType, ManifestVersion, Validator)>> =
+    LazyLock::new(|| {
+        BUNDLED_SCHEMAS
+            .into_iter()
+            .map(|(manifest_type, manifest_version, schema)| {
+                let schema = serde_json::from_str(schema).unwrap();
+                (
+                    manifest_type,
+                    manifest_version,
+                    jsonschema::draft7::new(&schema).unwrap(),
+                )
+            })
+            .collect()
+    });
+
+/// A value in a manifest that does not match its schema
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+#[display("{pointer}: {message}")]
+pub struct Violation {
+    /// The JSON pointer to the value, such as `/Installers/0/Architecture`
+    pub pointer: String,
+    pub message: String,
+}
+
+#[derive(Debug, Error)]
+pub enum SchemaError {
+    #[error("is not valid YAML: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    #[error(
+        "was not validated as no schema is bundled for {manifest_type} manifests with manifest version {manifest_version}"
+    )]
+    NoSchema {
+        manifest_type: String,
+        manifest_version: String,
+    },
+}
+
+/// Validates a manifest against the bundled schema for its manifest type and manifest version.
+///
+/// Manifests without a bundled schema, such as those from older manifest versions, cannot be
+/// validated and return [`SchemaError::NoSchema`].
+pub fn validate(yaml: &str) -> Result<Vec<Violation>, SchemaError> {
+    let manifest = serde_yaml::from_str::<Value>(yaml)?;
+    let manifest_type = manifest.get(MANIFEST_TYPE).and_then(|manifest_type| {
+        serde_json::from_value::<ManifestType>(manifest_type.clone()).ok()
+    });
+    let manifest_version = manifest
+        .get(MANIFEST_VERSION)
+        .and_then(Value::as_str)
+        .and_then(|version| version.parse::<ManifestVersion>().ok());
+
+    let Some(validator) = VALIDATORS
+        .iter()
+        .find(|(bundled_type, bundled_version, _)| {
+            Some(*bundled_type) == manifest_type && Some(*bundled_version) == manifest_version
+        })
+        .map(|(_, _, validator)| validator)
+    else {
+        let field = |key| {
+            manifest
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or("unknown")
+                .to_owned()
+        };
+        return Err(SchemaError::NoSchema {
+            manifest_type: field(MANIFEST_TYPE),
+            manifest_version: field(MANIFEST_VERSION),
+        });
+    };
+
+    Ok(validator
+        .iter_errors(&manifest)
+        .map(|error| Violation {
+            pointer: match error.instance_path().as_str() {
+                "" => String::from("/"),
+                pointer => pointer.to_owned(),
+            },
+            message: error.masked().to_string(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use rstest::rstest;
+    use winget_types::{
+        installer::InstallerManifest,
+        locale::{DefaultLocaleManifest, LocaleManifest},
+        version::VersionManifest,
+    };
+
+    use super::{BUNDLED_SCHEMAS, VALIDATORS, validate};
+    use crate::manifests::build_manifest_string;
+
+    const INSTALLER: &str = indoc! {"
+        PackageIdentifier: Contoso.App
+        PackageVersion: 1.2.3
+        InstallerType: inno
+        Installers:
+        - Architecture: x64
+          InstallerUrl: https://example.com/setup.exe
+          InstallerSha256: AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA
+        ManifestType: installer
+        ManifestVersion: 1.10.0
+    "};
+
+    const DEFAULT_LOCALE: &str = indoc! {"
+        PackageIdentifier: Contoso.App
+        PackageVersion: 1.2.3
+        PackageLocale: en-US
+        Publisher: Contoso
+        PackageName: App
+        License: MIT
+        ShortDescription: An app
+        Tags:
+        - contoso
+        ManifestType: defaultLocale
+        ManifestVersion: 1.10.0
+    "};
+
+    const LOCALE: &str = indoc! {"
+        PackageIdentifier: Contoso.App
+        PackageVersion: 1.2.3
+        PackageLocale: de-DE
+        ShortDescription: Eine App
+        ManifestType: locale
+        ManifestVersion: 1.10.0
+    "};
+
+    const VERSION: &str = indoc! {"
+        PackageIdentifier: Contoso.App
+        PackageVersion: 1.2.3
+        DefaultLocale: en-US
+        ManifestType: version
+        ManifestVersion: 1.10.0
+    "};
+
+    #[test]
+    fn bundled_schemas_compile() {
+        assert_eq!(VALIDATORS.len(), BUNDLED_SCHEMAS.len());
+    }
+
+    #[test]
+    fn generated_manifests_are_valid() {
+        for manifest in [
+            build_manifest_string(
+                &serde_yaml::from_str::<InstallerManifest>(INSTALLER).unwrap(),
+                None,
+            ),
+            build_manifest_string(
+                &serde_yaml::from_str::<DefaultLocaleManifest>(DEFAULT_LOCALE).unwrap(),
+                None,
+            ),
+            build_manifest_string(
+                &serde_yaml::from_str::<LocaleManifest>(LOCALE).unwrap(),
+                None,
+            ),
+            build_manifest_string(
+                &serde_yaml::from_str::<VersionManifest>(VERSION).unwrap(),
+                None,
+            ),
+        ] {
+            let manifest = manifest.unwrap();
+            assert_eq!(validate(&manifest).unwrap(), [], "{manifest}");
+        }
+    }
+
+    #[rstest]
+    #[case(
+        &DEFAULT_LOCALE.replace("An app", &"a".repeat(257)),
+        "/ShortDescription",
+        "value is longer than 256 characters"
+    )]
+    #[case(&DEFAULT_LOCALE.replace("License: MIT\n", ""), "/", "\"License\" is a required property")]
+    #[case(
+        &DEFAULT_LOCALE.replace("Tags:", "Monikr: app\nTags:"),
+        "/",
+        "Additional properties are not allowed ('Monikr' was unexpected)"
+    )]
+    #[case(&DEFAULT_LOCALE.replace("- contoso", "- ''"), "/Tags/0", "value is shorter than 1 character")]
+    #[case(
+        &INSTALLER.replace("- Architecture: x64", "- Architecture: x64\n  Scope: everyone"),
+        "/Installers/0/Scope",
+        "value is not one of"
+    )]
+    fn reports_violations(#[case] manifest: &str, #[case] pointer: &str, #[case] message: &str) {
+        let violations = validate(manifest).unwrap();
+
+        assert_eq!(violations.len(), 1, "{violations:?}");
+        assert_eq!(violations[0].pointer, pointer);
+        assert!(
+            violations[0].message.starts_with(message),
+            "{}",
+            violations[0].message
+        );
+    }
+
+    #[test]
+    fn reports_manifest_versions_without_a_schema() {
+        let manifest = DEFAULT_LOCALE.replace("1.10.0", "1.6.0");
+
+        assert_eq!(
+            validate(&manifest).unwrap_err().to_string(),
+            "was not validated as no schema is bundled for defaultLocale manifests with manifest version 1.6.0"
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+};
+
+use camino::Utf8Path;
+use serde::{Deserialize, de::DeserializeOwned};
+use thiserror::Error;
+use winget_types::{
+    LanguageTag, ManifestType, ManifestVersion, PackageIdentifier, PackageVersion,
+    installer::{
+        Command, FileExtension, InstallModes, InstallerSuccessCode, InstallerSwitches,
+        InstallerType, NestedInstallerFiles, Protocol, UpgradeBehavior,
+    },
+    locale::{
+        Author, Copyright, Description, InstallationNotes, License, LocaleManifest, Moniker,
+        PackageName, Publisher, ReleaseNotes, ShortDescription, Tag,
+    },
+    url::{
+        CopyrightUrl, DecodedUrl, LicenseUrl, PackageUrl, PublisherSupportUrl, PublisherUrl,
+        ReleaseNotesUrl,
+    },
+};
+
+use crate::manifests::Url;
+
+#[derive(Debug, Error)]
+pub enum SpecError {
+    #[error("Failed to read package definition {path}: {source}")]
+    ReadFile { path: String, source: io::Error },
+    #[error("Failed to parse package definition {path}: {source}")]
+    ParseFile {
+        path: String,
+        source: serde_yaml::Error,
+    },
+}
+
+/// A partial manifest that defines a new package. It accepts the root-level keys of installer and
+/// default locale manifests, along with `InstallerUrls`. Anything left out is detected from the
+/// installers or prompted for.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct PackageSpec {
+    pub package_identifier: Option<PackageIdentifier>,
+    pub package_version: Option<PackageVersion>,
+    pub package_locale: Option<LanguageTag>,
+    #[serde(default)]
+    pub installer_urls: Vec<Url>,
+    /// The type of EXE installers that were not recognised as a specific installer, which answers
+    /// whether they are portable instead of prompting
+    pub installer_type: Option<InstallerType>,
+    /// The files to install from each zip installer, keyed by the installer's URL
+    #[serde(default)]
+    pub nested_installer_files: BTreeMap<DecodedUrl, Vec<NestedInstallerFiles>>,
+    pub installer_switches: Option<InstallerSwitches>,
+    pub install_modes: Option<InstallModes>,
+    pub installer_success_codes: Option<BTreeSet<InstallerSuccessCode>>,
+    pub upgrade_behavior: Option<UpgradeBehavior>,
+    pub commands: Option<BTreeSet<Command>>,
+    pub protocols: Option<BTreeSet<Protocol>>,
+    pub file_extensions: Option<BTreeSet<FileExtension>>,
+    pub publisher: Option<Publisher>,
+    pub publisher_url: Option<PublisherUrl>,
+    pub publisher_support_url: Option<PublisherSupportUrl>,
+    pub author: Option<Author>,
+    pub package_name: Option<PackageName>,
+    pub package_url: Option<PackageUrl>,
+    pub license: Option<License>,
+    pub license_url: Option<LicenseUrl>,
+    pub copyright: Option<Copyright>,
+    pub copyright_url: Option<CopyrightUrl>,
+    pub short_description: Option<ShortDescription>,
+    pub description: Option<Description>,
+    pub moniker: Option<Moniker>,
+    pub tags: Option<BTreeSet<Tag>>,
+    pub release_notes_url: Option<ReleaseNotesUrl>,
+}
+
+impl PackageSpec {
+    pub fn from_file(path: &Utf8Path) -> Result<Self, SpecError> {
+        read_spec(path)
+    }
+}
+
+/// A partial locale manifest for an additional locale of a new package. Values that are left out
+/// fall back to the default locale when the package is installed.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase", deny_unknown_fields)]
+pub struct LocaleSpec {
+    pub publisher: Option<Publisher>,
+    pub publisher_url: Option<PublisherUrl>,
+    pub publisher_support_url: Option<PublisherSupportUrl>,
+    pub author: Option<Author>,
+    pub package_name: Option<PackageName>,
+    pub package_url: Option<PackageUrl>,
+    pub license: Option<License>,
+    pub license_url: Option<LicenseUrl>,
+    pub copyright: Option<Copyright>,
+    pub copyright_url: Option<CopyrightUrl>,
+    pub short_description: Option<ShortDescription>,
+    pub description: Option<Description>,
+    pub tags: Option<BTreeSet<Tag>>,
+    pub release_notes: Option<ReleaseNotes>,
+    pub release_notes_url: Option<ReleaseNotesUrl>,
+    pub installation_notes: Option<InstallationNotes>,
+}
+
+impl LocaleSpec {
+    pub fn from_file(path: &Utf8Path) -> Result<Self, SpecError> {
+        read_spec(path)
+    }
+
+    pub fn into_manifest(
+        self,
+        package_identifier: PackageIdentifier,
+        package_version: PackageVersion,
+        package_locale: LanguageTag,
+    ) -> LocaleManifest {
+        LocaleManifest {
+            package_identifier,
+            package_version,
+            package_locale,
+            publisher: self.publisher,
+            publisher_url: self.publisher_url,
+            publisher_support_url: self.publisher_support_url,
+            author: self.author,
+            package_name: self.package_name,
+            package_url: self.package_url,
+            license: self.license,
+            license_url: self.license_url,
+            copyright: self.copyright,
+            copyright_url: self.copyright_url,
+            short_description: self.short_description,
+            description: self.description,
+            tags: self.tags.unwrap_or_default(),
+            release_notes: self.release_notes,
+            release_notes_url: self.release_notes_url,
+            installation_notes: self.installation_notes,
+            privacy_url: None,
+            agreements: BTreeSet::new(),
+            purchase_url: None,
+            documentations: BTreeSet::new(),
+            icons: BTreeSet::new(),
+            manifest_type: ManifestType::Locale,
+            manifest_version: ManifestVersion::default(),
+        }
+    }
+}
+
+fn read_spec<T: DeserializeOwned>(path: &Utf8Path) -> Result<T, SpecError> {
+    let contents = fs::read_to_string(path).map_err(|source| SpecError::ReadFile {
+        path: path.to_string(),
+        source,
+    })?;
+    serde_yaml::from_str(&contents).map_err(|source| SpecError::ParseFile {
+        path: path.to_string(),
+        source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use winget_types::installer::{InstallModes, InstallerType};
+
+    use super::{LocaleSpec, PackageSpec};
+
+    #[test]
+    fn partial_manifest() {
+        const SPEC: &str = indoc! {"
+            PackageIdentifier: Package.Identifier
+            PackageVersion: 1.2.3
+            InstallerUrls:
+              - https://example.com/setup.exe
+              - https://example.com/app.zip
+            InstallerType: portable
+            NestedInstallerFiles:
+              https://example.com/app.zip:
+                - RelativeFilePath: app/setup.exe
+            InstallModes:
+              - silent
+            InstallerSwitches:
+              Silent: /S
+            Commands:
+              - package
+            Publisher: Publisher
+            ShortDescription: A short description
+            Tags:
+              - tag
+        "};
+
+        let spec = serde_yaml::from_str::<PackageSpec>(SPEC).unwrap();
+        assert_eq!(spec.installer_urls.len(), 2);
+        assert_eq!(
+            spec.nested_installer_files
+                .get(&"https://example.com/app.zip".parse().unwrap())
+                .map(Vec::len),
+            Some(1)
+        );
+        assert_eq!(spec.installer_type, Some(InstallerType::Portable));
+        assert_eq!(spec.install_modes, Some(InstallModes::SILENT));
+        assert!(spec.installer_switches.unwrap().silent.is_some());
+        assert!(spec.license.is_none());
+    }
+
+    #[test]
+    fn locale() {
+        const SPEC: &str = indoc! {"
+            ShortDescription: Ein Paket
+            ReleaseNotes: |
+              - Fehlerbehebungen
+        "};
+
+        let manifest = serde_yaml::from_str::<LocaleSpec>(SPEC)
+            .unwrap()
+            .into_manifest(
+                "Package.Identifier".parse().unwrap(),
+                "1.2.3".parse().unwrap(),
+                "de-DE".parse().unwrap(),
+            );
+        assert_eq!(manifest.package_locale.to_string(), "de-DE");
+        assert!(manifest.short_description.is_some());
+        assert!(manifest.release_notes.is_some());
+        assert!(manifest.description.is_none());
+    }
+
+    #[test]
+    fn unknown_key() {
+        assert!(serde_yaml::from_str::<PackageSpec>("Unknown: value").is_err());
+    }
+}
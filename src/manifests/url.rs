@@ -4,10 +4,11 @@ use std::{
     str::FromStr,
 };
 
+use serde_with::DeserializeFromStr;
 use url::ParseError;
 use winget_types::{installer::Architecture, url::DecodedUrl};
 
-#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord, Hash, DeserializeFromStr)]
 pub struct Url {
     inner: DecodedUrl,
     override_architecture: Option<Architecture>,
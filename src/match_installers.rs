@@ -1,13 +1,202 @@
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    num::NonZeroUsize,
+    str::FromStr,
+};
 
 use camino::Utf8Path;
-use winget_types::installer::{Architecture, Installer, Scope, VALID_FILE_EXTENSIONS};
+use itertools::{Either, Itertools};
+use strsim::normalized_levenshtein;
+use thiserror::Error;
+use winget_types::{
+    installer::{Architecture, Installer, VALID_FILE_EXTENSIONS},
+    url::DecodedUrl,
+};
 
+const ARCHITECTURE_SCORE: u32 = 20;
+const URL_ARCHITECTURE_SCORE: u32 = 10;
+const TYPE_SCORE: u32 = 30;
+const NESTED_TYPE_SCORE: u32 = 30;
+const SCOPE_SCORE: u32 = 10;
+const URL_SIMILARITY_SCORE: u32 = 20;
+const PRODUCT_SCORE: u32 = 20;
+
+/// Matches scoring below this are reported as low confidence
+pub const MIN_CONFIDENT_SCORE: u32 = 60;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MatchError {
+    #[error("--match {index}: the previous version only has {count} installers")]
+    InvalidIndex { index: NonZeroUsize, count: usize },
+    #[error("--match {index}: {url} is not one of the new installer URLs")]
+    UnknownUrl {
+        index: NonZeroUsize,
+        url: DecodedUrl,
+    },
+    #[error(
+        "Could not confidently match the previous installers {0} to a new installer of the same file type. Use --match <PREVIOUS_INDEX>=<URL> to choose their new installers"
+    )]
+    LowConfidence(String),
+}
+
+/// Assigns the previous installer at a 1-based index to the installer from a URL, given as
+/// `2=https://example.com/setup.exe`
+#[derive(Clone, Debug)]
+pub struct MatchOverride {
+    pub index: NonZeroUsize,
+    pub url: DecodedUrl,
+}
+
+impl FromStr for MatchOverride {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (index, url) = value
+            .split_once('=')
+            .ok_or_else(|| format!("expected <PREVIOUS_INDEX>=<URL> but found {value}"))?;
+        Ok(Self {
+            index: index
+                .trim()
+                .parse()
+                .map_err(|err| format!("invalid installer index {index}: {err}"))?,
+            url: url.trim().parse().map_err(|err| format!("{url}: {err}"))?,
+        })
+    }
+}
+
+/// How well a new installer matches a previous installer, broken down by signal
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub architecture: u32,
+    pub url_architecture: u32,
+    pub installer_type: u32,
+    pub nested_installer_type: u32,
+    pub scope: u32,
+    pub url_similarity: u32,
+    pub product: u32,
+    /// Whether the file extensions of both installers are the same
+    pub compatible: bool,
+    /// Whether the new installer is for the previous installer's architecture, or either is neutral
+    pub same_architecture: bool,
+}
+
+impl Score {
+    fn new(previous: &Installer, new: &Installer, url_architecture: Option<Architecture>) -> Self {
+        Self {
+            architecture: points(
+                new.architecture == previous.architecture,
+                ARCHITECTURE_SCORE,
+            ),
+            url_architecture: points(
+                url_architecture == Some(previous.architecture),
+                URL_ARCHITECTURE_SCORE,
+            ),
+            installer_type: points(new.r#type == previous.r#type, TYPE_SCORE),
+            nested_installer_type: points(
+                new.nested_installer_type == previous.nested_installer_type,
+                NESTED_TYPE_SCORE,
+            ),
+            scope: points(new.scope == previous.scope, SCOPE_SCORE),
+            url_similarity: scale(
+                url_similarity(&previous.url, &new.url),
+                URL_SIMILARITY_SCORE,
+            ),
+            product: scale(product_similarity(previous, new), PRODUCT_SCORE),
+            compatible: extension(&previous.url) == extension(&new.url),
+            same_architecture: new.architecture == previous.architecture
+                || url_architecture == Some(previous.architecture)
+                || [new.architecture, previous.architecture].contains(&Architecture::Neutral),
+        }
+    }
+
+    /// The sum of every signal, or 0 if the installers have different file extensions
+    pub const fn total(&self) -> u32 {
+        if self.compatible {
+            self.architecture
+                + self.url_architecture
+                + self.installer_type
+                + self.nested_installer_type
+                + self.scope
+                + self.url_similarity
+                + self.product
+        } else {
+            0
+        }
+    }
+
+    pub const fn is_confident(&self) -> bool {
+        self.total() >= MIN_CONFIDENT_SCORE
+    }
+
+    /// Whether the new installer could be an update of the previous installer at all
+    pub const fn is_plausible(&self) -> bool {
+        self.compatible && self.same_architecture
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.compatible {
+            return write!(f, "0 (different file type)");
+        }
+        let parts = [
+            ("architecture", self.architecture),
+            ("URL architecture", self.url_architecture),
+            ("type", self.installer_type),
+            ("nested type", self.nested_installer_type),
+            ("scope", self.scope),
+            ("URL", self.url_similarity),
+            ("product", self.product),
+        ];
+        write!(
+            f,
+            "{} ({})",
+            self.total(),
+            parts
+                .iter()
+                .filter(|(_, points)| *points > 0)
+                .map(|(name, points)| format!("{name} {points}"))
+                .join(", ")
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct InstallerMatch {
+    /// The 0-based position of the previous installer in the previous installer manifest
+    pub index: usize,
+    pub previous: Installer,
+    pub new: Installer,
+    pub score: Score,
+    pub overridden: bool,
+}
+
+#[derive(Debug)]
+pub struct MatchedInstallers {
+    /// Matches in the order of the previous installers
+    pub matches: Vec<InstallerMatch>,
+    /// New installers that no previous installer was matched with
+    pub unmatched: Vec<Installer>,
+    /// Previous installers without a plausible match, with their 0-based position in the previous
+    /// installer manifest
+    pub removed: Vec<(usize, Installer)>,
+}
+
+/// Matches each previous installer with a new installer.
+///
+/// The matching maximizes the total score so that every new installer is used at most once when
+/// there are at least as many new installers as previous installers, and every new installer is
+/// used at least once otherwise. Previous installers whose match has a different file extension or
+/// architecture are left unmatched so that they can be removed, and their new installers added.
+/// If that would leave no matches at all, the matches are kept when there is only one new
+/// installer and refused otherwise.
 pub fn match_installers(
     previous_installers: Vec<Installer>,
     new_installers: &[Installer],
-) -> HashMap<Installer, Installer> {
-    let found_architectures = new_installers
+    overrides: &[MatchOverride],
+) -> Result<MatchedInstallers, MatchError> {
+    let url_architectures = new_installers
         .iter()
         .filter_map(|installer| {
             let url = &installer.url;
@@ -15,80 +204,323 @@ pub fn match_installers(
         })
         .collect::<HashMap<_, _>>();
 
-    let found_scopes = new_installers
+    let scores = previous_installers
         .iter()
-        .filter_map(|installer| {
-            let url = &installer.url;
-            Scope::find_in(url.as_str()).map(|scope| (url, scope))
+        .map(|previous| {
+            new_installers
+                .iter()
+                .map(|new| Score::new(previous, new, url_architectures.get(&new.url).copied()))
+                .collect::<Vec<_>>()
         })
-        .collect::<HashMap<_, _>>();
+        .collect::<Vec<_>>();
+
+    let mut assignments = vec![None; previous_installers.len()];
+    let mut overridden = BTreeSet::new();
+    for MatchOverride { index, url } in overrides {
+        let row = index.get() - 1;
+        let Some(row_scores) = scores.get(row) else {
+            return Err(MatchError::InvalidIndex {
+                index: *index,
+                count: previous_installers.len(),
+            });
+        };
+        let column = new_installers
+            .iter()
+            .positions(|installer| installer.url == *url)
+            .max_by_key(|&column| row_scores[column].total())
+            .ok_or_else(|| MatchError::UnknownUrl {
+                index: *index,
+                url: url.clone(),
+            })?;
+        assignments[row] = Some(column);
+        overridden.insert(row);
+    }
 
-    previous_installers
+    let rows = (0..previous_installers.len())
+        .filter(|row| !overridden.contains(row))
+        .collect::<Vec<_>>();
+    let used = assignments
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<BTreeSet<_>>();
+    let mut columns = (0..new_installers.len())
+        .filter(|column| !used.contains(column))
+        .collect::<Vec<_>>();
+    if columns.is_empty() {
+        columns = (0..new_installers.len()).collect();
+    }
+    let total = |row: usize, column: usize| i64::from(scores[row][column].total());
+
+    if rows.len() <= columns.len() {
+        let assigned = assign(rows.len(), columns.len(), |r, c| total(rows[r], columns[c]));
+        for (r, c) in assigned.into_iter().enumerate() {
+            assignments[rows[r]] = Some(columns[c]);
+        }
+    } else if !columns.is_empty() {
+        // Give every new installer one previous installer, then the rest their best match
+        let assigned = assign(columns.len(), rows.len(), |c, r| total(rows[r], columns[c]));
+        for (c, r) in assigned.into_iter().enumerate() {
+            assignments[rows[r]] = Some(columns[c]);
+        }
+        for &row in &rows {
+            if assignments[row].is_none() {
+                assignments[row] = columns
+                    .iter()
+                    .copied()
+                    .filter(|&column| scores[row][column].is_plausible())
+                    .max_by_key(|&column| total(row, column));
+            }
+        }
+    }
+
+    let implausible = rows
+        .iter()
+        .copied()
+        .filter(|&row| assignments[row].is_none_or(|column| !scores[row][column].is_plausible()))
+        .collect::<Vec<_>>();
+    if !implausible.is_empty() && implausible.len() == previous_installers.len() {
+        if new_installers.len() > 1 {
+            return Err(MatchError::LowConfidence(
+                implausible
+                    .iter()
+                    .map(|row| format!("#{}", row + 1))
+                    .join(", "),
+            ));
+        }
+    } else {
+        for &row in &implausible {
+            assignments[row] = None;
+        }
+    }
+
+    let used = assignments
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<BTreeSet<_>>();
+    let (matches, removed) = previous_installers
         .into_iter()
-        .map(|previous_installer| {
-            let mut max_score = 0;
-            let mut best_match = None;
-
-            for new_installer in new_installers {
-                let installer_url = &new_installer.url;
-                let mut score = 0;
-                if new_installer.architecture == previous_installer.architecture {
-                    score += 1;
-                }
-                if found_architectures.get(installer_url) == Some(&previous_installer.architecture)
-                {
-                    score += 1;
-                }
-                if new_installer.r#type == previous_installer.r#type {
-                    score += 3;
-                }
-                if new_installer.nested_installer_type == previous_installer.nested_installer_type {
-                    score += 3;
+        .zip(assignments)
+        .enumerate()
+        .partition_map(|(index, (previous, column))| match column {
+            Some(column) => Either::Left(InstallerMatch {
+                index,
+                previous,
+                new: new_installers[column].clone(),
+                score: scores[index][column],
+                overridden: overridden.contains(&index),
+            }),
+            None => Either::Right((index, previous)),
+        });
+    let unmatched = new_installers
+        .iter()
+        .enumerate()
+        .filter(|(column, _)| !used.contains(column))
+        .map(|(_, installer)| installer.clone())
+        .collect();
+
+    Ok(MatchedInstallers {
+        matches,
+        unmatched,
+        removed,
+    })
+}
+
+const fn points(condition: bool, points: u32) -> u32 {
+    if condition { points } else { 0 }
+}
+
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "the similarity is between 0 and 1"
+)]
+fn scale(similarity: f64, max: u32) -> u32 {
+    (similarity * f64::from(max)).round() as u32
+}
+
+fn extension(url: &DecodedUrl) -> &str {
+    Utf8Path::new(url.as_str())
+        .extension()
+        .filter(|extension| VALID_FILE_EXTENSIONS.contains(extension))
+        .unwrap_or_default()
+}
+
+/// Splits the file name of a URL into lowercase words, leaving out version numbers
+fn url_tokens(url: &DecodedUrl) -> BTreeSet<String> {
+    let file_name = url
+        .path_segments()
+        .and_then(Iterator::last)
+        .unwrap_or_default();
+    file_name
+        .split(|char: char| !char.is_ascii_alphanumeric())
+        .map(str::to_ascii_lowercase)
+        .filter(|token| {
+            let digits = token.strip_prefix('v').unwrap_or(token);
+            !digits.is_empty() && !digits.bytes().all(|byte| byte.is_ascii_digit())
+        })
+        .collect()
+}
+
+/// The Jaccard similarity of the words in the file names of two URLs
+fn url_similarity(previous: &DecodedUrl, new: &DecodedUrl) -> f64 {
+    let previous = url_tokens(previous);
+    let new = url_tokens(new);
+    let union = previous.union(&new).count();
+    if union == 0 {
+        return 0.0;
+    }
+    let intersection = previous.intersection(&new).count();
+    #[expect(clippy::cast_precision_loss, reason = "token counts are small")]
+    let similarity = intersection as f64 / union as f64;
+    similarity
+}
+
+/// How likely two installers install the same product, from their product codes, package family
+/// names, upgrade codes and Apps and Features display names
+fn product_similarity(previous: &Installer, new: &Installer) -> f64 {
+    let same = |previous: Option<&str>, new: Option<&str>| {
+        previous
+            .zip(new)
+            .is_some_and(|(previous, new)| previous.eq_ignore_ascii_case(new))
+    };
+
+    if same(
+        previous.product_code.as_deref(),
+        new.product_code.as_deref(),
+    ) || previous
+        .package_family_name
+        .as_ref()
+        .is_some_and(|name| new.package_family_name.as_ref() == Some(name))
+    {
+        return 1.0;
+    }
+
+    previous
+        .apps_and_features_entries
+        .iter()
+        .cartesian_product(&new.apps_and_features_entries)
+        .map(|(previous, new)| {
+            if same(
+                previous.product_code.as_deref(),
+                new.product_code.as_deref(),
+            ) || same(
+                previous.upgrade_code.as_deref(),
+                new.upgrade_code.as_deref(),
+            ) {
+                1.0
+            } else {
+                previous
+                    .display_name
+                    .as_deref()
+                    .zip(new.display_name.as_deref())
+                    .map_or(0.0, |(previous, new)| {
+                        normalized_levenshtein(&previous.to_lowercase(), &new.to_lowercase())
+                    })
+            }
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Assigns each of `rows` to a distinct one of `columns` so that the total score is maximized,
+/// using the Hungarian algorithm. Requires `rows <= columns`.
+fn assign(rows: usize, columns: usize, score: impl Fn(usize, usize) -> i64) -> Vec<usize> {
+    debug_assert!(rows <= columns);
+    // Work with 1-based indices, where row and column 0 are sentinels
+    let mut row_potential = vec![0_i64; rows + 1];
+    let mut column_potential = vec![0_i64; columns + 1];
+    let mut column_row = vec![0_usize; columns + 1];
+    let mut way = vec![0_usize; columns + 1];
+
+    for row in 1..=rows {
+        column_row[0] = row;
+        let mut column = 0;
+        let mut min_slack = vec![i64::MAX; columns + 1];
+        let mut visited = vec![false; columns + 1];
+        loop {
+            visited[column] = true;
+            let current_row = column_row[column];
+            let mut delta = i64::MAX;
+            let mut next_column = 0;
+            for candidate in 1..=columns {
+                if visited[candidate] {
+                    continue;
                 }
-                if new_installer.scope == previous_installer.scope {
-                    score += 1;
+                // Minimize the negated score to maximize the score
+                let slack = -score(current_row - 1, candidate - 1)
+                    - row_potential[current_row]
+                    - column_potential[candidate];
+                if slack < min_slack[candidate] {
+                    min_slack[candidate] = slack;
+                    way[candidate] = column;
                 }
-                let new_extension = Utf8Path::new(new_installer.url.as_str())
-                    .extension()
-                    .filter(|extension| VALID_FILE_EXTENSIONS.contains(extension))
-                    .unwrap_or_default();
-                let previous_extension = Utf8Path::new(previous_installer.url.as_str())
-                    .extension()
-                    .filter(|extension| VALID_FILE_EXTENSIONS.contains(extension))
-                    .unwrap_or_default();
-                if new_extension != previous_extension {
-                    score = 0;
+                if min_slack[candidate] < delta {
+                    delta = min_slack[candidate];
+                    next_column = candidate;
                 }
-
-                let is_new_architecture = !found_architectures.is_empty()
-                    && !found_architectures.contains_key(installer_url);
-                let is_new_scope =
-                    !found_scopes.is_empty() && !found_scopes.contains_key(installer_url);
-
-                if score > max_score
-                    || (score == max_score && (is_new_architecture || is_new_scope))
-                    || best_match.is_none()
-                {
-                    max_score = score;
-                    best_match = Some(new_installer);
+            }
+            for candidate in 0..=columns {
+                if visited[candidate] {
+                    row_potential[column_row[candidate]] += delta;
+                    column_potential[candidate] -= delta;
+                } else {
+                    min_slack[candidate] -= delta;
                 }
             }
+            column = next_column;
+            if column_row[column] == 0 {
+                break;
+            }
+        }
+        while column != 0 {
+            let previous_column = way[column];
+            column_row[column] = column_row[previous_column];
+            column = previous_column;
+        }
+    }
 
-            (previous_installer, best_match.cloned().unwrap())
-        })
-        .collect::<HashMap<_, _>>()
+    let mut assignment = vec![0; rows];
+    for column in 1..=columns {
+        if column_row[column] != 0 {
+            assignment[column_row[column] - 1] = column - 1;
+        }
+    }
+    assignment
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{collections::HashMap, str::FromStr};
+    use std::str::FromStr;
 
     use winget_types::{
-        installer::{Architecture, Installer, Scope},
+        installer::{AppsAndFeaturesEntry, Architecture, Installer, InstallerType, Scope},
         url::DecodedUrl,
     };
 
-    use crate::match_installers::match_installers;
+    use super::{MatchError, MatchOverride, assign, match_installers};
+
+    fn installer(url: &str, architecture: Architecture) -> Installer {
+        Installer {
+            architecture,
+            r#type: Some(InstallerType::Exe),
+            url: DecodedUrl::from_str(url).unwrap(),
+            ..Installer::default()
+        }
+    }
+
+    fn matched_urls(
+        previous_installers: Vec<Installer>,
+        new_installers: &[Installer],
+        overrides: &[MatchOverride],
+    ) -> Vec<String> {
+        match_installers(previous_installers, new_installers, overrides)
+            .unwrap()
+            .matches
+            .into_iter()
+            .map(|installer_match| installer_match.new.url.to_string())
+            .collect()
+    }
 
     #[test]
     fn test_vscodium() {
@@ -122,9 +554,9 @@ mod tests {
         };
         let previous_installers = vec![
             installer_user_x86.clone(),
-            previous_machine_x86.clone(),
+            previous_machine_x86,
             installer_user_x64.clone(),
-            previous_machine_x64.clone(),
+            previous_machine_x64,
         ];
         let new_installers = vec![
             installer_user_x86.clone(),
@@ -132,15 +564,193 @@ mod tests {
             installer_user_x64.clone(),
             installer_x64.clone(),
         ];
-        let expected = HashMap::from([
-            (installer_user_x86.clone(), installer_user_x86),
-            (previous_machine_x86, installer_x86),
-            (installer_user_x64.clone(), installer_user_x64),
-            (previous_machine_x64, installer_x64),
-        ]);
+        let matched = match_installers(previous_installers, &new_installers, &[]).unwrap();
+        assert_eq!(
+            matched
+                .matches
+                .into_iter()
+                .map(|installer_match| installer_match.new)
+                .collect::<Vec<_>>(),
+            vec![
+                installer_user_x86,
+                installer_x86,
+                installer_user_x64,
+                installer_x64
+            ]
+        );
+        assert!(matched.unmatched.is_empty());
+    }
+
+    #[test]
+    fn one_to_one() {
+        // Both previous installers score highest against the same new installer on their own
+        let previous_installers = vec![
+            installer("https://example.com/app-1.0-x64.exe", Architecture::X64),
+            installer(
+                "https://example.com/app-1.0-portable.exe",
+                Architecture::X64,
+            ),
+        ];
+        let new_installers = vec![
+            installer("https://example.com/app-2.0-x64.exe", Architecture::X64),
+            installer(
+                "https://example.com/app-2.0-portable.exe",
+                Architecture::Neutral,
+            ),
+        ];
+        assert_eq!(
+            matched_urls(previous_installers, &new_installers, &[]),
+            [
+                "https://example.com/app-2.0-x64.exe",
+                "https://example.com/app-2.0-portable.exe"
+            ]
+        );
+    }
+
+    #[test]
+    fn product_code() {
+        let previous = |product_code: &str| Installer {
+            apps_and_features_entries: vec![
+                AppsAndFeaturesEntry::new().with_upgrade_code(product_code.to_owned()),
+            ],
+            ..installer("https://example.com/download", Architecture::X64)
+        };
+        let previous_installers = vec![previous("{A}"), previous("{B}")];
+        let new_installers = vec![
+            Installer {
+                url: DecodedUrl::from_str("https://example.com/download?id=2").unwrap(),
+                ..previous("{B}")
+            },
+            Installer {
+                url: DecodedUrl::from_str("https://example.com/download?id=1").unwrap(),
+                ..previous("{A}")
+            },
+        ];
         assert_eq!(
-            match_installers(previous_installers, &new_installers),
-            expected
+            matched_urls(previous_installers, &new_installers, &[]),
+            [
+                "https://example.com/download?id=1",
+                "https://example.com/download?id=2"
+            ]
         );
     }
+
+    #[test]
+    fn override_match() {
+        let installers = vec![
+            installer("https://example.com/user.exe", Architecture::X64),
+            installer("https://example.com/machine.exe", Architecture::X64),
+        ];
+        let overrides = ["1=https://example.com/machine.exe".parse().unwrap()];
+        let matched = match_installers(installers.clone(), &installers, &overrides).unwrap();
+        assert!(matched.matches[0].overridden);
+        assert_eq!(
+            matched.matches[0].new.url.as_str(),
+            "https://example.com/machine.exe"
+        );
+        assert_eq!(
+            matched.matches[1].new.url.as_str(),
+            "https://example.com/user.exe"
+        );
+    }
+
+    #[test]
+    fn invalid_override() {
+        let previous_installers = vec![installer("https://example.com/x64.exe", Architecture::X64)];
+        let new_installers = vec![installer("https://example.com/x64.exe", Architecture::X64)];
+        assert!(matches!(
+            match_installers(
+                previous_installers.clone(),
+                &new_installers,
+                &["2=https://example.com/x64.exe".parse().unwrap()]
+            ),
+            Err(MatchError::InvalidIndex { count: 1, .. })
+        ));
+        assert!(matches!(
+            match_installers(
+                previous_installers,
+                &new_installers,
+                &["1=https://example.com/other.exe".parse().unwrap()]
+            ),
+            Err(MatchError::UnknownUrl { .. })
+        ));
+    }
+
+    #[test]
+    fn changed_shape() {
+        let previous_installers = vec![
+            installer("https://example.com/setup-x86.exe", Architecture::X86),
+            installer("https://example.com/setup-x64.exe", Architecture::X64),
+            installer("https://example.com/setup-x64.msi", Architecture::X64),
+        ];
+        let new_installers = vec![
+            installer("https://example.com/setup-x64.exe", Architecture::X64),
+            installer("https://example.com/setup-arm64.exe", Architecture::Arm64),
+            installer("https://example.com/setup-x64.zip", Architecture::X64),
+        ];
+        let matched = match_installers(previous_installers, &new_installers, &[]).unwrap();
+        assert_eq!(matched.matches.len(), 1);
+        assert_eq!(
+            matched
+                .removed
+                .iter()
+                .map(|(index, _)| *index)
+                .collect::<Vec<_>>(),
+            [0, 2]
+        );
+        assert_eq!(
+            matched
+                .unmatched
+                .iter()
+                .map(|installer| installer.url.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "https://example.com/setup-arm64.exe",
+                "https://example.com/setup-x64.zip"
+            ]
+        );
+    }
+
+    #[test]
+    fn refuses_different_file_types() {
+        let previous_installers = vec![
+            installer("https://example.com/setup.exe", Architecture::X64),
+            installer("https://example.com/setup.msi", Architecture::X64),
+        ];
+        let new_installers = vec![
+            installer("https://example.com/setup.zip", Architecture::X64),
+            installer("https://example.com/setup.msix", Architecture::X64),
+        ];
+        assert_eq!(
+            match_installers(previous_installers, &new_installers, &[]).unwrap_err(),
+            MatchError::LowConfidence("#1, #2".to_owned())
+        );
+    }
+
+    #[test]
+    fn more_previous_installers() {
+        let previous_installers = vec![
+            Installer {
+                scope: Some(Scope::User),
+                ..installer("https://example.com/setup.exe", Architecture::X64)
+            },
+            Installer {
+                scope: Some(Scope::Machine),
+                ..installer("https://example.com/setup.exe", Architecture::X64)
+            },
+        ];
+        let new_installers = vec![installer(
+            "https://example.com/setup.exe",
+            Architecture::X64,
+        )];
+        let matched = match_installers(previous_installers, &new_installers, &[]).unwrap();
+        assert_eq!(matched.matches.len(), 2);
+        assert!(matched.unmatched.is_empty());
+    }
+
+    #[test]
+    fn hungarian_assignment() {
+        let scores = [[4, 1, 3], [2, 0, 5]];
+        assert_eq!(assign(2, 3, |row, column| scores[row][column]), [0, 2]);
+    }
 }
@@ -0,0 +1,61 @@
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+/// Serves one connection per response on a local port, returning the URL of `path` and each
+/// request that was received. A `None` response closes the connection without responding.
+pub fn mock_server(
+    path: &str,
+    responses: &[Option<&str>],
+) -> (String, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}{path}", listener.local_addr().unwrap());
+    let responses = responses
+        .iter()
+        .map(|response| response.map(str::to_owned))
+        .collect::<Vec<_>>();
+    let handle = thread::spawn(move || {
+        responses
+            .into_iter()
+            .map(|response| {
+                let (mut stream, _) = listener.accept().unwrap();
+                let request = read_request(&mut stream);
+                if let Some(response) = response {
+                    write!(stream, "{response}").unwrap();
+                }
+                request
+            })
+            .collect()
+    });
+    (url, handle)
+}
+
+/// Reads a request up to the end of its body, as given by its `Content-Length` header
+fn read_request(stream: &mut impl Read) -> String {
+    let mut request = Vec::new();
+    let mut buffer = [0; 1024];
+    loop {
+        let read = stream.read(&mut buffer).unwrap();
+        request.extend_from_slice(&buffer[..read]);
+        let text = String::from_utf8_lossy(&request);
+        if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+            let content_length = headers
+                .lines()
+                .find_map(|line| {
+                    line.to_ascii_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|length| length.trim().parse::<usize>().unwrap())
+                })
+                .unwrap_or_default();
+            if body.len() >= content_length {
+                break;
+            }
+        }
+        if read == 0 {
+            break;
+        }
+    }
+    String::from_utf8(request).unwrap()
+}
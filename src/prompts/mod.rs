@@ -7,6 +7,7 @@ use winget_types::installer::UpgradeBehavior;
 use crate::traits::Name;
 
 pub mod list;
+pub mod prompter;
 pub mod text;
 
 pub trait AllItems {
@@ -0,0 +1,203 @@
+use std::{
+    collections::BTreeSet,
+    fmt::{Debug, Display},
+    ops::BitOr,
+    str::FromStr,
+};
+
+use bitflags::Flags;
+use color_eyre::eyre::{Report, Result, eyre};
+use itertools::Itertools;
+
+use crate::{
+    prompts::{
+        AllItems, check_prompt,
+        list::{ListPrompt, list_prompt},
+        radio_prompt,
+        text::{TextPrompt, confirm_prompt, optional_prompt, required_prompt},
+    },
+    traits::Name,
+};
+
+/// Prompts for values that were not provided. When prompting is disabled, optional values are
+/// left empty and missing required values are collected so that they can all be reported at once.
+///
+/// When the values come from a package definition, optional values that it leaves out are treated
+/// as intentionally empty and are not prompted for.
+pub struct Prompter {
+    interactive: bool,
+    from_spec: bool,
+    missing: Vec<&'static str>,
+}
+
+impl Prompter {
+    pub const fn new(interactive: bool, from_spec: bool) -> Self {
+        Self {
+            interactive,
+            from_spec,
+            missing: Vec::new(),
+        }
+    }
+
+    /// Whether optional values that were not provided should be prompted for
+    const fn prompts_optional(&self) -> bool {
+        self.interactive && !self.from_spec
+    }
+
+    pub const fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+
+    /// Records a required value as missing if it was not provided and cannot be prompted for
+    pub fn expect<T: Name>(&mut self, value: Option<&T>) {
+        if value.is_none() && !self.interactive {
+            self.missing(T::NAME);
+        }
+    }
+
+    pub fn missing(&mut self, name: &'static str) {
+        if !self.missing.contains(&name) {
+            self.missing.push(name);
+        }
+    }
+
+    /// Returns the value, prompting for it if it was not provided. Returns [`None`] and records
+    /// the value as missing if prompting is disabled.
+    pub fn required<T>(&mut self, value: Option<T>) -> Result<Option<T>>
+    where
+        T: FromStr + TextPrompt,
+        <T as FromStr>::Err: ToString,
+    {
+        if value.is_some() || self.interactive {
+            Ok(Some(required_prompt(value)?))
+        } else {
+            self.missing(T::NAME);
+            Ok(None)
+        }
+    }
+
+    pub fn optional<T>(&self, value: Option<T>) -> Result<Option<T>>
+    where
+        T: FromStr + TextPrompt,
+        <T as FromStr>::Err: Display + Debug + Sync + Send + 'static,
+    {
+        if self.prompts_optional() {
+            Ok(optional_prompt(value)?)
+        } else {
+            Ok(value)
+        }
+    }
+
+    pub fn list<T>(&self, value: Option<BTreeSet<T>>) -> Result<BTreeSet<T>>
+    where
+        T: FromStr + ListPrompt + Ord,
+        <T as FromStr>::Err: Display,
+    {
+        match value {
+            Some(value) => Ok(value),
+            None if self.prompts_optional() => list_prompt(),
+            None => Ok(BTreeSet::new()),
+        }
+    }
+
+    pub fn check<T>(&self, value: Option<T>) -> Result<T>
+    where
+        T: Name + Flags + Display + BitOr<Output = T> + Copy,
+    {
+        match value {
+            Some(value) => Ok(value),
+            None if self.prompts_optional() => Ok(check_prompt()?),
+            None => Ok(T::empty()),
+        }
+    }
+
+    pub fn radio<T>(&self, value: Option<T>) -> Result<Option<T>>
+    where
+        T: Name + AllItems<Item = T> + Display,
+    {
+        match value {
+            Some(value) => Ok(Some(value)),
+            None if self.prompts_optional() => Ok(Some(radio_prompt()?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Asks a yes or no question, answering no if prompting is disabled
+    pub fn confirm(&self, message: &str) -> Result<bool> {
+        Ok(self.interactive && confirm_prompt(message)?)
+    }
+
+    /// Returns an error listing every missing required value
+    pub fn missing_error(&self) -> Report {
+        eyre!(
+            "Missing required values: {}. Provide them as arguments or in the file passed to --from",
+            self.missing.iter().join(", ")
+        )
+    }
+
+    /// Fails if any required values are missing
+    pub fn finish(&self) -> Result<()> {
+        if self.missing.is_empty() {
+            Ok(())
+        } else {
+            Err(self.missing_error())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use winget_types::{
+        PackageIdentifier,
+        installer::{InstallModes, UpgradeBehavior},
+        locale::{Author, Publisher, Tag},
+    };
+
+    use super::Prompter;
+
+    #[test]
+    fn collects_missing_values() {
+        let mut prompter = Prompter::new(false, false);
+        assert!(prompter.required::<Publisher>(None).unwrap().is_none());
+        prompter.expect::<PackageIdentifier>(None);
+        prompter.missing("Installer URLs");
+        assert!(prompter.list::<Tag>(None).unwrap().is_empty());
+        assert_eq!(
+            prompter.check::<InstallModes>(None).unwrap(),
+            InstallModes::empty()
+        );
+        assert!(prompter.radio::<UpgradeBehavior>(None).unwrap().is_none());
+        assert_eq!(
+            prompter.finish().unwrap_err().to_string(),
+            "Missing required values: Publisher, Package identifier, Installer URLs. Provide them as arguments or in the file passed to --from"
+        );
+    }
+
+    #[test]
+    fn uses_provided_values() {
+        let mut prompter = Prompter::new(false, false);
+        let tags = BTreeSet::from(["tag".parse::<Tag>().unwrap()]);
+        assert_eq!(prompter.list(Some(tags.clone())).unwrap(), tags);
+        assert!(
+            prompter
+                .required("Publisher".parse::<Publisher>().ok())
+                .unwrap()
+                .is_some()
+        );
+        assert!(prompter.finish().is_ok());
+    }
+
+    #[test]
+    fn skips_optional_values_left_out_of_spec() {
+        let prompter = Prompter::new(true, true);
+        assert!(prompter.optional::<Author>(None).unwrap().is_none());
+        assert!(prompter.list::<Tag>(None).unwrap().is_empty());
+        assert_eq!(
+            prompter.check::<InstallModes>(None).unwrap(),
+            InstallModes::empty()
+        );
+        assert!(prompter.radio::<UpgradeBehavior>(None).unwrap().is_none());
+    }
+}
@@ -13,18 +13,21 @@ use winget_types::{
         switches::{CustomSwitch, SilentSwitch, SilentWithProgressSwitch},
     },
     locale::{
-        Author, Copyright, Description, License, Moniker, PackageName, Publisher, ShortDescription,
+        Author, Copyright, Description, License, Moniker, PackageName, Publisher, ReleaseNotes,
+        ShortDescription,
     },
     url::{
         CopyrightUrl, LicenseUrl, PackageUrl, PublisherSupportUrl, PublisherUrl, ReleaseNotesUrl,
     },
 };
 
-use crate::{prompts::handle_inquire_error, traits::Name};
+use crate::{editor::Editor, prompts::handle_inquire_error, traits::Name};
 
 pub trait TextPrompt: Name {
     const HELP_MESSAGE: Option<&'static str> = None;
     const PLACEHOLDER: Option<&'static str> = None;
+    /// Whether the value can span multiple lines, so that drafts are reviewed in the editor
+    const MULTILINE: bool = false;
 }
 
 impl TextPrompt for PackageIdentifier {
@@ -45,6 +48,10 @@ impl TextPrompt for Description {}
 
 impl TextPrompt for ShortDescription {}
 
+impl TextPrompt for ReleaseNotes {
+    const MULTILINE: bool = true;
+}
+
 impl TextPrompt for LanguageTag {
     const HELP_MESSAGE: Option<&'static str> = Some("Example: en-US");
 }
@@ -102,31 +109,72 @@ where
     if let Some(value) = parameter {
         Ok(Some(value))
     } else {
-        let message = format!("{}:", <T as Name>::NAME);
-        let mut prompt = Text::new(&message).with_validator(|input: &str| {
-            if input.is_empty() {
-                Ok(Validation::Valid)
-            } else {
-                match input.parse::<T>() {
-                    Ok(_) => Ok(Validation::Valid),
-                    Err(error) => Ok(Validation::Invalid(error.into())),
-                }
-            }
-        });
-        if let Some(help_message) = T::HELP_MESSAGE {
-            prompt = prompt.with_help_message(help_message);
-        }
-        if let Some(placeholder) = T::PLACEHOLDER {
-            prompt = prompt.with_placeholder(placeholder);
-        }
-        let result = prompt.prompt().map_err(handle_inquire_error)?;
-        if result.is_empty() {
-            Ok(None)
+        optional_text_prompt(None)
+    }
+}
+
+/// Prompts for an optional value with a draft, such as a machine translation, already filled in so
+/// that it can be reviewed and edited. Multi-line values are opened in the editor, where clearing
+/// the draft leaves the value out.
+pub fn review_prompt<T>(draft: &str) -> InquireResult<Option<T>>
+where
+    T: FromStr + TextPrompt,
+    <T as FromStr>::Err: Display + Debug + Sync + Send + 'static,
+{
+    if !T::MULTILINE {
+        return optional_text_prompt(Some(draft));
+    }
+
+    let mut content = [(
+        format!("{} (machine translated draft)", T::NAME),
+        draft.to_owned(),
+    )];
+    Editor::new(&mut content).run()?;
+    let [(_, reviewed)] = content;
+    let reviewed = reviewed.replace("\r\n", "\n");
+    let reviewed = reviewed.trim();
+    if reviewed.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(reviewed.parse::<T>().map_err(|err| {
+            InquireError::from(CustomUserError::from(err.to_string()))
+        })?))
+    }
+}
+
+fn optional_text_prompt<T>(initial_value: Option<&str>) -> InquireResult<Option<T>>
+where
+    T: FromStr + TextPrompt,
+    <T as FromStr>::Err: Display + Debug + Sync + Send + 'static,
+{
+    let message = format!("{}:", <T as Name>::NAME);
+    let mut prompt = Text::new(&message).with_validator(|input: &str| {
+        if input.is_empty() {
+            Ok(Validation::Valid)
         } else {
-            Ok(Some(result.parse::<T>().map_err(|err| {
-                InquireError::from(CustomUserError::from(err.to_string()))
-            })?))
+            match input.parse::<T>() {
+                Ok(_) => Ok(Validation::Valid),
+                Err(error) => Ok(Validation::Invalid(error.into())),
+            }
         }
+    });
+    if let Some(initial_value) = initial_value {
+        prompt = prompt
+            .with_initial_value(initial_value)
+            .with_help_message("Machine translated draft. Edit it or clear it to leave it out");
+    } else if let Some(help_message) = T::HELP_MESSAGE {
+        prompt = prompt.with_help_message(help_message);
+    }
+    if let Some(placeholder) = T::PLACEHOLDER {
+        prompt = prompt.with_placeholder(placeholder);
+    }
+    let result = prompt.prompt().map_err(handle_inquire_error)?;
+    if result.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(result.parse::<T>().map_err(|err| {
+            InquireError::from(CustomUserError::from(err.to_string()))
+        })?))
     }
 }
 
@@ -7,7 +7,7 @@ use winget_types::{
     },
     locale::{
         Author, Copyright, Description, InstallationNotes, License, Moniker, PackageName,
-        Publisher, ShortDescription, Tag,
+        Publisher, ReleaseNotes, ShortDescription, Tag,
     },
     url::{
         CopyrightUrl, LicenseUrl, PackageUrl, PublisherSupportUrl, PublisherUrl, ReleaseNotesUrl,
@@ -53,6 +53,7 @@ impl_names!(
     Publisher => "Publisher",
     PublisherSupportUrl => "Publisher support URL",
     PublisherUrl => "Publisher URL",
+    ReleaseNotes => "Release notes",
     ReleaseNotesUrl => "Release notes URL",
     ShortDescription => "Short description",
     SilentSwitch => "Silent switch",
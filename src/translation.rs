@@ -0,0 +1,216 @@
+use std::{
+    env, io,
+    io::Write,
+    process::{Command, Stdio},
+    string::FromUtf8Error,
+};
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::task::JoinError;
+use url::Url;
+use winget_types::LanguageTag;
+
+use crate::credential::get_default_headers;
+
+const KOMAC_TRANSLATE_COMMAND: &str = "KOMAC_TRANSLATE_COMMAND";
+const KOMAC_TRANSLATE_URL: &str = "KOMAC_TRANSLATE_URL";
+const KOMAC_TRANSLATE_SOURCE: &str = "KOMAC_TRANSLATE_SOURCE";
+const KOMAC_TRANSLATE_TARGET: &str = "KOMAC_TRANSLATE_TARGET";
+
+#[derive(Debug, Error)]
+pub enum TranslationError {
+    #[error("Failed to run {KOMAC_TRANSLATE_COMMAND} `{command}`: {source}")]
+    Spawn { command: String, source: io::Error },
+    #[error("{KOMAC_TRANSLATE_COMMAND} `{command}` failed: {stderr}")]
+    CommandFailed { command: String, stderr: String },
+    #[error("The translation provider returned an empty translation")]
+    Empty,
+    #[error("{KOMAC_TRANSLATE_URL} is not a valid URL: {0}")]
+    InvalidUrl(#[from] url::ParseError),
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error("The translation was not valid UTF-8: {0}")]
+    Utf8(#[from] FromUtf8Error),
+    #[error(transparent)]
+    Join(#[from] JoinError),
+}
+
+/// A machine translation provider used to draft the text of additional locales.
+///
+/// A command is run through the shell with the text on stdin and the source and target locales in
+/// `KOMAC_TRANSLATE_SOURCE` and `KOMAC_TRANSLATE_TARGET`, and prints the translation. An HTTP
+/// endpoint receives a JSON object with `text`, `source` and `target` and responds with a JSON
+/// object with `text`.
+pub enum Translator {
+    Command(String),
+    Http { client: Client, url: Url },
+}
+
+#[derive(Serialize)]
+struct TranslationRequest<'a> {
+    text: &'a str,
+    source: &'a LanguageTag,
+    target: &'a LanguageTag,
+}
+
+#[derive(Deserialize)]
+struct TranslationResponse {
+    text: String,
+}
+
+impl Translator {
+    /// Returns the provider configured through `KOMAC_TRANSLATE_COMMAND` or `KOMAC_TRANSLATE_URL`
+    pub fn from_env() -> Result<Option<Self>, TranslationError> {
+        if let Ok(command) = env::var(KOMAC_TRANSLATE_COMMAND) {
+            Ok(Some(Self::Command(command)))
+        } else if let Ok(url) = env::var(KOMAC_TRANSLATE_URL) {
+            Ok(Some(Self::http(url.parse()?)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn http(url: Url) -> Result<Self, TranslationError> {
+        Ok(Self::Http {
+            client: Client::builder()
+                .default_headers(get_default_headers(None))
+                .build()?,
+            url,
+        })
+    }
+
+    pub async fn translate(
+        &self,
+        text: &str,
+        source: &LanguageTag,
+        target: &LanguageTag,
+    ) -> Result<String, TranslationError> {
+        let translation = match self {
+            Self::Command(command) => {
+                let (command, text) = (command.clone(), text.to_owned());
+                let (source, target) = (source.to_string(), target.to_string());
+                tokio::task::spawn_blocking(move || {
+                    run_translate_command(&command, &text, &source, &target)
+                })
+                .await??
+            }
+            Self::Http { client, url } => {
+                client
+                    .post(url.clone())
+                    .json(&TranslationRequest {
+                        text,
+                        source,
+                        target,
+                    })
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<TranslationResponse>()
+                    .await?
+                    .text
+            }
+        };
+
+        let translation = translation.trim();
+        if translation.is_empty() {
+            Err(TranslationError::Empty)
+        } else {
+            Ok(translation.to_owned())
+        }
+    }
+}
+
+fn run_translate_command(
+    command: &str,
+    text: &str,
+    source: &str,
+    target: &str,
+) -> Result<String, TranslationError> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let spawn_error = |source| TranslationError::Spawn {
+        command: command.to_owned(),
+        source,
+    };
+    let mut child = Command::new(shell)
+        .args([flag, command])
+        .env(KOMAC_TRANSLATE_SOURCE, source)
+        .env(KOMAC_TRANSLATE_TARGET, target)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(spawn_error)?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes()).map_err(spawn_error)?;
+    }
+    let output = child.wait_with_output().map_err(spawn_error)?;
+    if !output.status.success() {
+        return Err(TranslationError::CommandFailed {
+            command: command.to_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        });
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use winget_types::LanguageTag;
+
+    use super::Translator;
+    use crate::mock_server::mock_server;
+
+    #[tokio::test]
+    async fn http_translation() {
+        const RESPONSE: &str = r#"{"text":" Ein Paket "}"#;
+        let (url, requests) = mock_server(
+            "/translate",
+            &[Some(&format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{RESPONSE}",
+                RESPONSE.len()
+            ))],
+        );
+        let translator = Translator::http(url.parse().unwrap()).unwrap();
+
+        let translation = translator
+            .translate(
+                "A package",
+                &"en-US".parse::<LanguageTag>().unwrap(),
+                &"de-DE".parse::<LanguageTag>().unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(translation, "Ein Paket");
+        assert!(
+            requests.join().unwrap()[0]
+                .ends_with(r#"{"text":"A package","source":"en-US","target":"de-DE"}"#)
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn command_translation() {
+        let translator = Translator::Command(
+            r#"printf '%s>%s: ' "$KOMAC_TRANSLATE_SOURCE" "$KOMAC_TRANSLATE_TARGET"; cat"#
+                .to_owned(),
+        );
+
+        let translation = translator
+            .translate(
+                "A package",
+                &"en-US".parse::<LanguageTag>().unwrap(),
+                &"ja-JP".parse::<LanguageTag>().unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(translation, "en-US>ja-JP: A package");
+    }
+}
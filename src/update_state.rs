@@ -3,7 +3,7 @@ use std::{cmp::max, collections::BTreeSet};
 use derive_more::Display;
 use winget_types::PackageVersion;
 
-#[derive(Copy, Clone, Display)]
+#[derive(Copy, Clone, Display, Eq, PartialEq)]
 pub enum UpdateState {
     #[display("New package")]
     NewPackage,
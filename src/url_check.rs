@@ -0,0 +1,343 @@
+use std::time::Duration;
+
+use derive_more::Display;
+use futures_util::StreamExt;
+use reqwest::{
+    Client, RequestBuilder, Response, StatusCode,
+    header::{CONTENT_TYPE, RANGE},
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::time::sleep;
+use winget_types::{Sha256String, url::DecodedUrl};
+
+const RESOURCE_MISSING_STATUS_CODES: [StatusCode; 2] = [StatusCode::NOT_FOUND, StatusCode::GONE];
+
+/// Content types that servers respond with for error pages, where an installer is expected.
+/// `text/plain` is not included as some servers send it for binaries.
+const PAGE_CONTENT_TYPES: [&str; 2] = ["text/html", "application/xhtml+xml"];
+
+/// How many times a request is sent before a transport or server error is reported
+const ATTEMPTS: u8 = 2;
+
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Health {
+    #[display("dead")]
+    Dead,
+    #[display("changed")]
+    Changed,
+    #[display("healthy")]
+    Healthy,
+    /// The installer URLs could not be checked, such as after a timeout or a server error
+    #[display("unknown")]
+    Unknown,
+}
+
+/// Why an installer URL is considered dead
+#[derive(Clone, Debug, Display, PartialEq, Eq)]
+pub enum DeadReason {
+    #[display("`{_0}`")]
+    Status(StatusCode),
+    #[display("a `{_0}` page instead of an installer")]
+    Page(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UrlStatus {
+    Healthy,
+    Dead(DeadReason),
+    /// The URL serves a file with a different hash than the manifest's `InstallerSha256`
+    Changed {
+        actual: Sha256String,
+    },
+    /// The URL could not be checked, so it is neither considered dead nor healthy
+    Unknown(String),
+}
+
+impl UrlStatus {
+    pub const fn health(&self) -> Health {
+        match self {
+            Self::Healthy => Health::Healthy,
+            Self::Dead(_) => Health::Dead,
+            Self::Changed { .. } => Health::Changed,
+            Self::Unknown(_) => Health::Unknown,
+        }
+    }
+
+    pub fn detail(&self, expected: &Sha256String) -> Option<String> {
+        match self {
+            Self::Healthy => None,
+            Self::Dead(reason) => Some(format!("Returned {reason}")),
+            Self::Changed { actual } => Some(format!("Expected {expected} but found {actual}")),
+            Self::Unknown(error) => Some(format!("Could not be checked: {error}")),
+        }
+    }
+}
+
+/// The health of a version: dead if all of its installer URLs are dead, changed if any of them
+/// serve a different file, unknown if any of them could not be checked, and healthy otherwise.
+///
+/// A version is never dead while any of its installer URLs could not be checked.
+pub fn version_health<'a>(statuses: impl IntoIterator<Item = &'a UrlStatus>) -> Health {
+    let mut all_dead = true;
+    let mut any_changed = false;
+    let mut any_unknown = false;
+    let mut any = false;
+    for status in statuses {
+        any = true;
+        all_dead &= status.health() == Health::Dead;
+        any_changed |= status.health() == Health::Changed;
+        any_unknown |= status.health() == Health::Unknown;
+    }
+    if any && all_dead {
+        Health::Dead
+    } else if any_changed {
+        Health::Changed
+    } else if any_unknown {
+        Health::Unknown
+    } else {
+        Health::Healthy
+    }
+}
+
+/// Checks whether installer URLs still serve their installer.
+///
+/// By default, only the status code of a `HEAD` request is checked. A deep check falls back to a
+/// ranged `GET` request when `HEAD` is refused and treats error pages served with a successful
+/// status code as dead. Verifying the hash downloads the installer to compare it with
+/// `InstallerSha256`.
+pub struct UrlChecker {
+    client: Client,
+    deep: bool,
+    verify_hash: bool,
+}
+
+impl UrlChecker {
+    pub const fn new(client: Client, deep: bool, verify_hash: bool) -> Self {
+        Self {
+            client,
+            deep: deep || verify_hash,
+            verify_hash,
+        }
+    }
+
+    pub async fn check(&self, url: &DecodedUrl, sha_256: &Sha256String) -> UrlStatus {
+        if !self.deep {
+            return match self.send(|| self.client.head(url.as_str())).await {
+                Ok(response) => status_health(response.status()).unwrap_or(UrlStatus::Healthy),
+                Err(error) => UrlStatus::Unknown(error.to_string()),
+            };
+        }
+
+        let response = match self.send(|| self.client.head(url.as_str())).await {
+            Ok(response)
+                if response.status().is_success()
+                    || RESOURCE_MISSING_STATUS_CODES.contains(&response.status()) =>
+            {
+                response
+            }
+            // Some servers refuse HEAD requests, so request the first byte instead
+            _ => match self
+                .send(|| self.client.get(url.as_str()).header(RANGE, "bytes=0-0"))
+                .await
+            {
+                Ok(response) => response,
+                Err(error) => return UrlStatus::Unknown(error.to_string()),
+            },
+        };
+
+        let status = response.status();
+        if let Some(status) = status_health(status) {
+            return status;
+        }
+        if !status.is_success() {
+            return UrlStatus::Unknown(format!("the server returned `{status}`"));
+        }
+        if let Some(content_type) = page_content_type(&response) {
+            return UrlStatus::Dead(DeadReason::Page(content_type.to_owned()));
+        }
+        if self.verify_hash {
+            match self.download_hash(url).await {
+                Ok(actual) if actual != *sha_256 => return UrlStatus::Changed { actual },
+                Ok(_) => {}
+                Err(error) => return UrlStatus::Unknown(error.to_string()),
+            }
+        }
+        UrlStatus::Healthy
+    }
+
+    /// Sends a request, sending it again if it fails with a transport or server error
+    async fn send(&self, request: impl Fn() -> RequestBuilder) -> reqwest::Result<Response> {
+        let mut attempt = 1;
+        loop {
+            match request().send().await {
+                Ok(response) if !response.status().is_server_error() || attempt == ATTEMPTS => {
+                    return Ok(response);
+                }
+                Err(error) if attempt == ATTEMPTS => return Err(error),
+                _ => {}
+            }
+            attempt += 1;
+            sleep(RETRY_DELAY).await;
+        }
+    }
+
+    async fn download_hash(&self, url: &DecodedUrl) -> reqwest::Result<Sha256String> {
+        let mut hasher = Sha256::new();
+        let mut stream = self
+            .send(|| self.client.get(url.as_str()))
+            .await?
+            .error_for_status()?
+            .bytes_stream();
+        while let Some(chunk) = stream.next().await.transpose()? {
+            hasher.update(&chunk);
+        }
+        Ok(Sha256String::from_digest(&hasher.finalize()))
+    }
+}
+
+fn status_health(status: StatusCode) -> Option<UrlStatus> {
+    RESOURCE_MISSING_STATUS_CODES
+        .contains(&status)
+        .then_some(UrlStatus::Dead(DeadReason::Status(status)))
+}
+
+fn page_content_type(response: &Response) -> Option<&str> {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .filter(|content_type| is_page(content_type))
+}
+
+fn is_page(content_type: &str) -> bool {
+    let content_type = content_type.trim_start().to_ascii_lowercase();
+    PAGE_CONTENT_TYPES
+        .iter()
+        .any(|page| content_type.starts_with(page))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{Client, StatusCode};
+    use rstest::rstest;
+    use winget_types::Sha256String;
+
+    use super::{DeadReason, Health, UrlChecker, UrlStatus, is_page, version_health};
+    use crate::mock_server::mock_server;
+
+    #[rstest]
+    #[case("text/html; charset=utf-8", true)]
+    #[case("Text/HTML", true)]
+    #[case("application/xhtml+xml", true)]
+    #[case("text/plain", false)]
+    #[case("application/octet-stream", false)]
+    #[case("application/x-msdownload", false)]
+    fn page_content_types(#[case] content_type: &str, #[case] expected: bool) {
+        assert_eq!(is_page(content_type), expected);
+    }
+
+    #[test]
+    fn version_health_classification() {
+        let dead = UrlStatus::Dead(DeadReason::Status(StatusCode::NOT_FOUND));
+        let changed = UrlStatus::Changed {
+            actual: Sha256String::default(),
+        };
+        let unknown = UrlStatus::Unknown(String::from("timed out"));
+
+        assert_eq!(version_health([&dead, &dead]), Health::Dead);
+        assert_eq!(version_health([&dead, &changed]), Health::Changed);
+        assert_eq!(
+            version_health([&dead, &UrlStatus::Healthy]),
+            Health::Healthy
+        );
+        assert_eq!(version_health([&dead, &unknown]), Health::Unknown);
+        assert_eq!(version_health([&unknown]), Health::Unknown);
+        assert_eq!(version_health([]), Health::Healthy);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_ranged_get() {
+        let (url, requests) = mock_server(
+            "/setup.exe",
+            &[
+                Some(
+                    "HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                ),
+                Some(
+                    "HTTP/1.1 206 Partial Content\r\nContent-Type: application/octet-stream\r\nContent-Length: 1\r\nConnection: close\r\n\r\nM",
+                ),
+            ],
+        );
+        let checker = UrlChecker::new(Client::new(), true, false);
+
+        let status = checker
+            .check(&url.parse().unwrap(), &Sha256String::default())
+            .await;
+
+        assert_eq!(status, UrlStatus::Healthy);
+        assert_eq!(
+            requests
+                .join()
+                .unwrap()
+                .iter()
+                .filter_map(|request| request.lines().next())
+                .collect::<Vec<_>>(),
+            ["HEAD /setup.exe HTTP/1.1", "GET /setup.exe HTTP/1.1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn error_page_is_dead() {
+        let (url, _requests) = mock_server(
+            "/setup.exe",
+            &[Some(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            )],
+        );
+        let checker = UrlChecker::new(Client::new(), true, false);
+
+        assert_eq!(
+            checker
+                .check(&url.parse().unwrap(), &Sha256String::default())
+                .await,
+            UrlStatus::Dead(DeadReason::Page(String::from("text/html")))
+        );
+    }
+
+    #[tokio::test]
+    async fn transport_errors_are_retried_then_unknown() {
+        let (url, requests) = mock_server("/setup.exe", &[None, None]);
+        let checker = UrlChecker::new(Client::new(), false, false);
+
+        let status = checker
+            .check(&url.parse().unwrap(), &Sha256String::default())
+            .await;
+
+        assert!(matches!(status, UrlStatus::Unknown(_)), "{status:?}");
+        assert_eq!(status.health(), Health::Unknown);
+        assert_eq!(requests.join().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn transient_error_is_retried() {
+        let (url, _requests) = mock_server(
+            "/setup.exe",
+            &[
+                None,
+                Some("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+            ],
+        );
+        let checker = UrlChecker::new(Client::new(), false, false);
+
+        assert_eq!(
+            checker
+                .check(&url.parse().unwrap(), &Sha256String::default())
+                .await,
+            UrlStatus::Dead(DeadReason::Status(StatusCode::NOT_FOUND))
+        );
+    }
+}